@@ -9,11 +9,22 @@ pub const D9_I128: i128 = 1_000_000_000; // 1e9 (D9)
 pub const D18_U128: u128 = 1_000_000_000_000_000_000; // 1e18 (D18)
 
 pub const MAX_OUTCOMES: usize = 16;
+/// MAX_TX_ACCOUNT_LOCKS is the most accounts a single Solana transaction can reference.
+pub const MAX_TX_ACCOUNT_LOCKS: usize = 64;
 pub const OUTCOME_MINT_DECIMALS: u8 = 9;
 
 /// MAX_TVL_FEE is the maximum fee that can be set for the TVL fee, D18{1/year} -> 10% annually in D18.
 pub const MAX_TVL_FEE: u128 = 100_000_000_000_000_000;
 
+/// BPS_DENOMINATOR is 100% in basis points.
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// MAX_TRADE_FEE_BPS is the maximum buy or sell fee of a market, 10%.
+pub const MAX_TRADE_FEE_BPS: u16 = 10_00;
+
+/// MAX_CREATION_FEE is the maximum fee a market can charge its admin at init, 1 SOL.
+pub const MAX_CREATION_FEE: u64 = 1_000_000_000;
+
 /// DAY_IN_SECONDS is the number of se conds in a day.
 pub const DAY_IN_SECONDS: u64 = 86400;
 
@@ -24,12 +35,46 @@ pub const YEAR_IN_SECONDS: u64 = 365 * DAY_IN_SECONDS;
 pub const LN_2: u128 = 693_147_180_559_945_309;
 
 // Tunables (adjust or move to Market)
-pub const FEE_BPS: u64 = 10; // 0.1%
 pub const MAX_WITHDRAW_BPS: u64 = 50_00; // 50% of outcome reserve allowed per tx (in basis points; 10000 = 100%)
 
 pub const MIN_MARKET_DURATION: i64 = 1;
 
+/// Furthest in the future `resolve_at` may be when a market is created, 50 years. Anything past
+/// it is a garbage timestamp (or milliseconds) rather than a real resolution date, and keeps all
+/// timestamp arithmetic far from `i64` overflow.
+pub const MAX_RESOLVE_HORIZON: i64 = 50 * YEAR_IN_SECONDS as i64;
+
 /// 0.95 (95%) scaled to D9
 pub const OUTCOME_CONSENSUS_PERCENTAGE_THRESHOLD: u64 = 950_000_000;
 
+/// Seconds an outcome must stay priced at or above [`OUTCOME_CONSENSUS_PERCENTAGE_THRESHOLD`]
+/// before `resolve_by_consensus` resolves to it. A flash buy can push a price over the threshold
+/// within one transaction, but can't hold it there for an hour against every other trader.
+pub const CONSENSUS_DWELL_SECONDS: i64 = 60 * 60;
+
 pub const MINIMUM_OUTCOMES_PER_MARKET: u8 = 2;
+
+/// `winning_outcome` passed to `resolve_market` to void the market, see `MarketResolution::Void`.
+/// Never a real outcome index, since markets have at most `MAX_OUTCOMES` outcomes.
+pub const VOID_OUTCOME: u8 = u8::MAX;
+
+/// Price impact `Market::liquidity_depth` measures depth at, 1%
+pub const LIQUIDITY_DEPTH_IMPACT_BPS: u16 = 100;
+
+/// Trade size a new market must be able to quote, 0.01 SOL. `init_market` rejects a `scale`
+/// too small for the LMSR math to price a buy this size, rather than leaving the market to
+/// fail on its first trade.
+pub const REFERENCE_TRADE_LAMPORTS: u64 = 10_000_000;
+
+/// Markets per `RegistryPage`. A page is 4 KB, well under the 10 KB an account can be created
+/// with by CPI, and its rent (~0.03 SOL) is paid by whoever creates the page's first market.
+pub const REGISTRY_PAGE_CAPACITY: usize = 128;
+
+/// Maximum number of markets created by a single `batch_init_markets` call.
+///
+/// In practice the transaction size is the tighter limit: each market costs 2 + N account keys
+/// plus ~160 bytes of instruction data (the 128 byte label dominates, an oracle adds 32). A legacy transaction
+/// (1232 bytes) fits three binary markets; a versioned transaction with an address lookup
+/// table fits around six. Each outcome mint also costs two CPIs, so large batches of many-outcome
+/// markets should request a higher compute unit limit.
+pub const MAX_BATCH_MARKETS: usize = 8;
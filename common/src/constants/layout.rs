@@ -0,0 +1,76 @@
+/// One field of an account's byte layout, see [`MARKET_LAYOUT`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldLayout {
+    /// Field name, as in the Rust struct
+    pub name: &'static str,
+
+    /// Byte offset in the account data, including the 8 byte discriminator
+    pub offset: usize,
+
+    /// Size in bytes. Integers are little endian, arrays are `MAX_OUTCOMES` entries long.
+    pub size: usize,
+}
+
+impl FieldLayout {
+    const fn new(name: &'static str, offset: usize, size: usize) -> Self {
+        Self { name, offset, size }
+    }
+
+    /// Byte range of the field in the account data
+    pub const fn range(&self) -> std::ops::Range<usize> {
+        self.offset..self.offset + self.size
+    }
+}
+
+/// Size of a `Market` account in bytes, including the discriminator.
+pub const MARKET_ACCOUNT_SIZE: usize = 872;
+
+/// Byte layout of a `Market` account, in field order.
+///
+/// The account is `#[repr(C)]` zero copy, not Borsh: fields sit at fixed offsets with the
+/// alignment of their type and `_padding` aligns `consensus_since` to 8 bytes. Off-chain
+/// decoders (TypeScript, Python) should read fields at these offsets rather than deserializing
+/// the IDL type in order. The entries are contiguous and cover all [`MARKET_ACCOUNT_SIZE`] bytes.
+/// `fee_config` is itself `buy_bps` (u16), `sell_bps` (u16), 4 bytes of padding, `creation_fee`
+/// (u64) and `tvl_fee` (u64).
+/// The lmsr tests check every entry against the struct, so reordering a field fails CI instead of
+/// silently breaking clients.
+pub const MARKET_LAYOUT: &[FieldLayout] = &[
+    FieldLayout::new("discriminator", 0, 8),
+    FieldLayout::new("reserves", 8, 128),
+    FieldLayout::new("supplies", 136, 128),
+    FieldLayout::new("price_snapshot", 264, 128),
+    FieldLayout::new("price_cumulative", 392, 128),
+    FieldLayout::new("scale", 520, 8),
+    FieldLayout::new("initialized_at", 528, 8),
+    FieldLayout::new("resolve_at", 536, 8),
+    FieldLayout::new("trading_open_at", 544, 8),
+    FieldLayout::new("snapshot_at", 552, 8),
+    FieldLayout::new("price_cumulative_at", 560, 8),
+    FieldLayout::new("initial_liquidity", 568, 8),
+    FieldLayout::new("max_outcome_reserve", 576, 8),
+    FieldLayout::new("fee_config", 584, 24),
+    FieldLayout::new("accrued_protocol_fees", 608, 8),
+    FieldLayout::new("last_fee_accrual", 616, 8),
+    FieldLayout::new("admin", 624, 32),
+    FieldLayout::new("oracle", 656, 32),
+    FieldLayout::new("label", 688, 128),
+    FieldLayout::new("num_outcomes", 816, 1),
+    FieldLayout::new("bump", 817, 1),
+    FieldLayout::new("vault_bump", 818, 1),
+    FieldLayout::new("resolved", 819, 1),
+    FieldLayout::new("winning_outcome", 820, 1),
+    FieldLayout::new("refund_mode", 821, 1),
+    FieldLayout::new("mints_initialized", 822, 2),
+    FieldLayout::new("high_precision", 824, 1),
+    FieldLayout::new("in_progress", 825, 1),
+    FieldLayout::new("tokenized", 826, 1),
+    FieldLayout::new("consensus_outcome", 827, 1),
+    FieldLayout::new("outcome_decimals", 828, 1),
+    FieldLayout::new("_padding", 829, 3),
+    FieldLayout::new("consensus_since", 832, 8),
+    FieldLayout::new("accrued_rounding_surplus", 840, 8),
+    FieldLayout::new("fee_free_until", 848, 8),
+    FieldLayout::new("subsidy", 856, 8),
+    FieldLayout::new("trading_halt_before_resolve", 864, 8),
+];
@@ -1,5 +1,7 @@
 pub use common::*;
+pub use layout::*;
 pub use seeds::*;
 
 pub mod common;
+pub mod layout;
 pub mod seeds;
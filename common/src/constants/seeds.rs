@@ -9,3 +9,11 @@ pub const VAULT_SEED: &[u8] = b"vault";
 
 #[constant]
 pub const OUTCOME_MINT_SEED: &[u8] = b"mint";
+
+/// Seed to derive a `BettorPosition` PDA, with the market and owner
+#[constant]
+pub const POSITION_SEED: &[u8] = b"position";
+
+/// Seed to derive the `Registry` PDA, and with a page index each `RegistryPage`
+#[constant]
+pub const REGISTRY_SEED: &[u8] = b"registry";
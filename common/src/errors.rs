@@ -10,7 +10,7 @@ pub enum ErrorCode {
     #[msg("Too many outcomes")]
     TooManyOutcomes,
 
-    #[msg("Outcome is below two")]
+    #[msg("A market needs at least two outcomes")]
     NotEnoughOutcomes,
 
     #[msg("Account Not Signer")]
@@ -105,6 +105,135 @@ pub enum ErrorCode {
 
     #[msg("Outcome is not the winner")]
     OutcomeNotWinner,
+
+    #[msg("Buy would push the outcome reserve beyond the market cap")]
+    OutcomeReserveCapExceeded,
+
+    #[msg("Invalid market seed")]
+    InvalidMarketSeed,
+
+    #[msg("Invalid vault seed")]
+    InvalidVaultSeed,
+
+    #[msg("Invalid batch size")]
+    InvalidBatchSize,
+
+    #[msg("Slippage exceeded")]
+    SlippageExceeded,
+
+    #[msg("Prices were snapshotted less than a day ago")]
+    SnapshotTooSoon,
+
+    #[msg("No price snapshot has been taken")]
+    NoPriceSnapshot,
+
+    #[msg("Buy amount is too large for the market's liquidity parameter")]
+    BuyAmountOutOfRange,
+
+    #[msg("Market has no oracle")]
+    OracleNotSet,
+
+    #[msg("Oracle account does not match the market's oracle")]
+    InvalidOracle,
+
+    #[msg("Oracle account data is invalid")]
+    InvalidOracleData,
+
+    #[msg("TVL fee is above the maximum")]
+    InvalidTvlFee,
+
+    #[msg("Trading window is closed")]
+    TradingWindowClosed,
+
+    #[msg("Trading must open before the market resolves")]
+    InvalidTradingWindow,
+
+    #[msg("resolve_at must be a unix timestamp in the future")]
+    ResolveTimeInPast,
+
+    #[msg("Not every outcome mint has been initialized")]
+    MintsNotReady,
+
+    #[msg("Outcome mint is already initialized")]
+    MintAlreadyInitialized,
+
+    #[msg("Nothing to withdraw")]
+    NothingToWithdraw,
+
+    #[msg("Market is already in the middle of an instruction")]
+    ReentrancyDetected,
+
+    #[msg("TWAP window must end after it starts")]
+    InvalidTwapWindow,
+
+    #[msg("Outcome supply would exceed the safe range of the cost function")]
+    SupplyWouldSaturate,
+
+    #[msg("Outcome mint has not been initialized")]
+    OutcomeMintNotInitialized,
+
+    #[msg("A market with this label already exists")]
+    MarketLabelAlreadyExists,
+
+    #[msg("Liquidity parameter is too small to price a reference trade")]
+    ScaleTooSmall,
+
+    #[msg("Buy or sell fee exceeds the maximum")]
+    TradeFeeTooHigh,
+
+    #[msg("Creation fee exceeds the maximum")]
+    CreationFeeTooHigh,
+
+    #[msg("Market records positions instead of minting outcome tokens")]
+    MarketNotTokenized,
+
+    #[msg("Market mints outcome tokens instead of recording positions")]
+    MarketIsTokenized,
+
+    #[msg("Position holds fewer shares than requested")]
+    InsufficientPositionShares,
+
+    #[msg("Trade moves the price by more than the allowed impact")]
+    PriceImpactTooHigh,
+
+    #[msg("Registry page is not the PDA for the next registry entry")]
+    InvalidRegistryPage,
+
+    #[msg("Outcome supply is past the range the LMSR math can evaluate")]
+    SupplySaturated,
+
+    #[msg("Outcome has not held consensus for long enough")]
+    ConsensusDwellNotElapsed,
+
+    #[msg("resolve_at is not a plausible unix timestamp")]
+    InvalidResolveTime,
+
+    #[msg("The fee holiday must end by resolve_at")]
+    InvalidFeeHoliday,
+
+    #[msg("A new market must start with every supply and reserve at zero")]
+    MarketStateNotEmpty,
+
+    #[msg("Trading has not opened yet")]
+    MarketPaused,
+
+    #[msg("Outcome mint supply does not match the market's outcome supply")]
+    MintSupplyMismatch,
+
+    #[msg("Outcome decimals must be at most 9")]
+    InvalidOutcomeDecimals,
+
+    #[msg("Share amount is not a whole number of outcome token units")]
+    InvalidShareAmount,
+
+    #[msg("Logarithm of zero or a negative number is undefined")]
+    LogOfNonPositive,
+
+    #[msg("Exponent argument is outside the fixed-point exp domain")]
+    ExpArgumentOutOfRange,
+
+    #[msg("Cannot flip a position into the outcome it is already in")]
+    SameOutcome,
 }
 
 /// Check a condition and return an error if it is not met.
@@ -5,6 +5,8 @@
 //! * `constants` - Constants for the Vault ecosystem.
 //! * `errors` - Errors for the Vault ecosystem.
 //! * `utils` - Utility functions for the Vault ecosystem.
+#![allow(unexpected_cfgs)]
+
 pub mod constants;
 pub mod errors;
 pub mod utils;
@@ -1,8 +1,13 @@
-use crate::{check_condition, errors::ErrorCode};
+use crate::{
+    check_condition,
+    constants::{OUTCOME_MINT_SEED, VAULT_SEED},
+    errors::ErrorCode,
+};
 use anchor_lang::{
     prelude::*,
     solana_program::{program::invoke, program::invoke_signed},
 };
+use anchor_spl::associated_token::get_associated_token_address;
 use solana_system_interface::instruction::*;
 
 /// Helper function to validate the next account in an iterator.
@@ -159,3 +164,54 @@ pub fn init_pda_account_rent_if_needed<'info>(
     )?;
     Ok(true)
 }
+
+/// Check that `vault` is a market's vault: the `[VAULT_SEED, market, &[vault_bump]]` PDA, owned by
+/// the program that created it. The program moves lamports out of the vault directly, which only
+/// works on an account it owns, so a vault owned by anyone else is a substitute.
+///
+/// # Returns
+///
+/// * `Ok(())`: The account is the market's vault.
+/// * `Err(ErrorCode::InvalidAccountOwner)`: If the address doesn't derive from the market, or
+///   the account isn't owned by `program_id`.
+pub fn validate_vault(
+    vault: &AccountInfo,
+    market: &Pubkey,
+    vault_bump: u8,
+    program_id: &Pubkey,
+) -> Result<()> {
+    let expected =
+        Pubkey::create_program_address(&[VAULT_SEED, market.as_ref(), &[vault_bump]], program_id)
+            .map_err(|_| error!(ErrorCode::InvalidAccountOwner))?;
+    check_condition!(vault.key() == expected, InvalidAccountOwner);
+    check_condition!(vault.owner == program_id, InvalidAccountOwner);
+
+    Ok(())
+}
+
+/// Derive the outcome mint PDA of every outcome of a market, in outcome index order.
+///
+/// These are the `[OUTCOME_MINT_SEED, market, &[i]]` addresses `init_market` and
+/// `init_outcome_mint` create, for clients fetching balances or building instructions.
+pub fn outcome_mints(program_id: &Pubkey, market: &Pubkey, num_outcomes: u8) -> Vec<Pubkey> {
+    (0..num_outcomes)
+        .map(|i| {
+            Pubkey::find_program_address(&[OUTCOME_MINT_SEED, market.as_ref(), &[i]], program_id).0
+        })
+        .collect()
+}
+
+/// Derive `user`'s associated token account for every outcome mint of a market, in outcome index
+/// order, so a client can fetch all of a user's balances in one `getMultipleAccounts` call.
+/// Accounts the user never bought into simply don't exist yet.
+pub fn user_outcome_atas(
+    user: &Pubkey,
+    program_id: &Pubkey,
+    market: &Pubkey,
+    num_outcomes: u8,
+) -> Vec<Pubkey> {
+    outcome_mints(program_id, market, num_outcomes)
+        .iter()
+        .map(|mint| get_associated_token_address(user, mint))
+        .collect()
+}
@@ -0,0 +1,27 @@
+//! Yield estimates for liquidity providers, host only.
+//!
+//! Off-chain tooling that turns the fee and liquidity figures a market reports on-chain into
+//! an annual rate. Nothing on-chain depends on these, so they use f64.
+
+use crate::constants::YEAR_IN_SECONDS;
+
+/// Annualized yield of earning `accrued_fees` lamports on `total_liquidity` lamports over
+/// `elapsed_seconds`, compounded: `(1 + fees / liquidity)^(YEAR_IN_SECONDS / elapsed) - 1`.
+///
+/// Returns a fraction, e.g. `0.05` for 5%. Compounding assumes the fees earned so far are
+/// reinvested at the same rate, so short windows extrapolate a lot: treat the result as an
+/// estimate, not a promise. Returns 0 when there is no liquidity or no time has passed.
+///
+/// A market's `subsidy` is paid out to the winners rather than earned back, so leave it out of
+/// `total_liquidity`.
+pub fn lp_apy(accrued_fees: u64, total_liquidity: u64, elapsed_seconds: u64) -> f64 {
+    if total_liquidity == 0 || elapsed_seconds == 0 {
+        return 0.0;
+    }
+
+    let period_yield = accrued_fees as f64 / total_liquidity as f64;
+    let periods_per_year = YEAR_IN_SECONDS as f64 / elapsed_seconds as f64;
+
+    // ln_1p/exp_m1 keep precision for the tiny per-period yields fees usually are
+    (periods_per_year * period_yield.ln_1p()).exp_m1()
+}
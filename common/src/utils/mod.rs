@@ -1,7 +1,15 @@
 pub mod account_util;
+#[cfg(not(target_os = "solana"))]
+pub mod apy_util;
 pub mod math_util;
+pub mod payout_util;
+#[cfg(not(target_os = "solana"))]
+pub mod reference_lmsr;
 pub mod token_util;
 
 pub use account_util::*;
+#[cfg(not(target_os = "solana"))]
+pub use apy_util::*;
 pub use math_util::*;
+pub use payout_util::*;
 pub use token_util::*;
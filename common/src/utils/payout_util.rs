@@ -0,0 +1,65 @@
+use crate::constants::D9_U128;
+use crate::{check_condition, errors::ErrorCode};
+use anchor_lang::prelude::*;
+
+/// How winning shares are paid out when a market resolves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayoutMode {
+    /// Each winning share redeems for exactly one unit, i.e. 1e9 shares pay 1e9 lamports.
+    Unit,
+    /// Winners split the pot pro rata, so each winning share redeems for `pot / winning_supply`.
+    /// This is how `lmsr` markets redeem, where the pot is the vault.
+    Proportional {
+        /// Lamports shared between the winners
+        pot: u64,
+        /// Total shares of the winning outcome, scaled by 1e9
+        winning_supply: u64,
+    },
+}
+
+impl PayoutMode {
+    /// Lamports paid per whole (1e9) winning share, scaled by 1e9.
+    pub fn payout_per_share(&self) -> Result<u64> {
+        match *self {
+            PayoutMode::Unit => Ok(D9_U128 as u64),
+            PayoutMode::Proportional {
+                pot,
+                winning_supply,
+            } => {
+                check_condition!(winning_supply > 0, SupplyIsZero);
+
+                let payout = (pot as u128)
+                    .checked_mul(D9_U128)
+                    .ok_or(ErrorCode::MathOverflow)?
+                    / winning_supply as u128;
+
+                Ok(u64::try_from(payout).map_err(|_| ErrorCode::MathOverflow)?)
+            }
+        }
+    }
+}
+
+/// Probability (scaled by 1e9) that an outcome must have for a position bought at
+/// `average_fill_price` (lamports per whole share, scaled by 1e9) to break even in expectation.
+///
+/// Expected value is `p * payout_per_share - average_fill_price`, so the break-even point is
+/// `average_fill_price / payout_per_share`. With a unit payout that is the fill price itself.
+///
+/// A result above 1e9 means the position can't break even even if the outcome wins.
+///
+/// # Arguments
+/// * `average_fill_price` - Average lamports paid per whole share, scaled by 1e9
+/// * `payout_mode` - How the winning outcome pays out
+///
+/// Returns the break-even probability, scaled by 1e9
+pub fn break_even_probability(average_fill_price: u64, payout_mode: PayoutMode) -> Result<u64> {
+    let payout_per_share = payout_mode.payout_per_share()?;
+    check_condition!(payout_per_share > 0, MathOverflow);
+
+    let probability = (average_fill_price as u128)
+        .checked_mul(D9_U128)
+        .ok_or(ErrorCode::MathOverflow)?
+        / payout_per_share as u128;
+
+    Ok(u64::try_from(probability).map_err(|_| ErrorCode::MathOverflow)?)
+}
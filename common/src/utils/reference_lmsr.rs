@@ -0,0 +1,49 @@
+//! Reference LMSR math in f64, host only.
+//!
+//! This is the spec the on-chain fixed-point math is checked against: the same formulas,
+//! evaluated in floating point without the fixed-point exp/ln approximations. Tests use it to
+//! bound the on-chain error, and clients can use it for quotes that don't need to match the
+//! program to the lamport. Inputs and outputs use the on-chain units: supplies and shares in
+//! 1e9 units per whole share, `scale` (`b`) and costs in lamports, prices scaled by 1e9.
+//!
+//! Every sum of exponentials is shifted by the largest term, so supplies far past the on-chain
+//! `MAX_EXP_ARG * b` limit still evaluate without overflow. Fees are not included.
+
+/// `Σ exp(q_i / b - m)` and the shift `m = max(q_i / b)`
+fn shifted_sum(supplies: &[u64], scale: u64) -> (f64, f64) {
+    let b = scale as f64;
+    let max = supplies
+        .iter()
+        .map(|&q| q as f64 / b)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let sum = supplies.iter().map(|&q| (q as f64 / b - max).exp()).sum();
+
+    (sum, max)
+}
+
+/// LMSR cost function `b * ln(Σ exp(q_i / b))`, in lamports
+pub fn cost(supplies: &[u64], scale: u64) -> f64 {
+    let (sum, max) = shifted_sum(supplies, scale);
+
+    scale as f64 * (max + sum.ln())
+}
+
+/// Price of an outcome, `exp(q_i / b) / Σ exp(q_j / b)`, scaled by 1e9
+pub fn price(supplies: &[u64], scale: u64, outcome_index: usize) -> f64 {
+    let (sum, max) = shifted_sum(supplies, scale);
+    let term = (supplies[outcome_index] as f64 / scale as f64 - max).exp();
+
+    term / sum * 1e9
+}
+
+/// Shares minted by spending `amount_in` lamports on an outcome, the `Δq` with
+/// `cost(q + Δq * e_i) - cost(q) = amount_in`:
+///
+/// `Δq = b * ln(Σ exp((q_j - q_i) / b) * (exp(amount_in / b) - 1) + 1)`
+pub fn buy_shares(supplies: &[u64], scale: u64, outcome_index: usize, amount_in: u64) -> f64 {
+    let b = scale as f64;
+    let q_i = supplies[outcome_index] as f64;
+    let ratio: f64 = supplies.iter().map(|&q| ((q as f64 - q_i) / b).exp()).sum();
+
+    b * (ratio * (amount_in as f64 / b).exp_m1()).ln_1p()
+}
@@ -0,0 +1,141 @@
+//! Deterministic walk through a market's lifecycle using only the in-memory LMSR math.
+//! No Solana runtime is involved, so this runs anywhere with `cargo run --example simulate_market`.
+//!
+//! The script:
+//! 1. Creates a binary market with b = 1 SOL
+//! 2. Runs a fixed sequence of buys and sells, printing prices and cost after each step
+//! 3. Resolves to an outcome and pays out the winners pro rata from the vault
+use lmsr::state::Market;
+use lmsr::types::FixedSizeString;
+
+const LAMPORTS_PER_SOL: f64 = 1e9;
+/// Every trade happens at this time, with no fee holiday in effect
+const NOW: i64 = 0;
+
+enum Trade {
+    Buy {
+        trader: usize,
+        outcome: usize,
+        lamports: u64,
+    },
+    Sell {
+        trader: usize,
+        outcome: usize,
+        shares: u64,
+    },
+}
+
+fn print_state(market: &Market, vault: u64) {
+    for i in 0..market.num_outcomes as usize {
+        let price = market.price(i as u8).unwrap();
+        println!(
+            "    outcome {}: price {:.4} supply {}",
+            i,
+            price as f64 / 1e9,
+            market.supplies[i]
+        );
+    }
+    println!(
+        "    cost {:.6} SOL, vault {:.6} SOL",
+        market.cost().unwrap() as f64 / LAMPORTS_PER_SOL,
+        vault as f64 / LAMPORTS_PER_SOL
+    );
+}
+
+fn main() {
+    let traders = ["alice", "bob"];
+
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        resolve_at: 1_000,
+        label: FixedSizeString::new("will_it_rain"),
+        ..Default::default()
+    };
+
+    // Shares held per trader per outcome, and the lamports held by the vault
+    let mut holdings = [[0u64; 2]; 2];
+    let mut vault: u64 = 0;
+
+    println!("=== Initial state ===");
+    print_state(&market, vault);
+
+    let script = [
+        Trade::Buy {
+            trader: 0,
+            outcome: 0,
+            lamports: 1_000_000_000,
+        },
+        Trade::Buy {
+            trader: 1,
+            outcome: 1,
+            lamports: 2_000_000_000,
+        },
+        Trade::Buy {
+            trader: 0,
+            outcome: 0,
+            lamports: 2_000_000_000,
+        },
+        Trade::Buy {
+            trader: 1,
+            outcome: 0,
+            lamports: 500_000_000,
+        },
+        Trade::Sell {
+            trader: 1,
+            outcome: 1,
+            shares: 1_000_000_000,
+        },
+    ];
+
+    for trade in script {
+        match trade {
+            Trade::Buy {
+                trader,
+                outcome,
+                lamports,
+            } => {
+                let shares = market.buy_shares(outcome as u8, lamports, NOW).unwrap();
+                holdings[trader][outcome] += shares;
+                vault += lamports;
+                println!(
+                    "=== {} buys outcome {} for {} lamports -> {} shares ===",
+                    traders[trader], outcome, lamports, shares
+                );
+            }
+            Trade::Sell {
+                trader,
+                outcome,
+                shares,
+            } => {
+                // Selling pays out the drop in the LMSR cost function
+                let cost_before = market.cost().unwrap();
+                market.supplies[outcome] -= shares;
+                let payout = cost_before - market.cost().unwrap();
+
+                holdings[trader][outcome] -= shares;
+                vault -= payout;
+                println!(
+                    "=== {} sells {} shares of outcome {} -> {} lamports ===",
+                    traders[trader], shares, outcome, payout
+                );
+            }
+        }
+        print_state(&market, vault);
+    }
+
+    // Resolve: every winning share is redeemed pro rata against the vault
+    let winning_outcome = 0;
+    let winning_supply = market.supplies[winning_outcome];
+    println!("=== Resolved to outcome {} ===", winning_outcome);
+    for (trader, name) in traders.iter().enumerate() {
+        let shares = holdings[trader][winning_outcome];
+        let payout = (vault as u128 * shares as u128 / winning_supply as u128) as u64;
+        println!(
+            "    {} redeems {} shares for {:.6} SOL",
+            name,
+            shares,
+            payout as f64 / LAMPORTS_PER_SOL
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! Off-chain helpers for indexers, enabled with the `client` feature.
+
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+use crate::state::Market;
+
+/// A `getProgramAccounts` filter, mirroring `solana_rpc_client_api::filter::RpcFilterType`
+/// without pulling the RPC client into the program's dependencies.
+///
+/// - `DataSize(n)` maps to `RpcFilterType::DataSize(n)`
+/// - `Memcmp { offset, bytes }` maps to `RpcFilterType::Memcmp(Memcmp::new_raw_bytes(offset, bytes))`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarketFilter {
+    DataSize(u64),
+    Memcmp { offset: usize, bytes: Vec<u8> },
+}
+
+impl MarketFilter {
+    /// Whether `data` (a full account, discriminator included) passes this filter.
+    pub fn matches(&self, data: &[u8]) -> bool {
+        match self {
+            MarketFilter::DataSize(size) => data.len() as u64 == *size,
+            MarketFilter::Memcmp { offset, bytes } => data
+                .get(*offset..*offset + bytes.len())
+                .is_some_and(|slice| slice == bytes.as_slice()),
+        }
+    }
+}
+
+/// Builder for the filter set matching [`Market`] accounts.
+#[derive(Debug, Clone)]
+pub struct MarketFilters {
+    filters: Vec<MarketFilter>,
+}
+
+impl Default for MarketFilters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarketFilters {
+    /// Every [`Market`] account of the program.
+    pub fn new() -> Self {
+        Self {
+            filters: vec![
+                MarketFilter::DataSize(Market::SIZE as u64),
+                MarketFilter::Memcmp {
+                    offset: 0,
+                    bytes: Market::DISCRIMINATOR.to_vec(),
+                },
+            ],
+        }
+    }
+
+    /// Only markets administered by `admin`.
+    pub fn admin(mut self, admin: &Pubkey) -> Self {
+        self.filters.push(MarketFilter::Memcmp {
+            offset: Market::ADMIN_OFFSET,
+            bytes: admin.to_bytes().to_vec(),
+        });
+        self
+    }
+
+    /// Only markets that have not been resolved yet.
+    pub fn unresolved(mut self) -> Self {
+        self.filters.push(MarketFilter::Memcmp {
+            offset: Market::RESOLVED_OFFSET,
+            bytes: vec![0],
+        });
+        self
+    }
+
+    pub fn build(self) -> Vec<MarketFilter> {
+        self.filters
+    }
+}
+
+/// JSON form of a [`Market`] for indexers and analytics, with the derived `prices` and `cost`
+/// alongside the stored fields.
+///
+/// The zero copy account can't derive serde, so this is a separate DTO. Per-outcome arrays are
+/// cut to `num_outcomes`, pubkeys are base58, the label is a string and the u8 flags are bools.
+/// Padding is left out.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct MarketJson {
+    pub label: String,
+    pub admin: String,
+    pub oracle: String,
+    pub num_outcomes: u8,
+    pub scale: u64,
+    pub high_precision: bool,
+    pub tokenized: bool,
+
+    pub reserves: Vec<u64>,
+    pub supplies: Vec<u64>,
+    pub price_snapshot: Vec<u64>,
+    pub price_cumulative: Vec<u64>,
+
+    pub initialized_at: u64,
+    pub resolve_at: i64,
+    pub trading_open_at: i64,
+    pub trading_halt_before_resolve: i64,
+    pub snapshot_at: i64,
+    pub price_cumulative_at: i64,
+    pub last_fee_accrual: i64,
+
+    pub initial_liquidity: u64,
+    pub max_outcome_reserve: u64,
+    pub fee_config: FeeConfigJson,
+    pub fee_free_until: i64,
+    pub accrued_protocol_fees: u64,
+    pub accrued_rounding_surplus: u64,
+    pub subsidy: u64,
+
+    pub resolved: bool,
+    pub winning_outcome: u8,
+    pub refund_mode: bool,
+    pub mints_initialized: u16,
+    pub in_progress: bool,
+    pub consensus_outcome: u8,
+    pub outcome_decimals: u8,
+    pub consensus_since: i64,
+    pub bump: u8,
+    pub vault_bump: u8,
+
+    /// [`Market::price`] of each outcome, scaled by 1e9
+    pub prices: Vec<u64>,
+
+    /// [`Market::cost`] in lamports
+    pub cost: u64,
+}
+
+/// JSON form of [`crate::types::FeeConfig`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct FeeConfigJson {
+    pub buy_bps: u16,
+    pub sell_bps: u16,
+    pub creation_fee: u64,
+    pub tvl_fee: u64,
+}
+
+impl MarketJson {
+    /// Capture a market's fields and its current prices and cost.
+    pub fn from_market(market: &Market) -> Result<Self> {
+        market.validate_num_outcomes()?;
+        let n = market.num_outcomes as usize;
+
+        Ok(Self {
+            label: String::from_utf8_lossy(market.label.trimmed()).into_owned(),
+            admin: market.admin.to_string(),
+            oracle: market.oracle.to_string(),
+            num_outcomes: market.num_outcomes,
+            scale: market.scale,
+            high_precision: market.high_precision == 1,
+            tokenized: market.tokenized == 1,
+            reserves: market.reserves[..n].to_vec(),
+            supplies: market.supplies[..n].to_vec(),
+            price_snapshot: market.price_snapshot[..n].to_vec(),
+            price_cumulative: market.price_cumulative[..n].to_vec(),
+            initialized_at: market.initialized_at,
+            resolve_at: market.resolve_at,
+            trading_open_at: market.trading_open_at,
+            trading_halt_before_resolve: market.trading_halt_before_resolve,
+            snapshot_at: market.snapshot_at,
+            price_cumulative_at: market.price_cumulative_at,
+            last_fee_accrual: market.last_fee_accrual,
+            initial_liquidity: market.initial_liquidity,
+            max_outcome_reserve: market.max_outcome_reserve,
+            fee_config: FeeConfigJson {
+                buy_bps: market.fee_config.buy_bps,
+                sell_bps: market.fee_config.sell_bps,
+                creation_fee: market.fee_config.creation_fee,
+                tvl_fee: market.fee_config.tvl_fee,
+            },
+            fee_free_until: market.fee_free_until,
+            accrued_protocol_fees: market.accrued_protocol_fees,
+            accrued_rounding_surplus: market.accrued_rounding_surplus,
+            subsidy: market.subsidy,
+            resolved: market.resolved == 1,
+            winning_outcome: market.winning_outcome,
+            refund_mode: market.refund_mode == 1,
+            mints_initialized: market.mints_initialized,
+            in_progress: market.in_progress == 1,
+            consensus_outcome: market.consensus_outcome,
+            outcome_decimals: market.mint_decimals(),
+            consensus_since: market.consensus_since,
+            bump: market.bump,
+            vault_bump: market.vault_bump,
+            prices: (0..market.num_outcomes)
+                .map(|i| market.price(i))
+                .collect::<Result<_>>()?,
+            cost: market.cost()?,
+        })
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
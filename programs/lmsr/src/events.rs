@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+
+/// Emitted by `buy_shares` for every buy, in the transaction logs.
+#[event]
+pub struct SharesBought {
+    pub market: Pubkey,
+    pub buyer: Pubkey,
+    pub outcome_index: u8,
+
+    /// Lamports paid, including the buy fee
+    pub amount_in: u64,
+    pub shares_out: u64,
+
+    /// Caller-chosen id passed to `buy_shares`, echoed so clients can match log entries to the
+    /// orders they sent and spot a resubmitted one. Not checked by the program.
+    pub client_order_id: Option<u64>,
+
+    /// Slot the buy executed in
+    pub slot: u64,
+}
+
+/// Emitted by `redeem_position` for every redemption, in the transaction logs.
+#[event]
+pub struct PositionRedeemed {
+    pub market: Pubkey,
+    pub holder: Pubkey,
+    pub outcome_index: u8,
+    pub shares: u64,
+
+    /// Lamports paid out for the shares
+    pub amount_out: u64,
+
+    /// Part of the position's `cost_basis` the shares took with them, what was paid for them
+    pub cost_basis: u64,
+
+    /// Realized profit or loss, `amount_out - cost_basis`
+    pub pnl: i64,
+
+    /// Slot the redemption executed in
+    pub slot: u64,
+}
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Market;
+use common::constants::{MARKET_SEED, VAULT_SEED};
+use common::utils::validate_vault;
+
+#[derive(Accounts)]
+pub struct AccrueTvlFee<'info> {
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, &market.load()?.label.as_bytes()],
+        bump = market.load()?.bump,
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    /// CHECK: Check PDA. Account with no data that stores lamports for the [`Market`] as its `reserves`
+    #[account(
+        seeds = [VAULT_SEED, market.key().as_ref()],
+        bump = market.load()?.vault_bump,
+    )]
+    pub market_vault: UncheckedAccount<'info>,
+}
+
+/// Permissionless crank that moves the TVL fee since the last accrual into `accrued_protocol_fees`.
+/// See [`Market::accrue_tvl_fee`] for the bounds on the fee.
+///
+/// Returns the lamports accrued.
+pub fn accrue_tvl_fee(ctx: Context<AccrueTvlFee>) -> Result<u64> {
+    validate_vault(
+        &ctx.accounts.market_vault,
+        &ctx.accounts.market.key(),
+        ctx.accounts.market.load()?.vault_bump,
+        ctx.program_id,
+    )?;
+
+    let vault = ctx.accounts.market_vault.to_account_info();
+    let vault_lamports = vault
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(vault.data_len()));
+
+    let mut market = ctx.accounts.market.load_mut()?;
+    market.validate_num_outcomes()?;
+
+    let now = Clock::get()?.unix_timestamp;
+    market.accrue_tvl_fee(vault_lamports, now)
+}
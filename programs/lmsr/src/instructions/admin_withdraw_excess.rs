@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Market;
+use common::check_condition;
+use common::constants::{MARKET_SEED, VAULT_SEED};
+use common::errors::ErrorCode;
+use common::utils::validate_vault;
+
+#[derive(Accounts)]
+pub struct AdminWithdrawExcess<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one = admin,
+        seeds = [MARKET_SEED, &market.load()?.label.as_bytes()],
+        bump = market.load()?.bump,
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    /// CHECK: Check PDA. Account with no data that stores lamports for the [`Market`] as its `reserves`
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, market.key().as_ref()],
+        bump = market.load()?.vault_bump,
+    )]
+    pub market_vault: UncheckedAccount<'info>,
+}
+
+/// Move the lamports no trader can claim (e.g. SOL sent straight to the vault) to the admin.
+/// See [`Market::withdrawable_excess`].
+///
+/// Returns the lamports withdrawn.
+pub fn admin_withdraw_excess(ctx: Context<AdminWithdrawExcess>) -> Result<u64> {
+    validate_vault(
+        &ctx.accounts.market_vault,
+        &ctx.accounts.market.key(),
+        ctx.accounts.market.load()?.vault_bump,
+        ctx.program_id,
+    )?;
+
+    let vault = ctx.accounts.market_vault.to_account_info();
+    let vault_lamports = vault
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(vault.data_len()));
+
+    let excess = {
+        let market = ctx.accounts.market.load()?;
+        market.validate_num_outcomes()?;
+        market.withdrawable_excess(vault_lamports)?
+    };
+    check_condition!(excess > 0, NothingToWithdraw);
+
+    // The vault is owned by this program, so lamports can be moved without a CPI
+    **vault.try_borrow_mut_lamports()? = vault
+        .lamports()
+        .checked_sub(excess)
+        .ok_or(error!(ErrorCode::InsufficientVaultFunds))?;
+    let admin = ctx.accounts.admin.to_account_info();
+    **admin.try_borrow_mut_lamports()? = admin
+        .lamports()
+        .checked_add(excess)
+        .ok_or(error!(ErrorCode::MathOverflow))?;
+
+    Ok(excess)
+}
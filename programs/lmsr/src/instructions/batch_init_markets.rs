@@ -0,0 +1,132 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Token;
+
+use crate::instructions::{
+    create_outcome_mints, ensure_label_unused, fund_vault, initialize_market,
+};
+use crate::state::Market;
+use crate::types::InitMarketParams;
+use common::constants::{MARKET_SEED, MAX_BATCH_MARKETS, VAULT_SEED};
+use common::utils::init_pda_account_rent;
+use common::{check_condition, errors::ErrorCode};
+
+#[derive(Accounts)]
+pub struct BatchInitMarkets<'info> {
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    pub token_program: Program<'info, Token>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+/// Create every market in `params` in one transaction. Solana transactions are atomic, so if
+/// any market fails to initialize none of them are created.
+///
+/// Remaining accounts, for each market in `params` order:
+/// * `market` - PDA of [`MARKET_SEED`] and the label hash (writable)
+/// * `market_vault` - PDA of [`VAULT_SEED`] and the market (writable)
+/// * `num_outcomes` outcome mint PDAs in outcome index order (writable), none if not `tokenized`
+///
+/// See [`MAX_BATCH_MARKETS`] for the practical limit on batch size. Batched markets are not listed in the
+/// `Registry`, create them with `init_market` for that.
+pub fn batch_init_markets<'info>(
+    ctx: Context<'_, '_, 'info, 'info, BatchInitMarkets<'info>>,
+    params: Vec<InitMarketParams>,
+) -> Result<()> {
+    check_condition!(
+        !params.is_empty() && params.len() <= MAX_BATCH_MARKETS,
+        InvalidBatchSize
+    );
+
+    let expected_accounts: usize = params.iter().map(|p| 2 + mint_count(p)).sum();
+    check_condition!(
+        ctx.remaining_accounts.len() == expected_accounts,
+        InvalidMintCount
+    );
+
+    let admin = ctx.accounts.admin.to_account_info();
+    let rent = ctx.accounts.rent.to_account_info();
+    let system_program = ctx.accounts.system_program.to_account_info();
+    let token_program = ctx.accounts.token_program.to_account_info();
+
+    let mut offset = 0;
+    for market_params in params.iter() {
+        let accounts = &ctx.remaining_accounts[offset..offset + 2 + mint_count(market_params)];
+        offset += accounts.len();
+
+        let market_info = &accounts[0];
+        let market_vault_info = &accounts[1];
+        let mint_accounts = &accounts[2..];
+
+        let label_hash = market_params.label.as_bytes();
+        let (market_key, bump) =
+            Pubkey::find_program_address(&[MARKET_SEED, &label_hash], ctx.program_id);
+        check_condition!(market_info.key() == market_key, InvalidMarketSeed);
+        ensure_label_unused(market_info)?;
+
+        let (market_vault_key, vault_bump) =
+            Pubkey::find_program_address(&[VAULT_SEED, market_key.as_ref()], ctx.program_id);
+        check_condition!(
+            market_vault_info.key() == market_vault_key,
+            InvalidVaultSeed
+        );
+
+        init_pda_account_rent(
+            market_info,
+            Market::SIZE,
+            &admin,
+            ctx.program_id,
+            &system_program,
+            &[&[MARKET_SEED, &label_hash, &[bump]]],
+        )?;
+
+        init_pda_account_rent(
+            market_vault_info,
+            0,
+            &admin,
+            ctx.program_id,
+            &system_program,
+            &[&[VAULT_SEED, market_key.as_ref(), &[vault_bump]]],
+        )?;
+
+        let market_loader =
+            AccountLoader::<Market>::try_from_unchecked(ctx.program_id, market_info)?;
+        let decimals = {
+            let mut market = market_loader.load_init()?;
+            initialize_market(&mut market, market_params, admin.key, bump, vault_bump)?;
+            market.mints_initialized = market.all_mints_mask();
+            market.mint_decimals()
+        };
+        // Writes the account discriminator
+        market_loader.exit(ctx.program_id)?;
+
+        if market_params.tokenized {
+            create_outcome_mints(
+                ctx.program_id,
+                &market_key,
+                market_params,
+                bump,
+                decimals,
+                mint_accounts,
+                &admin,
+                &rent,
+                &system_program,
+                &token_program,
+            )?;
+        }
+
+        fund_vault(market_params, &admin, market_vault_info, &system_program)?;
+    }
+
+    Ok(())
+}
+
+/// Outcome mints a market in the batch is created with
+fn mint_count(params: &InitMarketParams) -> usize {
+    if params.tokenized {
+        params.num_outcomes as usize
+    } else {
+        0
+    }
+}
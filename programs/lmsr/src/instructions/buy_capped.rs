@@ -0,0 +1,102 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token;
+
+use crate::events::SharesBought;
+use crate::instructions::BuyShares;
+use crate::types::CappedFill;
+use common::constants::MARKET_SEED;
+use common::utils::validate_vault;
+use common::{check_condition, errors::ErrorCode};
+
+/// Buy shares of an outcome with up to `amount_in` lamports, filling as much as the market can
+/// take instead of rejecting a buy past `max_buy`. Only the filled lamports are transferred to
+/// the vault, so the unfilled remainder stays with the buyer. Takes the same accounts as
+/// `buy_shares`.
+///
+/// Returns the shares minted and the lamports filled, rejecting with `SlippageExceeded` if fewer
+/// than `min_shares_out` shares were minted.
+pub fn buy_capped(
+    ctx: Context<BuyShares>,
+    outcome_index: u8,
+    amount_in: u64,
+    min_shares_out: u64,
+) -> Result<CappedFill> {
+    validate_vault(
+        &ctx.accounts.market_vault,
+        &ctx.accounts.market.key(),
+        ctx.accounts.market.load()?.vault_bump,
+        ctx.program_id,
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let (fill, tokens_out, label_hash, bump) = {
+        let mut market = ctx.accounts.market.load_mut()?;
+        market.validate_num_outcomes()?;
+        market.enter()?;
+
+        market.is_tradable(now)?;
+        market.accumulate_prices(now)?;
+
+        let (shares_out, amount_filled) = market.buy_capped(outcome_index, amount_in, now)?;
+        check_condition!(shares_out >= min_shares_out, SlippageExceeded);
+        market.track_consensus(now)?;
+
+        (
+            CappedFill {
+                shares_out,
+                amount_filled,
+            },
+            market.shares_to_tokens(shares_out)?,
+            market.label.as_bytes(),
+            market.bump,
+        )
+    };
+
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.buyer.to_account_info(),
+                to: ctx.accounts.market_vault.to_account_info(),
+            },
+        ),
+        fill.amount_filled,
+    )?;
+
+    let market_signer_seeds: &[&[&[u8]]] = &[&[MARKET_SEED, &label_hash, &[bump]]];
+    token::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::MintTo {
+                mint: ctx.accounts.outcome_mint.to_account_info(),
+                to: ctx.accounts.buyer_token_account.to_account_info(),
+                authority: ctx.accounts.market.to_account_info(),
+            },
+            market_signer_seeds,
+        ),
+        tokens_out,
+    )?;
+
+    ctx.accounts.track_position(
+        ctx.bumps.position,
+        outcome_index,
+        fill.shares_out,
+        fill.amount_filled,
+        now,
+    )?;
+
+    ctx.accounts.market.load_mut()?.exit();
+
+    emit!(SharesBought {
+        market: ctx.accounts.market.key(),
+        buyer: ctx.accounts.buyer.key(),
+        outcome_index,
+        amount_in: fill.amount_filled,
+        shares_out: fill.shares_out,
+        client_order_id: None,
+        slot: Clock::get()?.slot,
+    });
+
+    Ok(fill)
+}
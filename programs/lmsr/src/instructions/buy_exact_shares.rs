@@ -0,0 +1,94 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token;
+
+use crate::events::SharesBought;
+use crate::instructions::BuyShares;
+use common::constants::MARKET_SEED;
+use common::utils::validate_vault;
+
+/// Buy exactly `shares_out` shares of an outcome, paying what they cost. Takes the same accounts
+/// as `buy_shares`.
+///
+/// Returns the lamports charged, fee included, rejecting with `SlippageExceeded` if that is more
+/// than `max_amount_in`.
+pub fn buy_exact_shares(
+    ctx: Context<BuyShares>,
+    outcome_index: u8,
+    shares_out: u64,
+    max_amount_in: u64,
+) -> Result<u64> {
+    validate_vault(
+        &ctx.accounts.market_vault,
+        &ctx.accounts.market.key(),
+        ctx.accounts.market.load()?.vault_bump,
+        ctx.program_id,
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let (amount_in, tokens_out, label_hash, bump) = {
+        let mut market = ctx.accounts.market.load_mut()?;
+        market.validate_num_outcomes()?;
+        market.enter()?;
+
+        market.is_tradable(now)?;
+        market.accumulate_prices(now)?;
+
+        let amount_in = market.buy_exact_shares(outcome_index, shares_out, max_amount_in, now)?;
+        market.track_consensus(now)?;
+
+        (
+            amount_in,
+            market.shares_to_tokens(shares_out)?,
+            market.label.as_bytes(),
+            market.bump,
+        )
+    };
+
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.buyer.to_account_info(),
+                to: ctx.accounts.market_vault.to_account_info(),
+            },
+        ),
+        amount_in,
+    )?;
+
+    let market_signer_seeds: &[&[&[u8]]] = &[&[MARKET_SEED, &label_hash, &[bump]]];
+    token::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::MintTo {
+                mint: ctx.accounts.outcome_mint.to_account_info(),
+                to: ctx.accounts.buyer_token_account.to_account_info(),
+                authority: ctx.accounts.market.to_account_info(),
+            },
+            market_signer_seeds,
+        ),
+        tokens_out,
+    )?;
+
+    ctx.accounts.track_position(
+        ctx.bumps.position,
+        outcome_index,
+        shares_out,
+        amount_in,
+        now,
+    )?;
+
+    ctx.accounts.market.load_mut()?.exit();
+
+    emit!(SharesBought {
+        market: ctx.accounts.market.key(),
+        buyer: ctx.accounts.buyer.key(),
+        outcome_index,
+        amount_in,
+        shares_out,
+        client_order_id: None,
+        slot: Clock::get()?.slot,
+    });
+
+    Ok(amount_in)
+}
@@ -0,0 +1,103 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+
+use crate::state::{BettorPosition, Market};
+use common::constants::{MARKET_SEED, POSITION_SEED, VAULT_SEED};
+use common::utils::validate_vault;
+use common::{check_condition, errors::ErrorCode};
+
+#[derive(Accounts)]
+pub struct BuyPosition<'info> {
+    pub system_program: Program<'info, System>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, &market.load()?.label.as_bytes()],
+        bump = market.load()?.bump,
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    /// CHECK: Check PDA. Account with no data that stores lamports for the [`Market`] as its `reserves`
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, market.key().as_ref()],
+        bump = market.load()?.vault_bump,
+    )]
+    pub market_vault: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = BettorPosition::SIZE,
+        seeds = [POSITION_SEED, market.key().as_ref(), buyer.key().as_ref()],
+        bump,
+    )]
+    pub position: Account<'info, BettorPosition>,
+}
+
+/// Buy shares of an outcome of an untokenized market for `amount_in` lamports.
+/// The lamports go to the vault and the shares are recorded in the buyer's position, the
+/// counterpart of `buy_shares` minting them. `amount_in`, fee included, is added to the
+/// position's cost basis.
+///
+/// Rejects with `PriceImpactTooHigh` if the buy raises the outcome's price by more than
+/// `max_price_impact_bps` relative to its price before (0 disables the cap).
+///
+/// Returns the shares bought, rejecting with `SlippageExceeded` if fewer than `min_shares_out`.
+pub fn buy_position(
+    ctx: Context<BuyPosition>,
+    outcome_index: u8,
+    amount_in: u64,
+    min_shares_out: u64,
+    max_price_impact_bps: u16,
+) -> Result<u64> {
+    validate_vault(
+        &ctx.accounts.market_vault,
+        &ctx.accounts.market.key(),
+        ctx.accounts.market.load()?.vault_bump,
+        ctx.program_id,
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let shares_out = {
+        let mut market = ctx.accounts.market.load_mut()?;
+        market.validate_num_outcomes()?;
+        check_condition!(market.tokenized == 0, MarketIsTokenized);
+        market.enter()?;
+
+        market.is_tradable(now)?;
+        market.accumulate_prices(now)?;
+
+        let shares_out =
+            market.buy_shares_within_impact(outcome_index, amount_in, max_price_impact_bps, now)?;
+        check_condition!(shares_out >= min_shares_out, SlippageExceeded);
+        market.track_consensus(now)?;
+
+        shares_out
+    };
+
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.buyer.to_account_info(),
+                to: ctx.accounts.market_vault.to_account_info(),
+            },
+        ),
+        amount_in,
+    )?;
+
+    let position = &mut ctx.accounts.position;
+    position.market = ctx.accounts.market.key();
+    position.owner = ctx.accounts.buyer.key();
+    position.bump = ctx.bumps.position;
+    position.credit(outcome_index, shares_out, amount_in)?;
+    position.last_trade_at = now;
+
+    ctx.accounts.market.load_mut()?.exit();
+
+    Ok(shares_out)
+}
@@ -0,0 +1,191 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Token, TokenAccount};
+
+use crate::events::SharesBought;
+use crate::state::{BettorPosition, Market};
+use common::constants::{MARKET_SEED, OUTCOME_MINT_SEED, POSITION_SEED, VAULT_SEED};
+use common::utils::validate_vault;
+use common::{check_condition, errors::ErrorCode};
+
+#[derive(Accounts)]
+#[instruction(outcome_index: u8)]
+pub struct BuyShares<'info> {
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, &market.load()?.label.as_bytes()],
+        bump = market.load()?.bump,
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    /// CHECK: Check PDA. Account with no data that stores lamports for the [`Market`] as its `reserves`
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, market.key().as_ref()],
+        bump = market.load()?.vault_bump,
+    )]
+    pub market_vault: UncheckedAccount<'info>,
+
+    /// CHECK: Check PDA. Only this program can create an account at the outcome mint PDA, so one
+    /// owned by the token program is the outcome's mint. Checked here rather than deserialized as
+    /// a `Mint` so a missing (lazy, see `init_outcome_mint`) mint fails with a clear error before
+    /// any token account is touched.
+    #[account(
+        mut,
+        seeds = [OUTCOME_MINT_SEED, market.key().as_ref(), &[outcome_index]],
+        bump,
+        owner = token::ID @ ErrorCode::OutcomeMintNotInitialized,
+    )]
+    pub outcome_mint: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = outcome_mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program,
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+
+    /// The buyer's [`BettorPosition`] in the market, to record the shares and what they cost for
+    /// profit and loss displays. Omit it to trade without tracking. It only sees the buyer's own
+    /// trades: outcome tokens transferred in or out are not tracked.
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = BettorPosition::SIZE,
+        seeds = [POSITION_SEED, market.key().as_ref(), buyer.key().as_ref()],
+        bump,
+    )]
+    pub position: Option<Account<'info, BettorPosition>>,
+}
+
+impl BuyShares<'_> {
+    /// Record `shares` bought for `cost` lamports in the buyer's position, if one was passed
+    pub(crate) fn track_position(
+        &mut self,
+        bump: Option<u8>,
+        outcome_index: u8,
+        shares: u64,
+        cost: u64,
+        now: i64,
+    ) -> Result<()> {
+        let (Some(position), Some(bump)) = (self.position.as_mut(), bump) else {
+            return Ok(());
+        };
+        position.market = self.market.key();
+        position.owner = self.buyer.key();
+        position.bump = bump;
+        position.credit(outcome_index, shares, cost)?;
+        position.last_trade_at = now;
+
+        Ok(())
+    }
+}
+
+/// Buy shares of an outcome for `amount_in` lamports.
+/// The lamports go to the vault and the shares are minted to the buyer.
+///
+/// Rejects with `PriceImpactTooHigh` if the buy raises the outcome's price by more than
+/// `max_price_impact_bps` relative to its price before (0 disables the cap).
+///
+/// Returns the shares minted, rejecting with `SlippageExceeded` if fewer than `min_shares_out`.
+///
+/// Pass the buyer's `position` to record the buy in it, see [`BuyShares::position`].
+///
+/// Emits [`SharesBought`] with the optional `client_order_id`, for clients to match the buy
+/// to their order. Solana's blockhash check already rejects a byte-identical resubmission, but
+/// an order re-signed after a timeout is a new transaction and executes again; the id lets the
+/// client tell from the logs that it already filled.
+pub fn buy_shares(
+    ctx: Context<BuyShares>,
+    outcome_index: u8,
+    amount_in: u64,
+    min_shares_out: u64,
+    max_price_impact_bps: u16,
+    client_order_id: Option<u64>,
+) -> Result<u64> {
+    validate_vault(
+        &ctx.accounts.market_vault,
+        &ctx.accounts.market.key(),
+        ctx.accounts.market.load()?.vault_bump,
+        ctx.program_id,
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let (shares_out, tokens_out, label_hash, bump) = {
+        let mut market = ctx.accounts.market.load_mut()?;
+        market.validate_num_outcomes()?;
+        market.enter()?;
+
+        market.is_tradable(now)?;
+        market.accumulate_prices(now)?;
+
+        let shares_out =
+            market.buy_shares_within_impact(outcome_index, amount_in, max_price_impact_bps, now)?;
+        check_condition!(shares_out >= min_shares_out, SlippageExceeded);
+        market.track_consensus(now)?;
+
+        (
+            shares_out,
+            market.shares_to_tokens(shares_out)?,
+            market.label.as_bytes(),
+            market.bump,
+        )
+    };
+
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.buyer.to_account_info(),
+                to: ctx.accounts.market_vault.to_account_info(),
+            },
+        ),
+        amount_in,
+    )?;
+
+    let market_signer_seeds: &[&[&[u8]]] = &[&[MARKET_SEED, &label_hash, &[bump]]];
+    token::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::MintTo {
+                mint: ctx.accounts.outcome_mint.to_account_info(),
+                to: ctx.accounts.buyer_token_account.to_account_info(),
+                authority: ctx.accounts.market.to_account_info(),
+            },
+            market_signer_seeds,
+        ),
+        tokens_out,
+    )?;
+
+    ctx.accounts.track_position(
+        ctx.bumps.position,
+        outcome_index,
+        shares_out,
+        amount_in,
+        now,
+    )?;
+
+    ctx.accounts.market.load_mut()?.exit();
+
+    emit!(SharesBought {
+        market: ctx.accounts.market.key(),
+        buyer: ctx.accounts.buyer.key(),
+        outcome_index,
+        amount_in,
+        shares_out,
+        client_order_id,
+        slot: Clock::get()?.slot,
+    });
+
+    Ok(shares_out)
+}
@@ -0,0 +1,125 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Token, TokenAccount};
+
+use crate::state::Market;
+use common::constants::{MARKET_SEED, OUTCOME_MINT_SEED};
+use common::errors::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(from_outcome: u8, to_outcome: u8)]
+pub struct Flip<'info> {
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    #[account(mut)]
+    pub trader: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, &market.load()?.label.as_bytes()],
+        bump = market.load()?.bump,
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    /// CHECK: Check PDA. Mint of the outcome sold, see `sell_shares`
+    #[account(
+        mut,
+        seeds = [OUTCOME_MINT_SEED, market.key().as_ref(), &[from_outcome]],
+        bump,
+        owner = token::ID @ ErrorCode::OutcomeMintNotInitialized,
+    )]
+    pub from_mint: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = from_mint,
+        associated_token::authority = trader,
+        associated_token::token_program = token_program,
+    )]
+    pub from_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Check PDA. Mint of the outcome bought, see `buy_shares`
+    #[account(
+        mut,
+        seeds = [OUTCOME_MINT_SEED, market.key().as_ref(), &[to_outcome]],
+        bump,
+        owner = token::ID @ ErrorCode::OutcomeMintNotInitialized,
+    )]
+    pub to_mint: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = trader,
+        associated_token::mint = to_mint,
+        associated_token::authority = trader,
+        associated_token::token_program = token_program,
+    )]
+    pub to_token_account: Account<'info, TokenAccount>,
+}
+
+/// Sell `shares` of `from_outcome` and buy `to_outcome` with the payout, in one step.
+/// The sold shares are burned and the bought ones minted, the lamports stay in the vault.
+///
+/// Returns the shares of `to_outcome` bought, rejecting the whole flip with `SlippageExceeded`
+/// if fewer than `min_target_shares`.
+pub fn flip(
+    ctx: Context<Flip>,
+    from_outcome: u8,
+    to_outcome: u8,
+    shares: u64,
+    min_target_shares: u64,
+) -> Result<u64> {
+    let (target_shares, tokens_in, tokens_out, label_hash, bump) = {
+        let mut market = ctx.accounts.market.load_mut()?;
+        market.validate_num_outcomes()?;
+        market.enter()?;
+
+        let now = Clock::get()?.unix_timestamp;
+        market.is_tradable(now)?;
+        market.accumulate_prices(now)?;
+
+        let target_shares =
+            market.flip(from_outcome, to_outcome, shares, min_target_shares, now)?;
+        market.track_consensus(now)?;
+
+        (
+            target_shares,
+            market.shares_to_tokens(shares)?,
+            market.shares_to_tokens(target_shares)?,
+            market.label.as_bytes(),
+            market.bump,
+        )
+    };
+
+    token::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::Burn {
+                mint: ctx.accounts.from_mint.to_account_info(),
+                from: ctx.accounts.from_token_account.to_account_info(),
+                authority: ctx.accounts.trader.to_account_info(),
+            },
+        ),
+        tokens_in,
+    )?;
+
+    let market_signer_seeds: &[&[&[u8]]] = &[&[MARKET_SEED, &label_hash, &[bump]]];
+    token::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::MintTo {
+                mint: ctx.accounts.to_mint.to_account_info(),
+                to: ctx.accounts.to_token_account.to_account_info(),
+                authority: ctx.accounts.market.to_account_info(),
+            },
+            market_signer_seeds,
+        ),
+        tokens_out,
+    )?;
+
+    ctx.accounts.market.load_mut()?.exit();
+
+    Ok(target_shares)
+}
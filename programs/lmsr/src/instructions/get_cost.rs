@@ -0,0 +1,15 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Market;
+
+#[derive(Accounts)]
+pub struct GetCost<'info> {
+    pub market: AccountLoader<'info, Market>,
+}
+
+/// Read-only view of [`Market::cost`], returned to the caller via return data.
+pub fn get_cost(ctx: Context<GetCost>) -> Result<u64> {
+    let market = ctx.accounts.market.load()?;
+
+    market.cost()
+}
@@ -0,0 +1,15 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Market;
+
+#[derive(Accounts)]
+pub struct GetCostToBalance<'info> {
+    pub market: AccountLoader<'info, Market>,
+}
+
+/// Read-only view of [`Market::cost_to_balance`], returned to the caller via return data.
+pub fn get_cost_to_balance(ctx: Context<GetCostToBalance>) -> Result<u64> {
+    let market = ctx.accounts.market.load()?;
+
+    market.cost_to_balance()
+}
@@ -0,0 +1,15 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Market;
+
+#[derive(Accounts)]
+pub struct GetMaxLoss<'info> {
+    pub market: AccountLoader<'info, Market>,
+}
+
+/// Read-only view of [`Market::max_loss`], returned to the caller via return data.
+pub fn get_max_loss(ctx: Context<GetMaxLoss>) -> Result<u64> {
+    let market = ctx.accounts.market.load()?;
+
+    market.max_loss()
+}
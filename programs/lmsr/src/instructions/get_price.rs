@@ -0,0 +1,15 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Market;
+
+#[derive(Accounts)]
+pub struct GetPrice<'info> {
+    pub market: AccountLoader<'info, Market>,
+}
+
+/// Read-only view of [`Market::price`], returned to the caller via return data.
+pub fn get_price(ctx: Context<GetPrice>, outcome_index: u8) -> Result<u64> {
+    let market = ctx.accounts.market.load()?;
+
+    market.price(outcome_index)
+}
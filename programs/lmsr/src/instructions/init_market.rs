@@ -1,20 +1,19 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::Token;
 use solana_program::program_pack::Pack;
-use spl_math::uint::U256;
 use spl_token::solana_program;
 
-use crate::state::Market;
-use crate::types::{FixedSizeString, MAX_PADDED_STRING_LENGTH};
+use crate::state::{Market, Registry, RegistryPage};
+use crate::types::InitMarketParams;
 use anchor_lang::system_program;
 use common::constants::{
-    MARKET_SEED, MAX_OUTCOMES, MINIMUM_OUTCOMES_PER_MARKET, MIN_MARKET_DURATION,
-    OUTCOME_MINT_DECIMALS, OUTCOME_MINT_SEED, VAULT_SEED,
+    MARKET_SEED, MAX_OUTCOMES, MAX_TX_ACCOUNT_LOCKS, OUTCOME_MINT_SEED, REGISTRY_SEED, VAULT_SEED,
 };
+use common::utils::{init_pda_account_rent, init_pda_account_rent_if_needed};
 use common::{check_condition, errors::ErrorCode};
 
 #[derive(Accounts)]
-#[instruction(num_outcomes: u8, scale: u64, resolve_at: i64, label: FixedSizeString)]
+#[instruction(params: InitMarketParams)]
 pub struct InitMarket<'info> {
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
@@ -23,126 +22,388 @@ pub struct InitMarket<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
 
+    /// CHECK: Check PDA. Created in the handler rather than by `init`, so a label that is already
+    /// taken fails with `MarketLabelAlreadyExists` instead of the system program's "already in use"
     #[account(
-        init,
-        payer = admin,
-        space = Market::SIZE,
-        seeds = [MARKET_SEED, &label.as_bytes()],
+        mut,
+        seeds = [MARKET_SEED, &params.label.as_bytes()],
         bump
     )]
-    pub market: AccountLoader<'info, Market>,
+    pub market: UncheckedAccount<'info>,
 
     /// CHECK: Check PDA. Account with no data that stores lamports for the [`Market`] as its `reserves`
     #[account(
-        init,
-        payer = admin,
-        space = 0,
+        mut,
         seeds = [VAULT_SEED, market.key().as_ref()],
         bump,
     )]
     pub market_vault: UncheckedAccount<'info>,
+
+    /// The program's [`Registry`], to list the market in. Omit it to create an unlisted market.
+    #[account(
+        mut,
+        seeds = [REGISTRY_SEED],
+        bump = registry.bump,
+    )]
+    pub registry: Option<Account<'info, Registry>>,
+
+    /// CHECK: Checked against the registry's next page in [`register_market`], which creates it
+    /// when the market is the first on its page. Required with `registry`.
+    #[account(mut)]
+    pub registry_page: Option<UncheckedAccount<'info>>,
 }
 
+/// Accounts an `init_market` transaction needs besides the outcome mints: the [`InitMarket`]
+/// accounts and the program itself.
+const INIT_MARKET_FIXED_ACCOUNTS: usize = 9;
+
+// A market with every outcome mint passed up front has to fit in one transaction. That is 25
+// accounts at 16 outcomes, or 800 bytes of keys, also well within the 1232 byte packet. Markets
+// that run out of compute or other space creating all their mints at once use `init_outcome_mint`.
+const _: () = assert!(INIT_MARKET_FIXED_ACCOUNTS + MAX_OUTCOMES <= MAX_TX_ACCOUNT_LOCKS);
+
 pub fn init_market<'info>(
     ctx: Context<'_, '_, 'info, 'info, InitMarket<'info>>,
-    num_outcomes: u8,
-    scale: u64,
-    resolve_at: i64,
-    label: FixedSizeString,
+    params: InitMarketParams,
 ) -> Result<()> {
-    let mut market = ctx.accounts.market.load_init()?;
+    let market_key = ctx.accounts.market.key();
+
+    // Either every outcome mint is created now, or none are and each is created later by
+    // `init_outcome_mint`, for markets with too many outcomes to fit in one transaction.
+    // Untokenized markets have no mints at all.
+    let lazy_mints = ctx.remaining_accounts.is_empty();
+    check_condition!(
+        lazy_mints
+            || (params.tokenized && ctx.remaining_accounts.len() == params.num_outcomes as usize),
+        InvalidMintCount
+    );
+
+    let admin = ctx.accounts.admin.to_account_info();
+    let market_info = ctx.accounts.market.to_account_info();
+    let system_program = ctx.accounts.system_program.to_account_info();
+    ensure_label_unused(&market_info)?;
+
+    let label_hash = params.label.as_bytes();
+    init_pda_account_rent(
+        &market_info,
+        Market::SIZE,
+        &admin,
+        ctx.program_id,
+        &system_program,
+        &[&[MARKET_SEED, &label_hash, &[ctx.bumps.market]]],
+    )?;
+    init_pda_account_rent(
+        &ctx.accounts.market_vault.to_account_info(),
+        0,
+        &admin,
+        ctx.program_id,
+        &system_program,
+        &[&[VAULT_SEED, market_key.as_ref(), &[ctx.bumps.market_vault]]],
+    )?;
+
+    // As `AccountLoader::load_init`, which needs the account borrowed for `'info`
+    let decimals = {
+        let mut data = market_info.try_borrow_mut_data()?;
+        data[..8].copy_from_slice(Market::DISCRIMINATOR);
+        let market: &mut Market = bytemuck::from_bytes_mut(&mut data[8..Market::SIZE]);
+        initialize_market(
+            market,
+            &params,
+            ctx.accounts.admin.key,
+            ctx.bumps.market,
+            ctx.bumps.market_vault,
+        )?;
+        if !lazy_mints {
+            market.mints_initialized = market.all_mints_mask();
+        }
+        market.mint_decimals()
+    };
+
+    if !lazy_mints {
+        create_outcome_mints(
+            ctx.program_id,
+            &market_key,
+            &params,
+            ctx.bumps.market,
+            decimals,
+            ctx.remaining_accounts,
+            &ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.rent.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+        )?;
+    }
+
+    fund_vault(
+        &params,
+        &ctx.accounts.admin.to_account_info(),
+        &ctx.accounts.market_vault.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    if let Some(registry) = ctx.accounts.registry.as_mut() {
+        let registry_page = ctx
+            .accounts
+            .registry_page
+            .as_ref()
+            .ok_or(error!(ErrorCode::InvalidRegistryPage))?;
+        register_market(
+            registry,
+            registry_page,
+            &market_key,
+            &admin,
+            ctx.program_id,
+            &system_program,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Append `market` to the [`Registry`], creating its [`RegistryPage`] if it is the first market
+/// on the page.
+pub(crate) fn register_market<'info>(
+    registry: &mut Registry,
+    registry_page: &AccountInfo<'info>,
+    market: &Pubkey,
+    payer: &AccountInfo<'info>,
+    program_id: &Pubkey,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let page_index = registry.next_page();
+    let page_seed = page_index.to_le_bytes();
+    let (expected_key, page_bump) =
+        Pubkey::find_program_address(&[REGISTRY_SEED, &page_seed], program_id);
+    check_condition!(registry_page.key() == expected_key, InvalidRegistryPage);
+
+    let created = init_pda_account_rent_if_needed(
+        registry_page,
+        RegistryPage::SIZE,
+        payer,
+        program_id,
+        system_program,
+        &[&[REGISTRY_SEED, &page_seed, &[page_bump]]],
+    )?;
+    let mut page = if created {
+        RegistryPage {
+            page: page_index,
+            markets: Vec::new(),
+        }
+    } else {
+        RegistryPage::try_deserialize(&mut &registry_page.try_borrow_data()?[..])?
+    };
+    page.markets.push(*market);
+    page.try_serialize(&mut &mut registry_page.try_borrow_mut_data()?[..])?;
+
+    registry.market_count = registry
+        .market_count
+        .checked_add(1)
+        .ok_or(error!(ErrorCode::MathOverflow))?;
+
+    Ok(())
+}
+
+/// Reject a market PDA that already exists. Labels must be unique, as the market PDA is derived
+/// from the label, and creating it again would otherwise fail with an opaque "already in use".
+pub(crate) fn ensure_label_unused(market: &AccountInfo) -> Result<()> {
+    check_condition!(
+        market.owner == &system_program::ID && market.data_is_empty(),
+        MarketLabelAlreadyExists
+    );
+
+    Ok(())
+}
 
+/// Validate the [`InitMarketParams`] and write them to a freshly created [`Market`].
+pub(crate) fn initialize_market(
+    market: &mut Market,
+    params: &InitMarketParams,
+    admin: &Pubkey,
+    bump: u8,
+    vault_bump: u8,
+) -> Result<()> {
     let now = Clock::get()?.unix_timestamp;
+    params.validate(now)?;
+
+    market.admin = *admin;
+    market.num_outcomes = params.num_outcomes;
+    market.resolve_at = params.resolve_at;
+    market.scale = params.scale;
+    market.bump = bump;
+    market.vault_bump = vault_bump;
+    market.label = params.label.normalized();
+    market.initial_liquidity = params.require_initial_liquidity;
+    market.subsidy = params.initial_subsidy;
+    market.max_outcome_reserve = params.max_outcome_reserve;
+    market.oracle = params.oracle.unwrap_or_default();
+    market.fee_config = params.fee_config;
+    market.accrued_protocol_fees = params.fee_config.creation_fee;
+    market.fee_free_until = params.fee_free_until;
+    market.trading_halt_before_resolve = params.trading_halt_before_resolve;
+    market.high_precision = params.high_precision as u8;
+    market.tokenized = params.tokenized as u8;
+    market.outcome_decimals = params.outcome_decimals;
+    if !params.tokenized {
+        market.mints_initialized = market.all_mints_mask();
+    }
+    market.last_fee_accrual = now;
+    market.trading_open_at = now;
+    market.price_cumulative_at = now;
+
+    // `validate` already requires MINIMUM_OUTCOMES_PER_MARKET, this holds even if that is lowered
+    check_condition!(market.num_outcomes > 0, NotEnoughOutcomes);
+    // Every supply and reserve starts at zero, even on an account that held data before
     check_condition!(
-        num_outcomes >= MINIMUM_OUTCOMES_PER_MARKET,
-        NotEnoughOutcomes
+        market.supplies.iter().all(|&q| q == 0) && market.reserves.iter().all(|&r| r == 0),
+        MarketStateNotEmpty
     );
-    check_condition!(now + MIN_MARKET_DURATION < resolve_at, MarketTooQuick);
-    check_condition!(num_outcomes as usize <= MAX_OUTCOMES, TooManyOutcomes);
+
+    Ok(())
+}
+
+/// Create and initialize one SPL mint per outcome, with the [`Market`] PDA as mint authority and
+/// `decimals` from [`Market::mint_decimals`].
+///
+/// `mint_accounts` must be the outcome mint PDAs in outcome index order, one per outcome.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn create_outcome_mints<'info>(
+    program_id: &Pubkey,
+    market_key: &Pubkey,
+    params: &InitMarketParams,
+    market_bump: u8,
+    decimals: u8,
+    mint_accounts: &[AccountInfo<'info>],
+    admin: &AccountInfo<'info>,
+    rent: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+) -> Result<()> {
     check_condition!(
-        label.value.len() <= MAX_PADDED_STRING_LENGTH,
-        InvalidLabelLength
+        !mint_accounts.is_empty() && mint_accounts.len() == params.num_outcomes as usize,
+        InvalidMintCount
     );
+    let label_hash = params.label.as_bytes();
 
-    let bump = ctx.bumps.market;
-    let market_key = ctx.accounts.market.key();
+    for (i, acct) in mint_accounts.iter().enumerate() {
+        create_outcome_mint(
+            program_id,
+            market_key,
+            &label_hash,
+            market_bump,
+            decimals,
+            i as u8,
+            acct,
+            admin,
+            rent,
+            system_program,
+            token_program,
+        )?;
+    }
 
+    Ok(())
+}
+
+/// Create and initialize the SPL mint of outcome `index`, with the [`Market`] PDA as mint authority.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn create_outcome_mint<'info>(
+    program_id: &Pubkey,
+    market_key: &Pubkey,
+    label_hash: &[u8; 32],
+    market_bump: u8,
+    decimals: u8,
+    index: u8,
+    mint_account: &AccountInfo<'info>,
+    admin: &AccountInfo<'info>,
+    rent: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+) -> Result<()> {
     // Market PDA seeds
-    let market_signer_seeds: &[&[&[u8]]] = &[&[MARKET_SEED, &label.as_bytes(), &[bump]]];
+    let market_signer_seeds: &[&[&[u8]]] = &[&[MARKET_SEED, label_hash, &[market_bump]]];
 
-    market.admin = *ctx.accounts.admin.key;
-    market.num_outcomes = num_outcomes;
-    market.resolve_at = resolve_at;
-    market.scale = scale;
-    market.bump = ctx.bumps.market;
-    market.vault_bump = ctx.bumps.market_vault;
-    market.label = label;
+    // Unchecked -> Mint
+    let mint_info = mint_account.clone();
+    let rent_info = rent.clone();
 
-    let remaining = ctx.remaining_accounts;
+    // get PDA + bump exactly how off-chain code does
+    let (expected_key, mint_bump) = Pubkey::find_program_address(
+        &[OUTCOME_MINT_SEED, market_key.as_ref(), &[index]],
+        program_id,
+    );
 
-    check_condition!(remaining.len() == num_outcomes as usize, InvalidMintCount);
+    check_condition!(mint_info.key() == expected_key, InvalidMintSeed);
 
-    for (i, acct) in remaining.iter().enumerate() {
-        // Unchecked -> Mint
-        let mint_info = acct.clone();
-        let rent_info = ctx.accounts.rent.to_account_info().clone();
+    let mint_signer_seeds: &[&[&[u8]]] = &[&[
+        OUTCOME_MINT_SEED,
+        market_key.as_ref(),
+        &[index],
+        &[mint_bump],
+    ]];
 
-        // get PDA + bump exactly how off-chain code does
-        let (expected_key, mint_bump) = Pubkey::find_program_address(
-            &[OUTCOME_MINT_SEED, market_key.as_ref(), &[i as u8]],
-            ctx.program_id,
-        );
-
-        check_condition!(mint_info.key() == expected_key, InvalidMintSeed);
-
-        let mint_signer_seeds: &[&[&[u8]]] = &[&[
-            OUTCOME_MINT_SEED,
-            market_key.as_ref(),
-            &[i as u8],
-            &[mint_bump],
-        ]];
-
-        let mint_space = spl_token::state::Mint::LEN;
-        let rent_lamports = Rent::get()?.minimum_balance(mint_space);
-
-        system_program::create_account(
-            CpiContext::new_with_signer(
-                ctx.accounts.system_program.to_account_info().clone(),
-                system_program::CreateAccount {
-                    from: ctx.accounts.admin.to_account_info(),
-                    to: mint_info.clone(),
-                },
-                mint_signer_seeds,
-            ),
-            rent_lamports,
-            mint_space as u64,
-            &ctx.accounts.token_program.key(),
-        )?;
+    let mint_space = spl_token::state::Mint::LEN;
+    let rent_lamports = Rent::get()?.minimum_balance(mint_space);
+
+    system_program::create_account(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::CreateAccount {
+                from: admin.clone(),
+                to: mint_info.clone(),
+            },
+            mint_signer_seeds,
+        ),
+        rent_lamports,
+        mint_space as u64,
+        &token_program.key(),
+    )?;
+
+    anchor_spl::token_interface::initialize_mint(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            anchor_spl::token_interface::InitializeMint {
+                mint: mint_info.clone(),
+                rent: rent_info.clone(),
+            },
+            market_signer_seeds,
+        ),
+        decimals,
+        market_key,
+        None,
+    )?;
 
-        anchor_spl::token_interface::initialize_mint(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info().clone(),
-                anchor_spl::token_interface::InitializeMint {
-                    mint: mint_info.clone(),
-                    rent: rent_info.clone(),
+    Ok(())
+}
+
+/// Move the admin's initial liquidity and subsidy (each opt-in, 0 disables) and the creation fee
+/// into the vault. The liquidity and subsidy are collateral held by the vault and are separate
+/// from the outcome supplies, the creation fee is set aside in `accrued_protocol_fees` by
+/// [`initialize_market`].
+pub(crate) fn fund_vault<'info>(
+    params: &InitMarketParams,
+    admin: &AccountInfo<'info>,
+    market_vault: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let amount = params
+        .require_initial_liquidity
+        .checked_add(params.initial_subsidy)
+        .and_then(|amount| amount.checked_add(params.fee_config.creation_fee))
+        .ok_or(error!(ErrorCode::MathOverflow))?;
+
+    if amount > 0 {
+        check_condition!(admin.lamports() >= amount, InsufficientFunds);
+
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: admin.clone(),
+                    to: market_vault.clone(),
                 },
-                market_signer_seeds,
             ),
-            OUTCOME_MINT_DECIMALS,
-            &market_key,
-            None,
+            amount,
         )?;
     }
 
-    // Compute initial invariant
-    // product(reserves[0..num_outcomes]) = 0 as all reserves = 0
-    // But we compute it properly so later it is easy to modify the logic.
-    let n = num_outcomes as usize;
-    let mut prod = U256::from(1u64);
-    for i in 0..n {
-        let r = U256::from(market.reserves[i]);
-        prod = prod.checked_mul(r).ok_or(error!(ErrorCode::MathOverflow))?;
-    }
-
     Ok(())
 }
@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Token;
+
+use crate::instructions::create_outcome_mint;
+use crate::state::Market;
+use common::constants::MARKET_SEED;
+use common::{check_condition, errors::ErrorCode};
+
+#[derive(Accounts)]
+pub struct InitOutcomeMint<'info> {
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    pub token_program: Program<'info, Token>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [MARKET_SEED, &market.load()?.label.as_bytes()],
+        bump = market.load()?.bump,
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    /// CHECK: Outcome mint PDA, checked and created in [`create_outcome_mint`]
+    #[account(mut)]
+    pub outcome_mint: UncheckedAccount<'info>,
+}
+
+/// Create the mint of one outcome for a market initialized without its mints.
+/// Trading opens once every outcome mint exists, see [`Market::mints_ready`].
+pub fn init_outcome_mint(ctx: Context<InitOutcomeMint>, outcome_index: u8) -> Result<()> {
+    let market_key = ctx.accounts.market.key();
+
+    let (label_hash, market_bump, decimals) = {
+        let mut market = ctx.accounts.market.load_mut()?;
+        market.validate_num_outcomes()?;
+        check_condition!(market.tokenized == 1, MarketNotTokenized);
+        market.mark_mint_initialized(outcome_index)?;
+
+        (market.label.as_bytes(), market.bump, market.mint_decimals())
+    };
+
+    create_outcome_mint(
+        ctx.program_id,
+        &market_key,
+        &label_hash,
+        market_bump,
+        decimals,
+        outcome_index,
+        &ctx.accounts.outcome_mint.to_account_info(),
+        &ctx.accounts.admin.to_account_info(),
+        &ctx.accounts.rent.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+    )
+}
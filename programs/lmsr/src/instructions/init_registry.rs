@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Registry;
+use common::constants::REGISTRY_SEED;
+
+#[derive(Accounts)]
+pub struct InitRegistry<'info> {
+    pub system_program: Program<'info, System>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = Registry::SIZE,
+        seeds = [REGISTRY_SEED],
+        bump,
+    )]
+    pub registry: Account<'info, Registry>,
+}
+
+/// Create the program's [`Registry`]. Permissionless, as it can only be created once and only
+/// `init_market` writes to it afterwards.
+pub fn init_registry(ctx: Context<InitRegistry>) -> Result<()> {
+    ctx.accounts.registry.bump = ctx.bumps.registry;
+
+    Ok(())
+}
@@ -1,3 +1,59 @@
+pub mod accrue_tvl_fee;
+pub mod admin_withdraw_excess;
+pub mod batch_init_markets;
+pub mod buy_capped;
+pub mod buy_exact_shares;
+pub mod buy_position;
+pub mod buy_shares;
+pub mod flip;
+pub mod get_cost;
+pub mod get_cost_to_balance;
+pub mod get_max_loss;
+pub mod get_price;
 pub mod init_market;
+pub mod init_outcome_mint;
+pub mod init_registry;
+pub mod quote_cpi;
+pub mod redeem;
+pub mod redeem_position;
+pub mod resolve_by_consensus;
+pub mod resolve_from_oracle;
+pub mod resolve_market;
+pub mod sell_basket;
+pub mod sell_position;
+pub mod sell_shares;
+pub mod set_fee_holiday;
+pub mod set_trading_window;
+pub mod snapshot_prices;
+pub mod sweep_dust;
+pub mod withdraw_fees;
 
+pub use accrue_tvl_fee::*;
+pub use admin_withdraw_excess::*;
+pub use batch_init_markets::*;
+pub use buy_capped::*;
+pub use buy_exact_shares::*;
+pub use buy_position::*;
+pub use buy_shares::*;
+pub use flip::*;
+pub use get_cost::*;
+pub use get_cost_to_balance::*;
+pub use get_max_loss::*;
+pub use get_price::*;
 pub use init_market::*;
+pub use init_outcome_mint::*;
+pub use init_registry::*;
+pub use quote_cpi::*;
+pub use redeem::*;
+pub use redeem_position::*;
+pub use resolve_by_consensus::*;
+pub use resolve_from_oracle::*;
+pub use resolve_market::*;
+pub use sell_basket::*;
+pub use sell_position::*;
+pub use sell_shares::*;
+pub use set_fee_holiday::*;
+pub use set_trading_window::*;
+pub use snapshot_prices::*;
+pub use sweep_dust::*;
+pub use withdraw_fees::*;
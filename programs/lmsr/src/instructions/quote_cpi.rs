@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Market;
+use crate::types::BuyQuote;
+
+#[derive(Accounts)]
+pub struct QuoteCpi<'info> {
+    pub market: AccountLoader<'info, Market>,
+}
+
+/// Read-only view of [`Market::quote_buy`] for other programs, returned via return data as a
+/// Borsh encoded [`BuyQuote`]. A caller CPIs into this instruction and reads the result with
+/// `get_return_data`, checking the returned program id is this program. The quote charges the
+/// fees a buy at the current cluster time would pay.
+pub fn quote_cpi(ctx: Context<QuoteCpi>, outcome_index: u8, amount_in: u64) -> Result<BuyQuote> {
+    let market = ctx.accounts.market.load()?;
+    let now = Clock::get()?.unix_timestamp;
+
+    market.quote_buy(outcome_index, amount_in, now)
+}
@@ -0,0 +1,102 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+
+use crate::state::Market;
+use common::constants::{MARKET_SEED, OUTCOME_MINT_SEED, VAULT_SEED};
+use common::errors::ErrorCode;
+use common::utils::validate_vault;
+
+#[derive(Accounts)]
+#[instruction(outcome_index: u8)]
+pub struct Redeem<'info> {
+    pub token_program: Program<'info, Token>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, &market.load()?.label.as_bytes()],
+        bump = market.load()?.bump,
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    /// CHECK: Check PDA. Account with no data that stores lamports for the [`Market`] as its `reserves`
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, market.key().as_ref()],
+        bump = market.load()?.vault_bump,
+    )]
+    pub market_vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [OUTCOME_MINT_SEED, market.key().as_ref(), &[outcome_index]],
+        bump,
+    )]
+    pub outcome_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = outcome_mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program,
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+}
+
+/// Burn `shares` of an outcome from a resolved market and pay the holder out of the vault.
+/// See [`Market::redeem`] for the payout in normal and refund mode.
+///
+/// Returns the lamports paid out.
+pub fn redeem(ctx: Context<Redeem>, outcome_index: u8, shares: u64) -> Result<u64> {
+    validate_vault(
+        &ctx.accounts.market_vault,
+        &ctx.accounts.market.key(),
+        ctx.accounts.market.load()?.vault_bump,
+        ctx.program_id,
+    )?;
+
+    let vault = ctx.accounts.market_vault.to_account_info();
+    let vault_available = vault
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(vault.data_len()));
+
+    let (payout, tokens_in) = {
+        let mut market = ctx.accounts.market.load_mut()?;
+        market.validate_num_outcomes()?;
+        market.enter()?;
+        market.check_mint_supply(outcome_index, ctx.accounts.outcome_mint.supply)?;
+        (
+            market.redeem(outcome_index, shares, vault_available)?,
+            market.shares_to_tokens(shares)?,
+        )
+    };
+
+    token::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::Burn {
+                mint: ctx.accounts.outcome_mint.to_account_info(),
+                from: ctx.accounts.holder_token_account.to_account_info(),
+                authority: ctx.accounts.holder.to_account_info(),
+            },
+        ),
+        tokens_in,
+    )?;
+
+    // The vault is owned by this program, so lamports can be moved without a CPI
+    **vault.try_borrow_mut_lamports()? = vault
+        .lamports()
+        .checked_sub(payout)
+        .ok_or(error!(ErrorCode::InsufficientVaultFunds))?;
+    let holder = ctx.accounts.holder.to_account_info();
+    **holder.try_borrow_mut_lamports()? = holder
+        .lamports()
+        .checked_add(payout)
+        .ok_or(error!(ErrorCode::MathOverflow))?;
+
+    ctx.accounts.market.load_mut()?.exit();
+
+    Ok(payout)
+}
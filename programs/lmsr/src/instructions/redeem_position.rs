@@ -0,0 +1,100 @@
+use anchor_lang::prelude::*;
+
+use crate::events::PositionRedeemed;
+use crate::state::{BettorPosition, Market};
+use common::constants::{MARKET_SEED, POSITION_SEED, VAULT_SEED};
+use common::utils::validate_vault;
+use common::{check_condition, errors::ErrorCode};
+
+#[derive(Accounts)]
+pub struct RedeemPosition<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, &market.load()?.label.as_bytes()],
+        bump = market.load()?.bump,
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    /// CHECK: Check PDA. Account with no data that stores lamports for the [`Market`] as its `reserves`
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, market.key().as_ref()],
+        bump = market.load()?.vault_bump,
+    )]
+    pub market_vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [POSITION_SEED, market.key().as_ref(), holder.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, BettorPosition>,
+}
+
+/// Redeem `shares` of an outcome from the holder's position in a resolved untokenized market and
+/// pay the holder out of the vault, the counterpart of `redeem`.
+/// See [`Market::redeem`] for the payout in normal and refund mode.
+///
+/// Emits [`PositionRedeemed`] with the realized profit or loss: the payout against the part of
+/// the position's cost basis the shares take with them.
+///
+/// Returns the lamports paid out.
+pub fn redeem_position(
+    ctx: Context<RedeemPosition>,
+    outcome_index: u8,
+    shares: u64,
+) -> Result<u64> {
+    validate_vault(
+        &ctx.accounts.market_vault,
+        &ctx.accounts.market.key(),
+        ctx.accounts.market.load()?.vault_bump,
+        ctx.program_id,
+    )?;
+
+    let vault = ctx.accounts.market_vault.to_account_info();
+    let vault_available = vault
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(vault.data_len()));
+
+    let (payout, cost_basis) = {
+        let mut market = ctx.accounts.market.load_mut()?;
+        market.validate_num_outcomes()?;
+        check_condition!(market.tokenized == 0, MarketIsTokenized);
+        market.enter()?;
+
+        let cost_basis = ctx.accounts.position.debit(outcome_index, shares)?;
+        (
+            market.redeem(outcome_index, shares, vault_available)?,
+            cost_basis,
+        )
+    };
+
+    // The vault is owned by this program, so lamports can be moved without a CPI
+    **vault.try_borrow_mut_lamports()? = vault
+        .lamports()
+        .checked_sub(payout)
+        .ok_or(error!(ErrorCode::InsufficientVaultFunds))?;
+    let holder = ctx.accounts.holder.to_account_info();
+    **holder.try_borrow_mut_lamports()? = holder
+        .lamports()
+        .checked_add(payout)
+        .ok_or(error!(ErrorCode::MathOverflow))?;
+
+    ctx.accounts.market.load_mut()?.exit();
+
+    emit!(PositionRedeemed {
+        market: ctx.accounts.market.key(),
+        holder: ctx.accounts.holder.key(),
+        outcome_index,
+        shares,
+        amount_out: payout,
+        cost_basis,
+        pnl: BettorPosition::realized_pnl(payout, cost_basis)?,
+        slot: Clock::get()?.slot,
+    });
+
+    Ok(payout)
+}
@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Market;
+use common::constants::{MARKET_SEED, VAULT_SEED};
+use common::utils::validate_vault;
+
+#[derive(Accounts)]
+pub struct ResolveFromOracle<'info> {
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, &market.load()?.label.as_bytes()],
+        bump = market.load()?.bump,
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    /// CHECK: Check PDA. Account with no data that stores lamports for the [`Market`] as its `reserves`
+    #[account(
+        seeds = [VAULT_SEED, market.key().as_ref()],
+        bump = market.load()?.vault_bump,
+    )]
+    pub market_vault: UncheckedAccount<'info>,
+
+    /// CHECK: Validated against the oracle committed to the [`Market`] at init.
+    pub oracle: UncheckedAccount<'info>,
+}
+
+/// Permissionlessly resolve the market to the outcome reported by its oracle,
+/// once `resolve_at` has passed. See [`Market::read_oracle`] for the expected data layout.
+/// The TVL fee is settled first, as in `resolve_market`.
+pub fn resolve_from_oracle(ctx: Context<ResolveFromOracle>) -> Result<()> {
+    validate_vault(
+        &ctx.accounts.market_vault,
+        &ctx.accounts.market.key(),
+        ctx.accounts.market.load()?.vault_bump,
+        ctx.program_id,
+    )?;
+
+    let vault = ctx.accounts.market_vault.to_account_info();
+    let vault_lamports = vault
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(vault.data_len()));
+
+    let mut market = ctx.accounts.market.load_mut()?;
+    market.validate_num_outcomes()?;
+    let oracle_data = ctx.accounts.oracle.try_borrow_data()?;
+
+    let now = Clock::get()?.unix_timestamp;
+    market.accrue_tvl_fee(vault_lamports, now)?;
+    market.resolve_from_oracle(ctx.accounts.oracle.key, &oracle_data, now)
+}
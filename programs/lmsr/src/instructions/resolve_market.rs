@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Market;
+use common::constants::{MARKET_SEED, VAULT_SEED};
+use common::utils::validate_vault;
+
+#[derive(Accounts)]
+pub struct ResolveMarket<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [MARKET_SEED, &market.load()?.label.as_bytes()],
+        bump = market.load()?.bump,
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    /// CHECK: Check PDA. Account with no data that stores lamports for the [`Market`] as its `reserves`
+    #[account(
+        seeds = [VAULT_SEED, market.key().as_ref()],
+        bump = market.load()?.vault_bump,
+    )]
+    pub market_vault: UncheckedAccount<'info>,
+}
+
+/// Resolve the market to `winning_outcome` once `resolve_at` has passed, or void it with
+/// [`common::constants::VOID_OUTCOME`] if the event can't be adjudicated.
+/// See [`Market::finalize_resolution`] for how an outcome nobody holds, or a void, is refunded.
+///
+/// The TVL fee is accrued up to now first, so it is settled before any winner redeems.
+pub fn resolve_market(ctx: Context<ResolveMarket>, winning_outcome: u8) -> Result<()> {
+    validate_vault(
+        &ctx.accounts.market_vault,
+        &ctx.accounts.market.key(),
+        ctx.accounts.market.load()?.vault_bump,
+        ctx.program_id,
+    )?;
+
+    let vault = ctx.accounts.market_vault.to_account_info();
+    let vault_lamports = vault
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(vault.data_len()));
+
+    let mut market = ctx.accounts.market.load_mut()?;
+    market.validate_num_outcomes()?;
+
+    let now = Clock::get()?.unix_timestamp;
+    market.accrue_tvl_fee(vault_lamports, now)?;
+    market.resolve(winning_outcome, now)
+}
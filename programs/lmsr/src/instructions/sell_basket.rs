@@ -0,0 +1,131 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token};
+
+use crate::state::Market;
+use crate::types::BasketLeg;
+use common::constants::{MARKET_SEED, OUTCOME_MINT_SEED, VAULT_SEED};
+use common::utils::validate_vault;
+use common::{check_condition, errors::ErrorCode};
+
+#[derive(Accounts)]
+pub struct SellBasket<'info> {
+    pub token_program: Program<'info, Token>,
+
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, &market.load()?.label.as_bytes()],
+        bump = market.load()?.bump,
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    /// CHECK: Check PDA. Account with no data that stores lamports for the [`Market`] as its `reserves`
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, market.key().as_ref()],
+        bump = market.load()?.vault_bump,
+    )]
+    pub market_vault: UncheckedAccount<'info>,
+}
+
+/// Sell shares of several outcomes atomically with one slippage bound on the total payout.
+/// See [`Market::sell_basket`] for how the leg order affects the individual payouts.
+///
+/// Remaining accounts, for each leg in `legs` order:
+/// * `outcome_mint` - PDA of [`OUTCOME_MINT_SEED`], the market and the outcome index (writable)
+/// * `seller_token_account` - the seller's token account of that mint (writable)
+///
+/// Returns the total lamports paid out, rejecting with `SlippageExceeded` if less than
+/// `min_total_out`.
+pub fn sell_basket<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SellBasket<'info>>,
+    legs: Vec<BasketLeg>,
+    min_total_out: u64,
+) -> Result<u64> {
+    check_condition!(
+        ctx.remaining_accounts.len() == 2 * legs.len(),
+        MissingRemainingAccount
+    );
+
+    validate_vault(
+        &ctx.accounts.market_vault,
+        &ctx.accounts.market.key(),
+        ctx.accounts.market.load()?.vault_bump,
+        ctx.program_id,
+    )?;
+
+    let vault = ctx.accounts.market_vault.to_account_info();
+    let vault_available = vault
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(vault.data_len()));
+
+    let (total_out, leg_tokens) = {
+        let mut market = ctx.accounts.market.load_mut()?;
+        market.validate_num_outcomes()?;
+        market.enter()?;
+
+        let now = Clock::get()?.unix_timestamp;
+        market.is_tradable(now)?;
+        market.accumulate_prices(now)?;
+
+        let total_out = market.sell_basket(&legs, now)?;
+        check_condition!(total_out >= min_total_out, SlippageExceeded);
+        check_condition!(
+            total_out <= market.tvl(vault_available),
+            InsufficientVaultFunds
+        );
+        market.track_consensus(now)?;
+
+        let leg_tokens = legs
+            .iter()
+            .map(|leg| market.shares_to_tokens(leg.shares))
+            .collect::<Result<Vec<u64>>>()?;
+        (total_out, leg_tokens)
+    };
+
+    let market_key = ctx.accounts.market.key();
+    for ((leg, tokens_in), accounts) in legs
+        .iter()
+        .zip(leg_tokens)
+        .zip(ctx.remaining_accounts.chunks(2))
+    {
+        let (outcome_mint, seller_token_account) = (&accounts[0], &accounts[1]);
+
+        let (expected_mint, _) = Pubkey::find_program_address(
+            &[OUTCOME_MINT_SEED, market_key.as_ref(), &[leg.outcome_index]],
+            ctx.program_id,
+        );
+        check_condition!(outcome_mint.key() == expected_mint, InvalidMintSeed);
+        check_condition!(*outcome_mint.owner == token::ID, OutcomeMintNotInitialized);
+
+        // The token program checks the account holds this mint and belongs to the seller
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Burn {
+                    mint: outcome_mint.clone(),
+                    from: seller_token_account.clone(),
+                    authority: ctx.accounts.seller.to_account_info(),
+                },
+            ),
+            tokens_in,
+        )?;
+    }
+
+    // The vault is owned by this program, so lamports can be moved without a CPI
+    **vault.try_borrow_mut_lamports()? = vault
+        .lamports()
+        .checked_sub(total_out)
+        .ok_or(error!(ErrorCode::InsufficientVaultFunds))?;
+    let seller = ctx.accounts.seller.to_account_info();
+    **seller.try_borrow_mut_lamports()? = seller
+        .lamports()
+        .checked_add(total_out)
+        .ok_or(error!(ErrorCode::MathOverflow))?;
+
+    ctx.accounts.market.load_mut()?.exit();
+
+    Ok(total_out)
+}
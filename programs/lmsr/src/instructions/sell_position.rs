@@ -0,0 +1,97 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{BettorPosition, Market};
+use common::constants::{MARKET_SEED, POSITION_SEED, VAULT_SEED};
+use common::utils::validate_vault;
+use common::{check_condition, errors::ErrorCode};
+
+#[derive(Accounts)]
+pub struct SellPosition<'info> {
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, &market.load()?.label.as_bytes()],
+        bump = market.load()?.bump,
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    /// CHECK: Check PDA. Account with no data that stores lamports for the [`Market`] as its `reserves`
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, market.key().as_ref()],
+        bump = market.load()?.vault_bump,
+    )]
+    pub market_vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [POSITION_SEED, market.key().as_ref(), seller.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, BettorPosition>,
+}
+
+/// Sell `shares` of an outcome from the seller's position in an untokenized market while trading
+/// is open, the counterpart of `sell_shares` burning them. The LMSR cost delta is paid out of
+/// the vault, and the shares' part of the cost basis comes off the position.
+///
+/// Returns the lamports paid out, rejecting with `SlippageExceeded` if fewer than `min_lamports_out`.
+pub fn sell_position(
+    ctx: Context<SellPosition>,
+    outcome_index: u8,
+    shares: u64,
+    min_lamports_out: u64,
+) -> Result<u64> {
+    validate_vault(
+        &ctx.accounts.market_vault,
+        &ctx.accounts.market.key(),
+        ctx.accounts.market.load()?.vault_bump,
+        ctx.program_id,
+    )?;
+
+    let vault = ctx.accounts.market_vault.to_account_info();
+    let vault_available = vault
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(vault.data_len()));
+
+    let payout = {
+        let mut market = ctx.accounts.market.load_mut()?;
+        market.validate_num_outcomes()?;
+        check_condition!(market.tokenized == 0, MarketIsTokenized);
+        market.enter()?;
+
+        let now = Clock::get()?.unix_timestamp;
+        market.is_tradable(now)?;
+        market.accumulate_prices(now)?;
+
+        let position = &mut ctx.accounts.position;
+        position.debit(outcome_index, shares)?;
+        position.last_trade_at = now;
+        let payout = market.sell_shares(outcome_index, shares, now)?;
+        check_condition!(payout >= min_lamports_out, SlippageExceeded);
+        check_condition!(
+            payout <= market.tvl(vault_available),
+            InsufficientVaultFunds
+        );
+        market.track_consensus(now)?;
+
+        payout
+    };
+
+    // The vault is owned by this program, so lamports can be moved without a CPI
+    **vault.try_borrow_mut_lamports()? = vault
+        .lamports()
+        .checked_sub(payout)
+        .ok_or(error!(ErrorCode::InsufficientVaultFunds))?;
+    let seller = ctx.accounts.seller.to_account_info();
+    **seller.try_borrow_mut_lamports()? = seller
+        .lamports()
+        .checked_add(payout)
+        .ok_or(error!(ErrorCode::MathOverflow))?;
+
+    ctx.accounts.market.load_mut()?.exit();
+
+    Ok(payout)
+}
@@ -0,0 +1,139 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount};
+
+use crate::state::{BettorPosition, Market};
+use common::constants::{MARKET_SEED, OUTCOME_MINT_SEED, POSITION_SEED, VAULT_SEED};
+use common::utils::validate_vault;
+use common::{check_condition, errors::ErrorCode};
+
+#[derive(Accounts)]
+#[instruction(outcome_index: u8)]
+pub struct SellShares<'info> {
+    pub token_program: Program<'info, Token>,
+
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, &market.load()?.label.as_bytes()],
+        bump = market.load()?.bump,
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    /// CHECK: Check PDA. Account with no data that stores lamports for the [`Market`] as its `reserves`
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, market.key().as_ref()],
+        bump = market.load()?.vault_bump,
+    )]
+    pub market_vault: UncheckedAccount<'info>,
+
+    /// CHECK: Check PDA. Only this program can create an account at the outcome mint PDA, so one
+    /// owned by the token program is the outcome's mint. Checked here rather than deserialized as
+    /// a `Mint` so a missing (lazy, see `init_outcome_mint`) mint fails with a clear error before
+    /// any token account is touched.
+    #[account(
+        mut,
+        seeds = [OUTCOME_MINT_SEED, market.key().as_ref(), &[outcome_index]],
+        bump,
+        owner = token::ID @ ErrorCode::OutcomeMintNotInitialized,
+    )]
+    pub outcome_mint: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = outcome_mint,
+        associated_token::authority = seller,
+        associated_token::token_program = token_program,
+    )]
+    pub seller_token_account: Account<'info, TokenAccount>,
+
+    /// The seller's [`BettorPosition`] in the market, if they track their trades in one (see
+    /// `BuyShares::position`). Omit it to trade without tracking.
+    #[account(
+        mut,
+        seeds = [POSITION_SEED, market.key().as_ref(), seller.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Option<Account<'info, BettorPosition>>,
+}
+
+/// Sell `shares` of an outcome back to the market while trading is open.
+/// The shares are burned and the LMSR cost delta is paid out of the vault.
+///
+/// Pass the seller's `position` to take the shares and their part of the cost basis off it. Tokens
+/// transferred in were never recorded there, so at most the shares the position holds come off.
+///
+/// Returns the lamports paid out, rejecting with `SlippageExceeded` if fewer than `min_lamports_out`.
+pub fn sell_shares(
+    ctx: Context<SellShares>,
+    outcome_index: u8,
+    shares: u64,
+    min_lamports_out: u64,
+) -> Result<u64> {
+    validate_vault(
+        &ctx.accounts.market_vault,
+        &ctx.accounts.market.key(),
+        ctx.accounts.market.load()?.vault_bump,
+        ctx.program_id,
+    )?;
+
+    let vault = ctx.accounts.market_vault.to_account_info();
+    let vault_available = vault
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(vault.data_len()));
+
+    let now = Clock::get()?.unix_timestamp;
+    let (payout, tokens_in) = {
+        let mut market = ctx.accounts.market.load_mut()?;
+        market.validate_num_outcomes()?;
+        market.enter()?;
+
+        market.is_tradable(now)?;
+        market.accumulate_prices(now)?;
+
+        let payout = market.sell_shares(outcome_index, shares, now)?;
+        check_condition!(payout >= min_lamports_out, SlippageExceeded);
+        check_condition!(
+            payout <= market.tvl(vault_available),
+            InsufficientVaultFunds
+        );
+        market.track_consensus(now)?;
+
+        (payout, market.shares_to_tokens(shares)?)
+    };
+
+    token::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token::Burn {
+                mint: ctx.accounts.outcome_mint.to_account_info(),
+                from: ctx.accounts.seller_token_account.to_account_info(),
+                authority: ctx.accounts.seller.to_account_info(),
+            },
+        ),
+        tokens_in,
+    )?;
+
+    // The vault is owned by this program, so lamports can be moved without a CPI
+    **vault.try_borrow_mut_lamports()? = vault
+        .lamports()
+        .checked_sub(payout)
+        .ok_or(error!(ErrorCode::InsufficientVaultFunds))?;
+    let seller = ctx.accounts.seller.to_account_info();
+    **seller.try_borrow_mut_lamports()? = seller
+        .lamports()
+        .checked_add(payout)
+        .ok_or(error!(ErrorCode::MathOverflow))?;
+
+    if let Some(position) = ctx.accounts.position.as_mut() {
+        let held = position.shares[outcome_index as usize];
+        position.debit(outcome_index, shares.min(held))?;
+        position.last_trade_at = now;
+    }
+
+    ctx.accounts.market.load_mut()?.exit();
+
+    Ok(payout)
+}
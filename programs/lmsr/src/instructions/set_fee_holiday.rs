@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Market;
+use common::constants::MARKET_SEED;
+
+#[derive(Accounts)]
+pub struct SetFeeHoliday<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [MARKET_SEED, &market.load()?.label.as_bytes()],
+        bump = market.load()?.bump,
+    )]
+    pub market: AccountLoader<'info, Market>,
+}
+
+/// Waive trade fees until `fee_free_until`. See [`Market::set_fee_holiday`].
+pub fn set_fee_holiday(ctx: Context<SetFeeHoliday>, fee_free_until: i64) -> Result<()> {
+    let mut market = ctx.accounts.market.load_mut()?;
+    market.validate_num_outcomes()?;
+
+    market.set_fee_holiday(fee_free_until)
+}
@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Market;
+use common::constants::MARKET_SEED;
+
+#[derive(Accounts)]
+pub struct SetTradingWindow<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [MARKET_SEED, &market.load()?.label.as_bytes()],
+        bump = market.load()?.bump,
+    )]
+    pub market: AccountLoader<'info, Market>,
+}
+
+/// Schedule when trading opens. See [`Market::set_trading_window`].
+pub fn set_trading_window(ctx: Context<SetTradingWindow>, trading_open_at: i64) -> Result<()> {
+    let mut market = ctx.accounts.market.load_mut()?;
+    market.validate_num_outcomes()?;
+
+    market.set_trading_window(trading_open_at)
+}
@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Market;
+use common::constants::MARKET_SEED;
+
+#[derive(Accounts)]
+pub struct SnapshotPrices<'info> {
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, &market.load()?.label.as_bytes()],
+        bump = market.load()?.bump,
+    )]
+    pub market: AccountLoader<'info, Market>,
+}
+
+/// Permissionless crank that stores the current prices as the reference for
+/// [`Market::price_delta_since_snapshot`]. See [`Market::snapshot_prices`] for the rate limit.
+pub fn snapshot_prices(ctx: Context<SnapshotPrices>) -> Result<()> {
+    let mut market = ctx.accounts.market.load_mut()?;
+    market.validate_num_outcomes()?;
+
+    let now = Clock::get()?.unix_timestamp;
+    market.snapshot_prices(now)
+}
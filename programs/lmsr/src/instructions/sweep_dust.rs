@@ -0,0 +1,68 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Market;
+use common::constants::{MARKET_SEED, VAULT_SEED};
+use common::errors::ErrorCode;
+use common::utils::validate_vault;
+
+#[derive(Accounts)]
+pub struct SweepDust<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [MARKET_SEED, &market.load()?.label.as_bytes()],
+        bump = market.load()?.bump,
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    /// CHECK: Check PDA. Account with no data that stores lamports for the [`Market`] as its `reserves`
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, market.key().as_ref()],
+        bump = market.load()?.vault_bump,
+    )]
+    pub market_vault: UncheckedAccount<'info>,
+}
+
+/// Move the vault's rounding surplus to the admin, never taking the vault below the collateral
+/// the market requires, and take it off `accrued_rounding_surplus`. See
+/// [`Market::sweepable_surplus`].
+///
+/// Returns the lamports swept.
+pub fn sweep_dust(ctx: Context<SweepDust>) -> Result<u64> {
+    validate_vault(
+        &ctx.accounts.market_vault,
+        &ctx.accounts.market.key(),
+        ctx.accounts.market.load()?.vault_bump,
+        ctx.program_id,
+    )?;
+
+    let vault = ctx.accounts.market_vault.to_account_info();
+    let vault_lamports = vault
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(vault.data_len()));
+
+    let dust = {
+        let mut market = ctx.accounts.market.load_mut()?;
+        market.validate_num_outcomes()?;
+        let dust = market.sweepable_surplus(vault_lamports)?;
+        market.accrued_rounding_surplus -= dust;
+        dust
+    };
+
+    // The vault is owned by this program, so lamports can be moved without a CPI
+    **vault.try_borrow_mut_lamports()? = vault
+        .lamports()
+        .checked_sub(dust)
+        .ok_or(error!(ErrorCode::InsufficientVaultFunds))?;
+    let admin = ctx.accounts.admin.to_account_info();
+    **admin.try_borrow_mut_lamports()? = admin
+        .lamports()
+        .checked_add(dust)
+        .ok_or(error!(ErrorCode::MathOverflow))?;
+
+    Ok(dust)
+}
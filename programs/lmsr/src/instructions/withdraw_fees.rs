@@ -0,0 +1,74 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Market;
+use common::check_condition;
+use common::constants::{MARKET_SEED, VAULT_SEED};
+use common::errors::ErrorCode;
+use common::utils::validate_vault;
+
+#[derive(Accounts)]
+pub struct WithdrawFees<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [MARKET_SEED, &market.load()?.label.as_bytes()],
+        bump = market.load()?.bump,
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    /// CHECK: Check PDA. Account with no data that stores lamports for the [`Market`] as its `reserves`
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, market.key().as_ref()],
+        bump = market.load()?.vault_bump,
+    )]
+    pub market_vault: UncheckedAccount<'info>,
+
+    /// Receives the fees, chosen by the admin
+    #[account(mut)]
+    pub fee_recipient: SystemAccount<'info>,
+}
+
+/// Move the `accrued_protocol_fees` out of the vault to `fee_recipient` and reset the counter.
+///
+/// The fees are already excluded from the TVL, so this leaves every trader's claim untouched. The
+/// rounding surplus is separate and goes through `sweep_dust`.
+///
+/// Returns the lamports withdrawn.
+pub fn withdraw_fees(ctx: Context<WithdrawFees>) -> Result<u64> {
+    validate_vault(
+        &ctx.accounts.market_vault,
+        &ctx.accounts.market.key(),
+        ctx.accounts.market.load()?.vault_bump,
+        ctx.program_id,
+    )?;
+
+    let vault = ctx.accounts.market_vault.to_account_info();
+    let vault_lamports = vault
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(vault.data_len()));
+
+    let fees = {
+        let mut market = ctx.accounts.market.load_mut()?;
+        market.validate_num_outcomes()?;
+        let fees = market.accrued_protocol_fees.min(vault_lamports);
+        market.accrued_protocol_fees -= fees;
+        fees
+    };
+    check_condition!(fees > 0, NothingToWithdraw);
+
+    // The vault is owned by this program, so lamports can be moved without a CPI
+    **vault.try_borrow_mut_lamports()? = vault
+        .lamports()
+        .checked_sub(fees)
+        .ok_or(error!(ErrorCode::InsufficientVaultFunds))?;
+    let recipient = ctx.accounts.fee_recipient.to_account_info();
+    **recipient.try_borrow_mut_lamports()? = recipient
+        .lamports()
+        .checked_add(fees)
+        .ok_or(error!(ErrorCode::MathOverflow))?;
+
+    Ok(fees)
+}
@@ -8,6 +8,9 @@ use anchor_lang::prelude::*;
 use instructions::*;
 use types::*;
 
+#[cfg(feature = "client")]
+pub mod client;
+pub mod events;
 pub mod instructions;
 pub mod state;
 pub mod types;
@@ -18,14 +21,224 @@ declare_id!("JDP9AsSqpzeea8yqscvMHU7gkvC7QR16UF35hf74tAFG");
 pub mod lmsr {
     use super::*;
 
-    /// Create a new market with N outcomes
+    /// Create a new market with N outcomes, with all outcome mints or none (see `init_outcome_mint`)
     pub fn init_market<'info>(
         ctx: Context<'_, '_, 'info, 'info, InitMarket<'info>>,
-        num_outcomes: u8,
-        scale: u64,
-        resolve_at: i64,
-        label: FixedSizeString,
+        params: InitMarketParams,
     ) -> Result<()> {
-        instructions::init_market(ctx, num_outcomes, scale, resolve_at, label)
+        instructions::init_market(ctx, params)
+    }
+
+    /// Create the mint of one outcome of a market initialized without its mints
+    pub fn init_outcome_mint(ctx: Context<InitOutcomeMint>, outcome_index: u8) -> Result<()> {
+        instructions::init_outcome_mint(ctx, outcome_index)
+    }
+
+    /// Create the program-wide registry that `init_market` lists new markets in
+    pub fn init_registry(ctx: Context<InitRegistry>) -> Result<()> {
+        instructions::init_registry(ctx)
+    }
+
+    /// Create several markets atomically, either all of them exist afterwards or none do
+    pub fn batch_init_markets<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchInitMarkets<'info>>,
+        params: Vec<InitMarketParams>,
+    ) -> Result<()> {
+        instructions::batch_init_markets(ctx, params)
+    }
+
+    /// Buy shares of an outcome, returning the shares minted
+    pub fn buy_shares(
+        ctx: Context<BuyShares>,
+        outcome_index: u8,
+        amount_in: u64,
+        min_shares_out: u64,
+        max_price_impact_bps: u16,
+        client_order_id: Option<u64>,
+    ) -> Result<u64> {
+        instructions::buy_shares(
+            ctx,
+            outcome_index,
+            amount_in,
+            min_shares_out,
+            max_price_impact_bps,
+            client_order_id,
+        )
+    }
+
+    /// Buy shares with up to `amount_in` lamports, filling what the market can take and leaving
+    /// the rest with the buyer
+    pub fn buy_capped(
+        ctx: Context<BuyShares>,
+        outcome_index: u8,
+        amount_in: u64,
+        min_shares_out: u64,
+    ) -> Result<CappedFill> {
+        instructions::buy_capped(ctx, outcome_index, amount_in, min_shares_out)
+    }
+
+    /// Buy exactly `shares_out` shares of an outcome, returning the lamports charged
+    pub fn buy_exact_shares(
+        ctx: Context<BuyShares>,
+        outcome_index: u8,
+        shares_out: u64,
+        max_amount_in: u64,
+    ) -> Result<u64> {
+        instructions::buy_exact_shares(ctx, outcome_index, shares_out, max_amount_in)
+    }
+
+    /// Buy shares of an outcome of an untokenized market into the buyer's position, returning
+    /// the shares bought
+    pub fn buy_position(
+        ctx: Context<BuyPosition>,
+        outcome_index: u8,
+        amount_in: u64,
+        min_shares_out: u64,
+        max_price_impact_bps: u16,
+    ) -> Result<u64> {
+        instructions::buy_position(
+            ctx,
+            outcome_index,
+            amount_in,
+            min_shares_out,
+            max_price_impact_bps,
+        )
+    }
+
+    /// Sell shares of one outcome and buy another with the payout, returning the shares bought
+    pub fn flip(
+        ctx: Context<Flip>,
+        from_outcome: u8,
+        to_outcome: u8,
+        shares: u64,
+        min_target_shares: u64,
+    ) -> Result<u64> {
+        instructions::flip(ctx, from_outcome, to_outcome, shares, min_target_shares)
+    }
+
+    /// Sell shares of several outcomes at once, returning the total lamports paid out
+    pub fn sell_basket<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SellBasket<'info>>,
+        legs: Vec<BasketLeg>,
+        min_total_out: u64,
+    ) -> Result<u64> {
+        instructions::sell_basket(ctx, legs, min_total_out)
+    }
+
+    /// Sell shares of an outcome back to the market, returning the lamports paid out
+    pub fn sell_shares(
+        ctx: Context<SellShares>,
+        outcome_index: u8,
+        shares: u64,
+        min_lamports_out: u64,
+    ) -> Result<u64> {
+        instructions::sell_shares(ctx, outcome_index, shares, min_lamports_out)
+    }
+
+    /// Sell shares of an outcome from the seller's position in an untokenized market, returning
+    /// the lamports paid out
+    pub fn sell_position(
+        ctx: Context<SellPosition>,
+        outcome_index: u8,
+        shares: u64,
+        min_lamports_out: u64,
+    ) -> Result<u64> {
+        instructions::sell_position(ctx, outcome_index, shares, min_lamports_out)
+    }
+
+    /// Resolve the market to a winning outcome, or void it with `VOID_OUTCOME`
+    pub fn resolve_market(ctx: Context<ResolveMarket>, winning_outcome: u8) -> Result<()> {
+        instructions::resolve_market(ctx, winning_outcome)
+    }
+
+    /// Resolve the market to the winning outcome reported by its oracle account
+    pub fn resolve_from_oracle(ctx: Context<ResolveFromOracle>) -> Result<()> {
+        instructions::resolve_from_oracle(ctx)
+    }
+
+    /// Once `resolve_at` has passed, resolve the market to the outcome that has held consensus
+    /// prices long enough
+    pub fn resolve_by_consensus(ctx: Context<ResolveByConsensus>) -> Result<()> {
+        instructions::resolve_by_consensus(ctx)
+    }
+
+    /// Burn outcome shares of a resolved market for lamports, returning the lamports paid out
+    pub fn redeem(ctx: Context<Redeem>, outcome_index: u8, shares: u64) -> Result<u64> {
+        instructions::redeem(ctx, outcome_index, shares)
+    }
+
+    /// Redeem shares from the holder's position in a resolved untokenized market, returning the
+    /// lamports paid out
+    pub fn redeem_position(
+        ctx: Context<RedeemPosition>,
+        outcome_index: u8,
+        shares: u64,
+    ) -> Result<u64> {
+        instructions::redeem_position(ctx, outcome_index, shares)
+    }
+
+    /// Waive trade fees until `fee_free_until`, at most `resolve_at`
+    pub fn set_fee_holiday(ctx: Context<SetFeeHoliday>, fee_free_until: i64) -> Result<()> {
+        instructions::set_fee_holiday(ctx, fee_free_until)
+    }
+
+    /// Set when trading opens; it closes at `trading_close_at()`, `trading_halt_before_resolve`
+    /// seconds before `resolve_at`
+    pub fn set_trading_window(ctx: Context<SetTradingWindow>, trading_open_at: i64) -> Result<()> {
+        instructions::set_trading_window(ctx, trading_open_at)
+    }
+
+    /// Record the current prices as the reference for price change indicators, at most once a day
+    pub fn snapshot_prices(ctx: Context<SnapshotPrices>) -> Result<()> {
+        instructions::snapshot_prices(ctx)
+    }
+
+    /// Set aside the pro-rated TVL fee since the last accrual
+    pub fn accrue_tvl_fee(ctx: Context<AccrueTvlFee>) -> Result<u64> {
+        instructions::accrue_tvl_fee(ctx)
+    }
+
+    /// Withdraw the rounding surplus the vault kept above the cost function to the admin
+    pub fn sweep_dust(ctx: Context<SweepDust>) -> Result<u64> {
+        instructions::sweep_dust(ctx)
+    }
+
+    /// Withdraw the accrued protocol fees to a recipient chosen by the admin
+    pub fn withdraw_fees(ctx: Context<WithdrawFees>) -> Result<u64> {
+        instructions::withdraw_fees(ctx)
+    }
+
+    /// Withdraw vault lamports no trader can claim, such as SOL sent directly to the vault
+    pub fn admin_withdraw_excess(ctx: Context<AdminWithdrawExcess>) -> Result<u64> {
+        instructions::admin_withdraw_excess(ctx)
+    }
+
+    /// Get the LMSR cost function of the market in lamports
+    pub fn get_cost(ctx: Context<GetCost>) -> Result<u64> {
+        instructions::get_cost(ctx)
+    }
+
+    /// Get the lamports needed to buy the market back to uniform prices
+    pub fn get_cost_to_balance(ctx: Context<GetCostToBalance>) -> Result<u64> {
+        instructions::get_cost_to_balance(ctx)
+    }
+
+    /// Get the market maker's worst-case loss `b * ln(N)` in lamports
+    pub fn get_max_loss(ctx: Context<GetMaxLoss>) -> Result<u64> {
+        instructions::get_max_loss(ctx)
+    }
+
+    /// Get the LMSR price of an outcome, scaled by 1e9
+    pub fn get_price(ctx: Context<GetPrice>, outcome_index: u8) -> Result<u64> {
+        instructions::get_price(ctx, outcome_index)
+    }
+
+    /// Quote a buy for other programs: the shares it would mint and the resulting price
+    pub fn quote_cpi(
+        ctx: Context<QuoteCpi>,
+        outcome_index: u8,
+        amount_in: u64,
+    ) -> Result<BuyQuote> {
+        instructions::quote_cpi(ctx, outcome_index, amount_in)
     }
 }
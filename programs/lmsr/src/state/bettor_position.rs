@@ -0,0 +1,99 @@
+use anchor_lang::prelude::*;
+use common::constants::{D9_U128, MAX_OUTCOMES};
+use common::{check_condition, errors::ErrorCode};
+
+/// Shares one owner holds in an untokenized [`crate::state::Market`], which records positions
+/// instead of minting outcome tokens (see `InitMarketParams::tokenized`).
+///
+/// PDA of [`common::constants::POSITION_SEED`], the market and the owner. Unlike outcome tokens a
+/// position can't be transferred or used by other programs, only bought into, sold from and
+/// redeemed by its owner. That also makes it the place to track what the owner paid, which
+/// `cost_basis` records for profit and loss displays. One account holds every outcome, so a trader
+/// pays rent once per market rather than once per outcome.
+///
+/// A trader in a tokenized market can keep one too, passing it to `buy_shares` and `sell_shares`
+/// to track their cost basis. There it is only a record of the owner's own trades: the tokens
+/// are what pays out, and any transferred in or out never show up in it.
+#[account]
+#[derive(InitSpace, Default)]
+pub struct BettorPosition {
+    /// The [`crate::state::Market`] the shares are in
+    pub market: Pubkey,
+
+    /// The only signer that can sell or redeem the shares
+    pub owner: Pubkey,
+
+    /// Shares held of each outcome, in the same 1e9 units as `Market::supplies`
+    pub shares: [u64; MAX_OUTCOMES],
+
+    /// Lamports paid for the `shares` still held of each outcome, fees included. Sells and
+    /// redemptions take it down pro rata, so the average price paid per share is unchanged.
+    pub cost_basis: [u64; MAX_OUTCOMES],
+
+    /// When the owner last bought into or sold from the position
+    pub last_trade_at: i64,
+
+    /// Bump for this [`BettorPosition`]
+    pub bump: u8,
+}
+
+impl BettorPosition {
+    pub const SIZE: usize = 8 + BettorPosition::INIT_SPACE;
+
+    /// Record `shares` of an outcome bought into the position for `cost` lamports
+    pub fn credit(&mut self, outcome_index: u8, shares: u64, cost: u64) -> Result<()> {
+        let i = outcome_index as usize;
+        check_condition!(i < MAX_OUTCOMES, InvalidOutcomeIndex);
+        self.shares[i] = self.shares[i]
+            .checked_add(shares)
+            .ok_or(error!(ErrorCode::MathOverflow))?;
+        self.cost_basis[i] = self.cost_basis[i]
+            .checked_add(cost)
+            .ok_or(error!(ErrorCode::MathOverflow))?;
+
+        Ok(())
+    }
+
+    /// Remove `shares` of an outcome sold or redeemed from the position, along with their share
+    /// of the cost basis, rounded down. Debiting everything held clears the basis.
+    ///
+    /// Returns the cost basis removed.
+    pub fn debit(&mut self, outcome_index: u8, shares: u64) -> Result<u64> {
+        let i = outcome_index as usize;
+        check_condition!(i < MAX_OUTCOMES, InvalidOutcomeIndex);
+        let held = self.shares[i];
+        check_condition!(shares <= held, InsufficientPositionShares);
+
+        let basis = if shares == held {
+            self.cost_basis[i]
+        } else {
+            (self.cost_basis[i] as u128 * shares as u128 / held as u128) as u64
+        };
+        self.shares[i] -= shares;
+        self.cost_basis[i] -= basis;
+
+        Ok(basis)
+    }
+
+    /// Profit or loss in lamports of `proceeds` received for shares that cost `cost_basis`, as
+    /// [`BettorPosition::debit`] returns it when they leave the position.
+    pub fn realized_pnl(proceeds: u64, cost_basis: u64) -> Result<i64> {
+        i64::try_from(proceeds as i128 - cost_basis as i128)
+            .map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+
+    /// Profit or loss in lamports if the shares held of an outcome were worth `current_price`
+    /// each (scaled by 1e9, as `Market::price` returns), against what was paid for them.
+    ///
+    /// This marks to the marginal price, so it overstates what selling the whole position would
+    /// pay: a sell moves the price down as it goes.
+    pub fn unrealized_pnl(&self, outcome_index: u8, current_price: u64) -> Result<i64> {
+        let i = outcome_index as usize;
+        check_condition!(i < MAX_OUTCOMES, InvalidOutcomeIndex);
+
+        // Shares are 1e9 units per whole share, so shares * price / 1e9 is in lamports
+        let value = self.shares[i] as i128 * current_price as i128 / D9_U128 as i128;
+        i64::try_from(value - self.cost_basis[i] as i128)
+            .map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+}
@@ -1,16 +1,30 @@
-use crate::types::FixedSizeString;
+use crate::types::{
+    BasketLeg, BuyQuote, FeeConfig, FixedSizeString, MarketHealth, MarketResolution,
+    MarketSnapshot, OracleReport,
+};
 use anchor_lang::prelude::*;
 use common::check_condition;
 use common::constants::common::*;
-use common::constants::MAX_OUTCOMES;
+use common::constants::{MARKET_ACCOUNT_SIZE, MAX_OUTCOMES};
 use common::errors::ErrorCode;
+use spl_math::uint::U256;
 
+/// `zero_copy` already derives `Clone` and `Copy`. `Debug` is only derived for off-chain tooling,
+/// and like any derive it doesn't touch the `#[repr(C)]` layout.
 #[account(zero_copy)]
 #[derive(InitSpace, Default)]
+#[cfg_attr(feature = "client", derive(Debug))]
 #[repr(C)]
 pub struct Market {
     /// Reserves for each outcome, fixed-point scaled.
     /// All values stored as u64 but promoted to u128 for math.
+    ///
+    /// Accounting model: the lamports themselves are pooled in the vault, since LMSR collateral is
+    /// fungible across outcomes. `reserves` is a per-outcome ledger of net lamports deposited,
+    /// used for the reserve cap and refund mode, and `Σ reserves` always equals the net deposits.
+    /// Buys credit the bought outcome. Sells pay out the cost delta, which can exceed what the
+    /// outcome's ledger holds once trades on other outcomes move the cost, so the excess is
+    /// debited from the other outcomes (see [`Market::sell_shares`]).
     pub reserves: [u64; MAX_OUTCOMES],
 
     /// Outcome mint token supplies for each outcome, fixed-point scaled.
@@ -18,6 +32,15 @@ pub struct Market {
     /// Each outcome has a unique mint but all have the same decimals, so this is safe to apply generic math to.
     pub supplies: [u64; MAX_OUTCOMES],
 
+    /// Prices of each outcome at `snapshot_at`, scaled by 1e9.
+    /// Reference point for [`Market::price_delta_since_snapshot`].
+    pub price_snapshot: [u64; MAX_OUTCOMES],
+
+    /// TWAP accumulator: `Σ price(i) * seconds` (price scaled by 1e9) since the market opened,
+    /// brought up to date before every trade by [`Market::accumulate_prices`].
+    /// Wraps on overflow like a Uniswap v2 accumulator, only deltas are meaningful.
+    pub price_cumulative: [u64; MAX_OUTCOMES],
+
     /// LMSR liquidity parameter 'b' (in lamports)
     /// Controls market depth - higher values mean more liquidity and smaller price impact
     pub scale: u64,
@@ -27,9 +50,41 @@ pub struct Market {
     /// When the market will resolve and halt trading
     pub resolve_at: i64,
 
+    /// When trading opens. Trades are only allowed in `[trading_open_at, trading_close_at())`,
+    /// see [`Market::trading_close_at`].
+    pub trading_open_at: i64,
+
+    /// When `price_snapshot` was last taken, zero if never
+    pub snapshot_at: i64,
+
+    /// When `price_cumulative` was last brought up to date
+    pub price_cumulative_at: i64,
+
+    /// Lamports the admin seeded into the vault at init, separate from the outcome supplies.
+    /// Zero if the market was created without initial liquidity.
+    pub initial_liquidity: u64,
+
+    /// Circuit breaker: maximum lamports a single outcome's `reserves` may reach.
+    /// Keeps the market inside the numerically-safe range of `fp_exp`. Zero disables the cap.
+    pub max_outcome_reserve: u64,
+
+    /// Buy, sell, creation and TVL fees, validated against their `MAX_*` caps at init
+    pub fee_config: FeeConfig,
+
+    /// Lamports of the vault set aside as explicit fees: trade fees (see [`FeeConfig`]), the
+    /// creation fee and [`Market::accrue_tvl_fee`]. They stay in the vault, excluded from the TVL
+    /// and from redemptions, until `withdraw_fees` sends them to a fee recipient.
+    pub accrued_protocol_fees: u64,
+
+    /// When the TVL fee was last accrued
+    pub last_fee_accrual: i64,
+
     /// The admin of the market who can mutate it
     pub admin: Pubkey,
 
+    /// Oracle account committed at init that may resolve the market, `Pubkey::default()` if none
+    pub oracle: Pubkey,
+
     pub label: FixedSizeString,
 
     /// Number of outcomes (N)
@@ -41,37 +96,476 @@ pub struct Market {
     /// Bump for market_vault which contains SOL reserves on behalf of the [`Market`]
     pub vault_bump: u8,
 
+    /// 1 once the market has been resolved and trading has halted
+    pub resolved: u8,
+
+    /// Index of the winning outcome, only meaningful once `resolved`. [`VOID_OUTCOME`] if the
+    /// market was voided, see [`Market::resolution`].
+    pub winning_outcome: u8,
+
+    /// 1 if the market was voided or resolved to an outcome nobody holds, in which case every
+    /// outcome's shares are refunded pro rata from that outcome's `reserves` instead of paying
+    /// winners
+    pub refund_mode: u8,
+
+    /// Bit `i` is set once the mint of outcome `i` exists. Trading is blocked until every
+    /// outcome's bit is set, see [`Market::mints_ready`]. Untokenized markets have no mints to
+    /// wait for, so every bit is set at init.
+    pub mints_initialized: u16,
+
+    /// 1 if the LMSR math runs at 1e18 instead of 1e9, see [`Market::fp_one`].
+    ///
+    /// Only the intermediate exp/ln values change scale. `supplies` stay in 1e9 units whatever the
+    /// outcome mint decimals, and `reserves` stay in lamports, so both keep fitting in u64 and the
+    /// account layout is the same for every market.
+    pub high_precision: u8,
+
+    /// 1 while an instruction that CPIs mid-mutation is running, see [`Market::enter`]
+    pub in_progress: u8,
+
+    /// 1 if shares are SPL tokens of the outcome mints, 0 if they are recorded in
+    /// [`crate::state::BettorPosition`] accounts instead. The LMSR math is the same either way.
+    pub tokenized: u8,
+
+    /// Outcome priced at or above [`OUTCOME_CONSENSUS_PERCENTAGE_THRESHOLD`] since
+    /// `consensus_since`, only meaningful while that is non-zero
+    pub consensus_outcome: u8,
+
+    /// Decimals of the outcome mints, 0 for the default [`OUTCOME_MINT_DECIMALS`]. Only the
+    /// token amounts depend on it, see [`Market::share_unit`].
+    pub outcome_decimals: u8,
+
     /// Padding for zero copy alignment
-    pub _padding: [u8; 13],
+    pub _padding: [u8; 3],
+
+    /// When `consensus_outcome` last crossed the consensus threshold, zero while no outcome is
+    /// above it. See [`Market::track_consensus`].
+    pub consensus_since: i64,
+
+    /// Lamports the market kept from fixed-point rounding on buys, the part of the deposit above
+    /// the cost delta. A sell keeps less than a lamport and adds nothing. Unlike
+    /// `accrued_protocol_fees` they stay in the TVL until `sweep_dust` drains them, see
+    /// [`Market::sweepable_surplus`].
+    pub accrued_rounding_surplus: u64,
+
+    /// Trades before this unix timestamp pay no buy or sell fee, see [`Market::trade_fees`].
+    /// At most `resolve_at`, zero for no fee holiday.
+    pub fee_free_until: i64,
+
+    /// Lamports the admin paid into the vault at init to subsidize the market. Unlike
+    /// `initial_liquidity` they can't be withdrawn as excess or swept as dust while they may back
+    /// a payout, see [`Market::locked_subsidy`]. Winners redeem them pro rata with the rest of
+    /// the vault, so a subsidized market can pay out more than traders paid in.
+    pub subsidy: u64,
+
+    /// Seconds before `resolve_at` during which trading is halted, so that nobody (the admin
+    /// included) can trade on a result learned just before resolution. Zero trades until
+    /// `resolve_at`.
+    pub trading_halt_before_resolve: i64,
 }
 
+// Off-chain decoders rely on `MARKET_LAYOUT`, which the tests check field by field
+const _: () = assert!(Market::SIZE == MARKET_ACCOUNT_SIZE);
+
 impl Market {
     pub const SIZE: usize = 8 + Market::INIT_SPACE;
+
+    // Byte offsets of fields in the account data (including the 8 byte discriminator), for
+    // `getProgramAccounts` memcmp filters. `#[repr(C)]` zero copy fixes the layout, so these only
+    // change if fields are reordered. See `client::market_filters` with the `client` feature.
+    // Every field is listed in `MARKET_LAYOUT`.
+
+    /// Offset of `resolve_at` (i64, little endian)
+    pub const RESOLVE_AT_OFFSET: usize = 8 + std::mem::offset_of!(Market, resolve_at);
+
+    /// Offset of `admin` (32 byte pubkey)
+    pub const ADMIN_OFFSET: usize = 8 + std::mem::offset_of!(Market, admin);
+
+    /// Offset of `resolved` (u8, 0 or 1)
+    pub const RESOLVED_OFFSET: usize = 8 + std::mem::offset_of!(Market, resolved);
+
+    /// An in-memory market ready to trade, for tests that exercise the math without deploying
+    /// the program. Every outcome mint counts as initialized and trading opens at time zero.
+    #[cfg(feature = "test-utils")]
+    pub fn new_for_test(num_outcomes: u8, scale: u64, resolve_at: i64) -> Result<Market> {
+        check_condition!(scale > 0, LiquidityParameterIsZero);
+        let mut market = Market {
+            num_outcomes,
+            scale,
+            resolve_at,
+            ..Default::default()
+        };
+        market.validate_num_outcomes()?;
+        market.mints_initialized = market.all_mints_mask();
+
+        Ok(market)
+    }
+
+    /// Invariant checked at the top of every mutating instruction, before any math runs.
+    ///
+    /// `num_outcomes` is only written by `init_market` but bounds every loop and index into
+    /// `supplies`/`reserves`, so a corrupted value would silently misread the zero-copy arrays.
+    pub fn validate_num_outcomes(&self) -> Result<()> {
+        check_condition!(
+            self.num_outcomes >= MINIMUM_OUTCOMES_PER_MARKET,
+            NotEnoughOutcomes
+        );
+        check_condition!(self.num_outcomes as usize <= MAX_OUTCOMES, TooManyOutcomes);
+
+        Ok(())
+    }
+
+    /// Gate run before [`Market::cost`], [`Market::price`] and [`Market::buy_shares`] touch the
+    /// LMSR math, the fixed-point counterpart of rejecting NaN inputs.
+    ///
+    /// Checks every precondition the math relies on, each with its own error:
+    /// - `num_outcomes` in range (`NotEnoughOutcomes`, `TooManyOutcomes`), see
+    ///   [`Market::validate_num_outcomes`]
+    /// - `scale` (`b`) is not zero (`LiquidityParameterIsZero`)
+    /// - every `q / b` is within the fp_exp domain (`SupplySaturated`). Past it exp fails with
+    ///   `ExpArgumentOutOfRange` deep inside the math. Trades stop well before, at
+    ///   [`MAX_EXP_ARG`] `* b`, so only a corrupted account gets here.
+    ///
+    /// Together these keep `Σ exp(q_j/b - m)` in [1, N], so it is never zero and its ln always
+    /// exists.
+    pub fn validate_math_state(&self) -> Result<()> {
+        self.validate_num_outcomes()?;
+        check_condition!(self.scale > 0, LiquidityParameterIsZero);
+
+        let b = self.scale as u128;
+        let fp = self.fp_scale();
+        for &q in &self.supplies[..self.num_outcomes as usize] {
+            check_condition!(exp_arg(q, b, fp)? <= fp.max_exp_arg, SupplySaturated);
+        }
+
+        Ok(())
+    }
+
+    /// Validate an outcome index against `num_outcomes`, returning it as a `usize` for indexing.
+    ///
+    /// Every method taking an outcome index goes through this, so an out of range index (or a
+    /// corrupted `num_outcomes` past [`MAX_OUTCOMES`]) is always `InvalidOutcomeIndex`.
+    pub fn checked_outcome(&self, index: u8) -> Result<usize> {
+        check_condition!(
+            (self.num_outcomes as usize) <= MAX_OUTCOMES && index < self.num_outcomes,
+            InvalidOutcomeIndex
+        );
+
+        Ok(index as usize)
+    }
+
+    /// Reentrancy guard, taken at the start of an instruction that makes CPIs after mutating the
+    /// market and released by [`Market::exit`] once they return.
+    ///
+    /// This is defense in depth: the runtime already rejects indirect reentrancy and the classic
+    /// token program has no hooks, but a Token-2022 outcome mint with a transfer hook would call
+    /// out to third-party code between the two. A failed instruction reverts the flag with
+    /// everything else, so it can't get stuck.
+    pub fn enter(&mut self) -> Result<()> {
+        check_condition!(self.in_progress == 0, ReentrancyDetected);
+        self.in_progress = 1;
+
+        Ok(())
+    }
+
+    /// Release the guard taken by [`Market::enter`]
+    pub fn exit(&mut self) {
+        self.in_progress = 0;
+    }
+
+    /// The fixed-point 1.0 the LMSR math runs at: 1e18 for `high_precision` markets, else 1e9.
+    ///
+    /// D18 cuts the rounding of each exp/ln down to a fraction of a lamport, so costs and minted
+    /// shares track the exact LMSR to within a lamport even on large trades, at the price of more
+    /// series terms and U256 intermediates, i.e. more compute units per trade.
+    pub fn fp_one(&self) -> i128 {
+        self.fp_scale().one
+    }
+
+    fn fp_scale(&self) -> &'static FpScale {
+        if self.high_precision == 1 {
+            &FP_D18
+        } else {
+            &FP_D9
+        }
+    }
+
+    /// Whether the market has exactly two outcomes, which prices through [`binary_terms`]
+    pub fn is_binary(&self) -> bool {
+        self.num_outcomes == 2
+    }
+
+    /// [`log_sum_exp`] over this market's outcomes at its precision
+    fn log_sum_exp(&self) -> Result<(u128, i128)> {
+        self.log_sum_exp_with(true)
+    }
+
+    /// [`Market::log_sum_exp`], taking the [`binary_terms`] fast path for binary markets only if
+    /// `binary_fast_path` is set
+    fn log_sum_exp_with(&self, binary_fast_path: bool) -> Result<(u128, i128)> {
+        let n = self.num_outcomes as usize;
+        check_condition!(n <= MAX_OUTCOMES, InvalidOutcomeIndex);
+        if binary_fast_path && self.is_binary() {
+            let (terms, max_arg) =
+                binary_terms(&self.supplies, self.scale as u128, self.fp_scale())?;
+            return Ok((terms[0] + terms[1], max_arg));
+        }
+        log_sum_exp(&self.supplies[..n], self.scale as u128, self.fp_scale())
+    }
+
+    /// `ln Σ exp(q_j / b)` scaled by [`Market::fp_one`]
+    fn ln_sum_exp(&self) -> Result<i128> {
+        self.ln_sum_exp_with(true)
+    }
+
+    fn ln_sum_exp_with(&self, binary_fast_path: bool) -> Result<i128> {
+        let (sum_exp, max_arg) = self.log_sum_exp_with(binary_fast_path)?;
+        max_arg
+            .checked_add(ln_at(sum_exp, self.fp_scale())?)
+            .ok_or(error!(ErrorCode::MathOverflow))
+    }
+}
+
+/// The exp argument `q / b` of a supply `q` in 1e9 units, scaled by `fp.one`.
+///
+/// Supplies are stored scaled by 1e9, b is in lamports, so q / b is already the unscaled ratio
+/// and `(q * one) / b` is the exp argument at the given precision.
+fn exp_arg(q: u64, b: u128, fp: &FpScale) -> Result<i128> {
+    mul_div(q as i128, fp.one, b as i128)
+}
+
+/// `exp(arg - max_arg)` scaled by `fp.one`, for `arg <= max_arg`.
+///
+/// The series is only accurate for non-negative arguments, so this is `1 / exp(max_arg - arg)`.
+fn exp_shifted(arg: i128, max_arg: i128, fp: &FpScale) -> Result<u128> {
+    let exp_gap = exp_at(max_arg - arg, fp)?;
+    Ok(mul_div(fp.one, fp.one, exp_gap as i128)? as u128)
+}
+
+/// Numerically stable `Σ exp(q_j / b)`, shared by [`Market::cost`], [`Market::price`] and
+/// [`Market::buy_shares`].
+///
+/// Returns `(Σ exp(q_j/b - m), m)` with `m = max_j q_j/b`, both scaled by `fp.one`, so that
+/// `ln Σ exp(q_j/b) = m + ln(sum)`. Every shifted term is in (0, 1], so the sum stays in [1, N]
+/// however far the supplies grow. The arguments themselves must still be within the exp domain,
+/// as `exp(m)` bounds what a trade on the leading outcome can be priced at.
+fn log_sum_exp(supplies: &[u64], b: u128, fp: &FpScale) -> Result<(u128, i128)> {
+    check_condition!(b > 0, LiquidityParameterIsZero);
+
+    let mut max_arg: i128 = 0;
+    for q in supplies {
+        max_arg = max_arg.max(exp_arg(*q, b, fp)?);
+    }
+    check_condition!(max_arg <= fp.max_exp_arg, ExpArgumentOutOfRange);
+
+    let mut sum_exp: u128 = 0;
+    for q in supplies {
+        sum_exp = sum_exp
+            .checked_add(exp_shifted(exp_arg(*q, b, fp)?, max_arg, fp)?)
+            .ok_or(error!(ErrorCode::MathOverflow))?;
+    }
+
+    Ok((sum_exp, max_arg))
+}
+
+/// The two shifted terms of [`log_sum_exp`] for a binary market, in logistic form.
+///
+/// With `g = |q_0 - q_1| / b`, the leader's term is exactly `one` and the other is
+/// `exp(-g)`, so each price is `1 / (1 + exp(-g))` or `exp(-g) / (1 + exp(-g))`. This evaluates
+/// one exp where the general path evaluates two, the leader's being `exp(0)`. Results must match
+/// [`log_sum_exp`] within 1 unit; as `exp(0)` is exactly `one` there, they are in fact identical.
+/// Ties put the leader on outcome 0.
+fn binary_terms(supplies: &[u64], b: u128, fp: &FpScale) -> Result<([u128; 2], i128)> {
+    check_condition!(b > 0, LiquidityParameterIsZero);
+
+    let args = [exp_arg(supplies[0], b, fp)?, exp_arg(supplies[1], b, fp)?];
+    let leader = usize::from(args[1] > args[0]);
+    let max_arg = args[leader];
+    check_condition!(max_arg <= fp.max_exp_arg, ExpArgumentOutOfRange);
+
+    let mut terms = [fp.one as u128; 2];
+    terms[1 - leader] = exp_shifted(args[1 - leader], max_arg, fp)?;
+
+    Ok((terms, max_arg))
+}
+
+/// Maximum number of Taylor series terms evaluated by [`fp_exp`] and [`fp_ln`].
+/// 20 terms is accurate enough but arbitrary.
+pub const FP_SERIES_TERMS: u32 = 20;
+
+/// Maximum number of Taylor series terms evaluated by [`fp_exp_d18`] and [`fp_ln_d18`].
+///
+/// The ln series converges slowest, as 0.5^n / n at the top of its reduced range, and needs
+/// about 55 terms to reach 1e-18. Extra terms cost nothing once a term rounds to zero, unless
+/// the `deterministic-cu` feature is on.
+pub const FP_SERIES_TERMS_D18: u32 = 64;
+
+/// Largest argument (scaled by 1e9) [`fp_exp`] evaluates, beyond which it fails with
+/// `ExpArgumentOutOfRange`.
+pub const FP_EXP_MAX_ARG: i128 = 20 * D9_I128;
+
+/// Largest `q_i / b` (unscaled) an outcome supply may reach, see [`Market::buy_shares`].
+///
+/// Safely under the 20 bound of [`fp_exp`], so a market that hits it still has headroom for
+/// the exp arguments of every later trade instead of failing on them.
+pub const MAX_EXP_ARG: u64 = 18;
+
+/// [`Market::buy_capped`] leaves `b / FILL_SUPPLY_MARGIN` shares of headroom under the
+/// [`MAX_EXP_ARG`] supply limit, well above the fixed-point error in the shares a buy mints
+const FILL_SUPPLY_MARGIN: u128 = 1_000_000;
+
+/// 1e18 (D18), signed
+const D18_I128: i128 = D18_U128 as i128;
+
+/// Whether the Taylor series loops stop as soon as a term rounds to zero.
+///
+/// Early exit makes the compute cost data-dependent. With the `deterministic-cu` feature
+/// every call evaluates all [`FP_SERIES_TERMS`] (or [`FP_SERIES_TERMS_D18`]) terms, so a simulated transaction consumes
+/// the same CU as the landed one regardless of how the market moved in between.
+const SERIES_EARLY_EXIT: bool = !cfg!(feature = "deterministic-cu");
+
+/// A fixed-point precision: the scale of 1.0 and the constants the series need at that scale.
+struct FpScale {
+    one: i128,
+    max_exp_arg: i128,
+    series_terms: u32,
+    /// ln(2) = 0.693147180559945309...
+    ln_2: i128,
+    /// ln(3) = 1.098612288668109691...
+    ln_3: i128,
+    /// e = 2.718281828459045235...
+    e: i128,
+}
+
+const FP_D9: FpScale = FpScale {
+    one: D9_I128,
+    max_exp_arg: FP_EXP_MAX_ARG,
+    series_terms: FP_SERIES_TERMS,
+    ln_2: 693_147_180,
+    ln_3: 1_098_612_288,
+    e: 2_718_281_828,
+};
+
+const FP_D18: FpScale = FpScale {
+    one: D18_I128,
+    max_exp_arg: 20 * D18_I128,
+    series_terms: FP_SERIES_TERMS_D18,
+    ln_2: LN_2 as i128,
+    ln_3: 1_098_612_288_668_109_691,
+    e: 2_718_281_828_459_045_235,
+};
+
+/// Worst-case `|Σ price(i) - 1e9|` (in 1e9 price units) for a market with `num_outcomes`.
+///
+/// Each of the N prices is floored independently over the same denominator, losing less than
+/// one unit each, and the losses of N prices that sum to exactly 1e9 before flooring can add up
+/// to at most N - 1 units. This is the precision contract of [`Market::price_sum_residual`].
+pub fn price_sum_tolerance(num_outcomes: u8) -> u64 {
+    num_outcomes.saturating_sub(1) as u64
+}
+
+/// `term * 1e9 / sum` rounded down, the price of an outcome whose exp term is `term` out of `sum`,
+/// capped at `u64::MAX`.
+///
+/// The log-sum-exp shift keeps the terms of [`Market::price`] at most `one`, so the multiply
+/// fits in u128. Should a term ever be large enough to overflow it, the ratio is taken in U256
+/// instead, so a valid price doesn't turn into `MathOverflow`.
+pub fn price_ratio(term: u128, sum: u128) -> Result<u64> {
+    check_condition!(sum != 0, MathOverflow);
+    let price = match term.checked_mul(D9_U128) {
+        Some(product) => product / sum,
+        None => {
+            let wide = U256::from(term) * U256::from(D9_U128) / U256::from(sum);
+            if wide > U256::from(u64::MAX) {
+                return Ok(u64::MAX);
+            }
+            wide.as_u128()
+        }
+    };
+
+    Ok(price.min(u64::MAX as u128) as u64)
+}
+
+/// `a * b / d` rounded toward zero, widening to U256 when `a * b` doesn't fit in i128.
+fn mul_div(a: i128, b: i128, d: i128) -> Result<i128> {
+    check_condition!(d != 0, MathOverflow);
+    if let Some(product) = a.checked_mul(b) {
+        return Ok(product / d);
+    }
+
+    let negative = (a < 0) ^ (b < 0) ^ (d < 0);
+    let quotient =
+        U256::from(a.unsigned_abs()) * U256::from(b.unsigned_abs()) / U256::from(d.unsigned_abs());
+    check_condition!(quotient <= U256::from(i128::MAX as u128), MathOverflow);
+
+    let quotient = quotient.as_u128() as i128;
+    Ok(if negative { -quotient } else { quotient })
 }
 
 /// Fixed-point exponential function: exp(x) where x is scaled by 1e9
 /// Returns result scaled by 1e9
 /// Uses Taylor series: exp(x) = 1 + x + x²/2! + x³/3! + ...
-/// Accurate for x in range [-10, 10] (scaled)
+/// For x >= 1 (scaled) the series only runs on the fractional part, `exp(x) = exp(x - k) * e^k`,
+/// which keeps the result within a few 1e-9 of exp(x) up to 20.
 /// NOTE: this should be linear approximation on-chain if possible, but if large trades are allowed then that is not feasible.
-fn fp_exp(x: i128) -> Result<u128> {
-    if x > 20 * D9_I128 {
-        return Ok(u128::MAX);
+///
+/// Compute cost:
+/// - Best case: x = 0 converges on the first term (1 iteration).
+/// - x >= 1 (scaled): the series on x - k, under 1 so about 13 iterations at 1e9, plus one
+///   multiplication by e for each whole unit k, up to 20.
+/// - Worst case for negative x: near -20 (scaled) runs all [`FP_SERIES_TERMS`] iterations.
+/// - Outside [-20, 20] it returns without entering the loop.
+///
+/// Each iteration is two i128 divisions and one i128 multiplication, which are emulated on SBF.
+/// See `tests/test_compute_units.rs` for measured CU at both ends of the domain.
+///
+/// Domain and overflow:
+/// - x > [`FP_EXP_MAX_ARG`] fails with `ExpArgumentOutOfRange`: the result is not representable
+///   to any useful precision, and a saturated `u128::MAX` would silently misprice whatever is
+///   built on it. x < -[`FP_EXP_MAX_ARG`] returns 0, exp(-20) being within a few units of it.
+/// - `MathOverflow` is only returned for an intermediate that doesn't fit, never for x itself.
+/// - Every product goes through `mul_div`, which widens to U256 when it doesn't fit in i128
+///   (e.g. `exp(19) * e` at 1e18, see [`fp_exp_d18`]) instead of wrapping into a garbage value.
+/// - The truncated series is inaccurate for large negative x (the alternating terms don't cancel
+///   within [`FP_SERIES_TERMS`]). The market only ever passes x >= 0.
+pub fn fp_exp(x: i128) -> Result<u128> {
+    exp_at(x, &FP_D9)
+}
+
+/// [`fp_exp`] with x and the result scaled by 1e18, evaluating up to [`FP_SERIES_TERMS_D18`]
+/// terms. Bounded at ±20 (scaled) like [`fp_exp`].
+pub fn fp_exp_d18(x: i128) -> Result<u128> {
+    exp_at(x, &FP_D18)
+}
+
+fn exp_at(x: i128, fp: &FpScale) -> Result<u128> {
+    if x > fp.max_exp_arg {
+        return Err(error!(ErrorCode::ExpArgumentOutOfRange));
     }
-    if x < -20 * D9_I128 {
+    if x < -fp.max_exp_arg {
         return Ok(0);
     }
 
+    // exp(x) = exp(x - k) * e^k for the whole part k of x, so the series only sees [0, 1) and
+    // converges within its terms. Summed directly from x, 20 terms undershoot exp(15) by 8%.
+    if x >= fp.one {
+        let whole = x / fp.one;
+        let mut result = exp_at(x - whole * fp.one, fp)? as i128;
+        for _ in 0..whole {
+            result = mul_div(result, fp.e, fp.one)?;
+        }
+        return Ok(result as u128);
+    }
+
     // Taylor series: exp(x) = 1 + x + x²/2! + x³/3! + x⁴/4! + ...
-    let mut result: i128 = D9_I128; // Start with 1.0
-    let mut term: i128 = D9_I128; // Current term in series
+    let mut result: i128 = fp.one; // Start with 1.0
+    let mut term: i128 = fp.one; // Current term in series
 
-    // 20 terms is accurate enough but arbitrary
-    for n in 1..=20 {
+    for n in 1..=fp.series_terms {
         // term = term * x / n
-        term = (term * x) / D9_I128 / (n as i128);
+        term = mul_div(term, x, fp.one)? / (n as i128);
 
-        if term.abs() < 1 {
+        if SERIES_EARLY_EXIT && term.abs() < 1 {
             break; // Convergence reached
         }
 
@@ -91,67 +585,92 @@ fn fp_exp(x: i128) -> Result<u128> {
 /// Returns result scaled by 1e9
 /// Uses Taylor series around x=1: ln(x) = (x-1) - (x-1)²/2 + (x-1)³/3 - ...
 /// NOTE: this should be linear approximation on-chain if possible, but if large trades are allowed then that is not feasible.
-fn fp_ln(x: u128) -> Result<i128> {
+///
+/// Compute cost:
+/// - Best case: x in {1, 2, 3, 4} (scaled) is a table lookup with no iterations.
+/// - Range reduction: x < 1 recurses once via 1/x, x >= e recurses once per division by e and
+///   1.5 < x < e recurses once more via x/2, i.e. about ln(x) + 2 times. For the largest sum of
+///   exponentials a market can produce (16 * exp(20), scaled) that is 25 recursions.
+/// - The remaining series runs at most [`FP_SERIES_TERMS`] iterations.
+///
+/// The recursion depth counts against the SBF call depth limit (64 frames), so callers must not
+/// pass values anywhere near `u128::MAX`.
+///
+/// ln(0) is undefined and fails with `LogOfNonPositive`, the only non-positive input a `u128`
+/// can hold. `MathOverflow` is only returned for an intermediate that doesn't fit.
+pub fn fp_ln(x: u128) -> Result<i128> {
+    ln_at(x, &FP_D9)
+}
+
+/// [`fp_ln`] with x and the result scaled by 1e18, evaluating up to [`FP_SERIES_TERMS_D18`]
+/// terms. The range reduction, and so the recursion depth, is the same as [`fp_ln`].
+pub fn fp_ln_d18(x: u128) -> Result<i128> {
+    ln_at(x, &FP_D18)
+}
+
+fn ln_at(x: u128, fp: &FpScale) -> Result<i128> {
     if x == 0 {
-        return Err(error!(ErrorCode::MathOverflow)); // ln(0) is undefined
+        return Err(error!(ErrorCode::LogOfNonPositive));
     }
 
-    if x == D9_I128 as u128 {
+    let one = fp.one as u128;
+    if x == one {
         return Ok(0); // ln(1) = 0
     }
 
     // Lookup table for common values (improves accuracy)
-    // ln(2) = 0.693147180559945...
-    const LN_2: i128 = 693_147_180;
-    if x == 2 * D9_I128 as u128 {
-        return Ok(LN_2);
+    if x == 2 * one {
+        return Ok(fp.ln_2);
     }
-    // ln(3) = 1.098612288668110...
-    const LN_3: i128 = 1_098_612_288;
-    if x == 3 * D9_I128 as u128 {
-        return Ok(LN_3);
+    if x == 3 * one {
+        return Ok(fp.ln_3);
     }
     // ln(4) = 2*ln(2)
-    if x == 4 * D9_I128 as u128 {
-        return Ok(2 * LN_2);
+    if x == 4 * one {
+        return Ok(2 * fp.ln_2);
     }
 
-    let x_i128 = x as i128;
+    let x_i128 = i128::try_from(x).map_err(|_| error!(ErrorCode::MathOverflow))?;
 
-    // For better convergence, use ln(x) = -ln(1/x) if x < 1
-    if x < D9_I128 as u128 {
-        let inv = (D9_I128 * D9_I128) / x_i128;
-        return fp_ln(inv as u128).map(|v| -v);
+    // For better convergence, use ln(x) = -ln(1/x) if x < 1. Within 0.001 below 1 the series
+    // needs only a couple of terms, and skipping the reciprocal avoids its rounding, so those fall
+    // through to the series on a negative y. Wider, the same-signed terms each round the same way.
+    let direct_band = one / 1_000; // 0.001 scaled
+    if x < one - direct_band {
+        let inv = mul_div(fp.one, fp.one, x_i128)?;
+        return ln_at(inv as u128, fp).map(|v| -v);
     }
 
     // For x > 1.5, use ln(x) = ln(x/e) + 1 to bring closer to 1
     // This improves convergence of the Taylor series
-    // e ≈ 2.718281828, scaled = 2718281828
-    const E_SCALED: i128 = 2_718_281_828;
-    const THRESHOLD: u128 = (3 * D9_I128 as u128) / 2; // 1.5 scaled
-    if x > THRESHOLD {
-        let reduced = (x_i128 * D9_I128) / E_SCALED;
-        return fp_ln(reduced as u128).map(|v| v + D9_I128);
+    let threshold = (3 * one) / 2; // 1.5 scaled
+    if x >= fp.e as u128 {
+        let reduced = mul_div(x_i128, fp.one, fp.e)?;
+        return ln_at(reduced as u128, fp).map(|v| v + fp.one);
+    }
+    // Between 1.5 and e, dividing by e would land below 1 and the reciprocal back above 1.5,
+    // recursing forever. Use ln(x) = ln(x/2) + ln(2) instead, which lands in (0.75, 1.36).
+    if x > threshold {
+        return ln_at(x / 2, fp).map(|v| v + fp.ln_2);
     }
 
     // Taylor series: ln(1+y) = y - y²/2 + y³/3 - y⁴/4 + ...
     // where y = x - 1
-    let y = x_i128 - D9_I128;
+    let y = x_i128 - fp.one;
     let mut result: i128 = 0;
     let mut y_power = y;
 
-    // 20 terms is accurate enough but arbitrary
-    for n in 1..=20 {
+    for n in 1..=fp.series_terms {
         let sign = if n % 2 == 1 { 1 } else { -1 };
         let term = (y_power * sign) / (n as i128);
 
-        if term.abs() < 1 {
+        if SERIES_EARLY_EXIT && term.abs() < 1 {
             break;
         }
         result = result
             .checked_add(term)
             .ok_or(error!(ErrorCode::MathOverflow))?;
-        y_power = (y_power * y) / D9_I128;
+        y_power = mul_div(y_power, y, fp.one)?;
     }
 
     Ok(result)
@@ -169,170 +688,1535 @@ impl Market {
     ///
     /// Returns the cost in lamports
     pub fn cost(&self) -> Result<u64> {
-        let n = self.num_outcomes as usize;
-        check_condition!(n <= MAX_OUTCOMES, InvalidOutcomeIndex);
+        let cost = self.cost_precise()? / D9_U128;
+        u64::try_from(cost).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
 
-        let b = self.scale as u128;
-        check_condition!(b > 0, ReserveIsZero);
+    /// [`Market::cost`] scaled by 1e9, i.e. in billionths of a lamport, before truncation.
+    ///
+    /// The difference of two costs is what a trade moves, and flooring each to a lamport first can
+    /// put that difference a lamport off. Features built on cost deltas subtract these instead and
+    /// round once. At 1e9 precision the extra digits are exactly what `b * ln` carries; at 1e18
+    /// they are the leading digits of the sub-lamport remainder.
+    pub fn cost_precise(&self) -> Result<u128> {
+        self.cost_precise_with(true)
+    }
 
-        const SCALE: i128 = 1_000_000_000; // 1e9 for fixed-point
+    /// [`Market::cost_precise`] through the general N-outcome path even for binary markets, to
+    /// check the binary fast path against.
+    #[cfg(feature = "test-utils")]
+    pub fn cost_precise_general(&self) -> Result<u128> {
+        self.cost_precise_with(false)
+    }
 
-        // Calculate Σ exp(q_i / b)
-        // Supplies are stored scaled by 1e9, b is in lamports
-        // We need (q / 1e9) / (b / 1e9) = q / b, then scale by 1e9 for fp_exp
-        // Simplified: (q * 1e9) / b
-        let mut sum_exp: u128 = 0;
-        for i in 0..n {
-            let q_i_scaled = self.supplies[i] as i128;
-            let exp_arg = (q_i_scaled * SCALE) / (b as i128);
-            let exp_val = fp_exp(exp_arg)?;
-            sum_exp = sum_exp
-                .checked_add(exp_val)
-                .ok_or(error!(ErrorCode::MathOverflow))?;
-        }
+    fn cost_precise_with(&self, binary_fast_path: bool) -> Result<u128> {
+        self.validate_math_state()?;
+        let b = self.scale as i128;
 
-        // Calculate C(q) = b * ln(sum)
-        let ln_sum = fp_ln(sum_exp)?;
-        let cost_i128 = ((b as i128) * ln_sum) / SCALE;
+        // C(q) = b * ln(Σ exp(q_i / b)), ln scaled by `one`, rescaled to 1e9
+        let fp = self.fp_scale();
+        let cost = mul_div(b, self.ln_sum_exp_with(binary_fast_path)?, fp.one / D9_I128)?;
 
         // Cost should always be non-negative for valid market states
-        check_condition!(cost_i128 >= 0, MathOverflow);
+        u128::try_from(cost).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+
+    /// Lamports (before the buy fee) the cost function charges for `shares` of an outcome,
+    /// `C(q + shares * e_i) - C(q)`, taken between [`Market::cost_precise`] values and rounded up.
+    pub fn cost_of_buy(&self, outcome_index: u8, shares: u64) -> Result<u64> {
+        let outcome_index = self.checked_outcome(outcome_index)?;
+        let mut after = *self;
+        after.supplies[outcome_index] = after.supplies[outcome_index]
+            .checked_add(shares)
+            .ok_or(error!(ErrorCode::MathOverflow))?;
+
+        let delta = after.cost_precise()?.saturating_sub(self.cost_precise()?);
+        u64::try_from(delta.div_ceil(D9_U128)).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+
+    /// The market maker's worst-case loss in lamports, `b * ln(N)`.
+    ///
+    /// This is `cost()` of a fresh market: the most the admin could owe beyond the reserves traders
+    /// paid in, reached when the outcome everyone bought wins. For `N = 2` it is `≈ 0.693 * b`.
+    pub fn max_loss(&self) -> Result<u64> {
+        let n = self.num_outcomes as u128;
+        check_condition!(n > 0, NotEnoughOutcomes);
+
+        let b = self.scale as i128;
+        check_condition!(b > 0, LiquidityParameterIsZero);
+
+        let fp = self.fp_scale();
+        let ln_n = ln_at(n * fp.one as u128, fp)?;
+        let max_loss = mul_div(b, ln_n, fp.one)?;
+
+        u64::try_from(max_loss).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+
+    /// Lamports needed to bring the market back to uniform prices `1/N` by buying only.
+    ///
+    /// Prices depend only on the differences between supplies, so buying every outcome up to the
+    /// largest supply makes them all equal, and LMSR prices that as the cost delta
+    /// `C(q_max, ..., q_max) - C(q)`. Buying is the only way to get there without burning someone
+    /// else's shares. An already uniform market costs exactly zero. The delta is taken between
+    /// [`Market::cost_precise`] values and rounded up.
+    pub fn cost_to_balance(&self) -> Result<u64> {
+        let n = self.num_outcomes as usize;
+        check_condition!(n <= MAX_OUTCOMES, InvalidOutcomeIndex);
 
-        Ok(cost_i128 as u64)
+        let max_supply = self.supplies[..n].iter().copied().max().unwrap_or(0);
+        let mut balanced = *self;
+        balanced.supplies[..n].fill(max_supply);
+
+        let delta = balanced
+            .cost_precise()?
+            .saturating_sub(self.cost_precise()?);
+        u64::try_from(delta.div_ceil(D9_U128)).map_err(|_| error!(ErrorCode::MathOverflow))
     }
 
     /// Compute how many shares to mint based on the LMSR cost function.
     /// Takes lamports in exchange.
     ///
     /// Updates:
-    /// - supplies[outcome_index] increases by calculated shares (supply)
-    /// - reserves[outcome_index] increases by lamports (reserves)
+    /// - accrued_protocol_fees increases by the buy fee on `amount_in` for a trade at `now`, see
+    ///   [`Market::trade_fees`]
+    /// - supplies[outcome_index] increases by calculated shares (supply), rounded down to a
+    ///   multiple of [`Market::share_unit`]
+    /// - reserves[outcome_index] increases by the lamports left after the fee (reserves)
+    /// - accrued_rounding_surplus moves by those lamports less the cost delta, taken between
+    ///   [`Market::cost_precise`] values and rounded up: what rounding the shares minted down
+    ///   leaves in the vault
+    ///
+    /// Rejects with `SupplyWouldSaturate` if the new supply would exceed `MAX_EXP_ARG * b`.
     ///
     /// Return the shares (supply) minted
-    pub fn buy_shares(&mut self, outcome_index: usize, amount_in: u64) -> Result<u64> {
-        let n = self.num_outcomes as usize;
-        check_condition!(outcome_index < n, InvalidOutcomeIndex);
+    pub fn buy_shares(&mut self, outcome_index: u8, amount_in: u64, now: i64) -> Result<u64> {
+        let outcome_index = self.checked_outcome(outcome_index)?;
+        self.validate_math_state()?;
         check_condition!(amount_in > 0, DepositIsZero);
 
-        let b = self.scale as u128;
-        check_condition!(b > 0, LiquidityParameterIsZero);
+        // Only what is left after the fee buys shares
+        let fee = self.trade_fees(now).buy_fee(amount_in);
+        let amount_in = amount_in - fee;
+        check_condition!(amount_in > 0, DepositIsZero);
 
-        // Δq = b * ln(S * (exp(amount_in/b) - 1) / exp(q_i/b) + 1)
+        let b = self.scale as u128;
+        let cost_before = self.cost_precise()?;
 
-        // S = Σ exp(q_j / b)
-        // Supplies are stored scaled by 1e9, b is in lamports
-        // We need (q / 1e9) / (b / 1e9) = q / b, then scale by 1e9 for fp_exp
-        // Simplified: (q * 1e9) / b
-        let mut sum_exp: u128 = 0;
-        for i in 0..n {
-            let q_j_scaled = self.supplies[i] as i128;
-            let exp_arg = (q_j_scaled * D9_I128) / (b as i128);
-            let exp_val = fp_exp(exp_arg)?;
-            sum_exp = sum_exp
-                .checked_add(exp_val)
+        if self.max_outcome_reserve > 0 {
+            let new_reserve = self.reserves[outcome_index]
+                .checked_add(amount_in)
                 .ok_or(error!(ErrorCode::MathOverflow))?;
+            check_condition!(
+                new_reserve <= self.max_outcome_reserve,
+                OutcomeReserveCapExceeded
+            );
         }
 
-        // exp(q_i / b)
-        let q_i_scaled = self.supplies[outcome_index] as i128;
-        let exp_qi_b = fp_exp((q_i_scaled * D9_I128) / (b as i128))?;
+        // Δq = b * ln(S * (exp(amount_in/b) - 1) / exp(q_i/b) + 1)
+
+        // S = Σ exp(q_j / b) = exp(m) * S', S' the shifted sum
+        let (sum_exp, max_arg) = self.log_sum_exp()?;
+
+        // S / exp(q_i / b) = S' * exp(m - q_i / b)
+        let fp = self.fp_scale();
+        let exp_gap = exp_at(max_arg - exp_arg(self.supplies[outcome_index], b, fp)?, fp)?;
 
         // exp(amount_in / b)
-        let amount_scaled = (amount_in as i128) * D9_I128;
-        let exp_amount_b = fp_exp(amount_scaled / (b as i128))?;
+        // fp_exp rejects anything past its domain, reject it here first with the buy's own error.
+        // This is the same bound reported by `max_buy`.
+        let amount_scaled = (amount_in as i128)
+            .checked_mul(fp.one)
+            .ok_or(error!(ErrorCode::MathOverflow))?;
+        let exp_amount_arg = amount_scaled / (b as i128);
+        check_condition!(exp_amount_arg <= fp.max_exp_arg, BuyAmountOutOfRange);
+        let exp_amount_b = exp_at(exp_amount_arg, fp)?;
 
-        // Δq = b * ln(S * (exp(amount_in/b) - 1) / exp(q_i/b) + 1)
-        let numerator = sum_exp
-            .checked_mul(
+        // Δq = b * ln(S' * exp(m - q_i/b) * (exp(amount_in/b) - 1) + 1)
+        // S', exp(m - q_i/b) and (exp(amount_in/b) - 1) are each scaled by `one`, so the product
+        // is scaled by one³ (past u128, hence U256) and dividing by one² leaves the fraction
+        // scaled by `one`, matching the 1.0 added below.
+        let numerator = U256::from(sum_exp)
+            .checked_mul(U256::from(exp_gap))
+            .ok_or(error!(ErrorCode::MathOverflow))?
+            .checked_mul(U256::from(
                 exp_amount_b
-                    .checked_sub(D9_I128 as u128)
+                    .checked_sub(fp.one as u128)
                     .ok_or(error!(ErrorCode::MathOverflow))?,
-            )
-            .ok_or(error!(ErrorCode::MathOverflow))?
-            / (D9_I128 as u128);
+            ))
+            .ok_or(error!(ErrorCode::MathOverflow))?;
 
+        let one_squared = U256::from(fp.one as u128) * U256::from(fp.one as u128);
         let fraction = numerator
-            .checked_div(exp_qi_b)
+            .checked_div(one_squared)
             .ok_or(error!(ErrorCode::MathOverflow))?;
+        check_condition!(fraction <= U256::from(u128::MAX), MathOverflow);
         let ln_arg = fraction
-            .checked_add(D9_I128 as u128)
+            .as_u128()
+            .checked_add(fp.one as u128)
             .ok_or(error!(ErrorCode::MathOverflow))?;
-        let ln_result = fp_ln(ln_arg)?;
+        let ln_result = ln_at(ln_arg, fp)?;
 
         // Δq = b * ln(...)
-        // b is in lamports, ln_result is scaled by 1e9
-        // Result: b * ln_result is shares scaled by 1e9 (which is how we store supplies)
-        let shares_out = ((b as i128) * ln_result) as u64;
+        // b is in lamports, ln_result is scaled by `one`
+        // Result: b * ln_result / one is shares scaled by 1e9 (which is how we store supplies,
+        // one whole share being 1e9 units, the same as one SOL in lamports)
+        let shares_out = mul_div(b as i128, ln_result, fp.one)?;
+        let shares_out = u64::try_from(shares_out).map_err(|_| error!(ErrorCode::MathOverflow))?;
+
+        // Whole outcome tokens only, what the remainder would have cost stays in the vault
+        let shares_out = shares_out - shares_out % self.share_unit();
         check_condition!(shares_out > 0, DepositIsZero);
 
-        self.supplies[outcome_index] = self.supplies[outcome_index]
+        let new_supply = self.supplies[outcome_index]
             .checked_add(shares_out)
             .ok_or(error!(ErrorCode::MathOverflow))?;
+        check_condition!(
+            new_supply as u128 <= b * MAX_EXP_ARG as u128,
+            SupplyWouldSaturate
+        );
+        self.supplies[outcome_index] = new_supply;
 
         self.reserves[outcome_index] = self.reserves[outcome_index]
             .checked_add(amount_in)
             .ok_or(error!(ErrorCode::MathOverflow))?;
+        self.accrued_protocol_fees = self
+            .accrued_protocol_fees
+            .checked_add(fee)
+            .ok_or(error!(ErrorCode::MathOverflow))?;
+        let cost_delta = self
+            .cost_precise()?
+            .saturating_sub(cost_before)
+            .div_ceil(D9_U128);
+        let cost_delta = u64::try_from(cost_delta).map_err(|_| error!(ErrorCode::MathOverflow))?;
+        self.record_rounding(amount_in, cost_delta)?;
 
         Ok(shares_out)
     }
 
-    /// Compute LMSR price/probability for an outcome.
-    /// Returns u64 scaled by 1e9 for safe math (i.e. 1.0 = 1_000_000_000).
+    /// Move `accrued_rounding_surplus` by what a trade kept, `kept`, less what it owed by the cost
+    /// function, `owed`. A buy can land a few lamports under its cost delta, so the
+    /// difference may be negative; the counter stops at zero rather than going below it.
+    fn record_rounding(&mut self, kept: u64, owed: u64) -> Result<()> {
+        self.accrued_rounding_surplus = if kept >= owed {
+            self.accrued_rounding_surplus
+                .checked_add(kept - owed)
+                .ok_or(error!(ErrorCode::MathOverflow))?
+        } else {
+            self.accrued_rounding_surplus.saturating_sub(owed - kept)
+        };
+
+        Ok(())
+    }
+
+    /// [`Market::buy_shares`], rejecting with `PriceImpactTooHigh` if the buy raises the outcome's
+    /// price by more than `max_impact_bps` relative to its price before. 0 disables the cap.
     ///
-    /// LMSR price formula:
-    /// p_i = exp(q_i / b) / Σ exp(q_j / b)
+    /// Unlike the `min_shares_out` slippage bound, which protects a quoted fill from other trades
+    /// landing first, this caps how far the trader's own order moves the price, e.g. an amount
+    /// typed with a few zeros too many.
+    pub fn buy_shares_within_impact(
+        &mut self,
+        outcome_index: u8,
+        amount_in: u64,
+        max_impact_bps: u16,
+        now: i64,
+    ) -> Result<u64> {
+        if max_impact_bps == 0 {
+            return self.buy_shares(outcome_index, amount_in, now);
+        }
+
+        // Bought on a copy, so a rejected buy leaves the market untouched
+        let mut after = *self;
+        let shares_out = after.buy_shares(outcome_index, amount_in, now)?;
+        let max_price = self.price(outcome_index)? as u128
+            * (BPS_DENOMINATOR as u128 + max_impact_bps as u128)
+            / BPS_DENOMINATOR as u128;
+        check_condition!(
+            after.price(outcome_index)? as u128 <= max_price,
+            PriceImpactTooHigh
+        );
+        *self = after;
+
+        Ok(shares_out)
+    }
+
+    /// Compute the lamports paid for burning `shares` of an outcome, the LMSR cost delta
+    /// `C(q) - C(q - shares * e_i)` taken between [`Market::cost_precise`] values and rounded
+    /// down once, so a buy followed by a sell can't profit.
     ///
-    /// Where:
-    /// - q_i is the quantity of shares for outcome i (supply)
-    /// - b is the liquidity parameter
-    /// - The sum is over all outcomes
+    /// Updates:
+    /// - supplies[outcome_index] decreases by `shares`
+    /// - reserves decrease by the payout in total: first from `reserves[outcome_index]`, then any
+    ///   excess from the other outcomes pro rata to their reserves, so no entry underflows
+    /// - accrued_protocol_fees increases by the sell fee on the payout for a trade at `now`, see
+    ///   [`Market::trade_fees`], which stays in the vault instead of going to the seller
     ///
-    /// This gives the price/probability for each outcome.
-    /// Prices always sum to exactly 1.0 (100%) across all outcomes.
-    pub fn price(&self, outcome_index: usize) -> Result<u64> {
+    /// The vault keeps the sub-lamport remainder of the cost delta, which isn't recorded in
+    /// `accrued_rounding_surplus`.
+    ///
+    /// Selling the last outstanding share empties the market (see [`Market::is_empty`]). It stays
+    /// open: every supply is back at zero, so `cost()` and the prices are exactly those of a fresh
+    /// market. What's left in `reserves` is the rounding the market kept on the way, which no
+    /// share can claim any more, so the ledger is cleared and the lamports become [`Market::dust`].
+    ///
+    /// Return the lamports paid out to the seller, after the sell fee
+    pub fn sell_shares(&mut self, outcome_index: u8, shares: u64, now: i64) -> Result<u64> {
         let n = self.num_outcomes as usize;
-        check_condition!(n <= MAX_OUTCOMES, InvalidOutcomeIndex);
-        check_condition!(outcome_index < n, InvalidOutcomeIndex);
+        let outcome_index = self.checked_outcome(outcome_index)?;
+        check_condition!(shares > 0, BurnIsZero);
+        check_condition!(shares <= self.supplies[outcome_index], BurnIsMoreThanSupply);
 
-        let b = self.scale as u128;
-        check_condition!(b > 0, LiquidityParameterIsZero);
+        let mut after = *self;
+        after.supplies[outcome_index] -= shares;
+        let cost_delta = self.cost_precise()?.saturating_sub(after.cost_precise()?) / D9_U128;
+        let payout = u64::try_from(cost_delta).map_err(|_| error!(ErrorCode::MathOverflow))?;
+        check_condition!(payout > 0, SharesAreZero);
 
-        // Calculate exp(q_i / b) for the target outcome
-        // Supplies are stored scaled by 1e9, b is in lamports
-        // We need (q / 1e9) / (b / 1e9) = q / b, then scale by 1e9 for fp_exp
-        // Simplified: (q * 1e9) / b
-        let q_i_scaled = self.supplies[outcome_index] as i128;
-        let exp_qi_b = fp_exp((q_i_scaled * D9_I128) / (b as i128))?;
+        let total_reserves: u64 = self.reserves[..n].iter().sum();
+        check_condition!(payout <= total_reserves, InsufficientVaultFunds);
 
-        // Calculate Σ exp(q_j / b) for all outcomes
-        let mut sum_exp: u128 = 0;
-        for i in 0..n {
-            let q_j_scaled = self.supplies[i] as i128;
-            let exp_arg = (q_j_scaled * D9_I128) / (b as i128);
-            let exp_val = fp_exp(exp_arg)?;
-            sum_exp = sum_exp
-                .checked_add(exp_val)
+        self.supplies[outcome_index] -= shares;
+        self.debit_reserves(outcome_index, payout);
+        if self.is_empty() {
+            self.reserves[..n].fill(0);
+        }
+
+        let fee = self.trade_fees(now).sell_fee(payout);
+        self.accrued_protocol_fees = self
+            .accrued_protocol_fees
+            .checked_add(fee)
+            .ok_or(error!(ErrorCode::MathOverflow))?;
+
+        Ok(payout - fee)
+    }
+
+    /// Whether no outcome has any shares outstanding, as at init or after everything was sold back
+    pub fn is_empty(&self) -> bool {
+        self.supplies[..self.num_outcomes as usize]
+            .iter()
+            .all(|&supply| supply == 0)
+    }
+
+    /// Sell several outcomes in one go, in `legs` order, returning the total lamports paid out.
+    ///
+    /// Each leg is a [`Market::sell_shares`] against the state the previous legs left behind, so
+    /// the order changes the individual payouts: selling an outcome lowers the cost, which lowers
+    /// what the later legs' cost deltas are worth. The total is what the slippage bound applies to.
+    pub fn sell_basket(&mut self, legs: &[BasketLeg], now: i64) -> Result<u64> {
+        check_condition!(
+            !legs.is_empty() && legs.len() <= self.num_outcomes as usize,
+            InvalidBatchSize
+        );
+
+        let mut total: u64 = 0;
+        for leg in legs {
+            let payout = self.sell_shares(leg.outcome_index, leg.shares, now)?;
+            total = total
+                .checked_add(payout)
                 .ok_or(error!(ErrorCode::MathOverflow))?;
         }
 
-        // Handle edge case: if sum is zero (shouldn't happen)
-        if sum_exp == 0 {
-            return Ok(0);
+        Ok(total)
+    }
+
+    /// Move a position from one outcome to another: sell `shares` of `from_outcome` and buy
+    /// `to_outcome` with the payout, returning the shares bought.
+    ///
+    /// The lamports never leave the vault, but both legs are ordinary trades: the sell fee is
+    /// taken on the payout and the buy fee on what is bought with it. The only guard is on the
+    /// combined result, `SlippageExceeded` unless at least `min_target_shares` are bought. Both
+    /// legs run on a copy, so a rejected flip leaves the market untouched. Flipping an outcome into
+    /// itself is rejected with `SameOutcome`.
+    pub fn flip(
+        &mut self,
+        from_outcome: u8,
+        to_outcome: u8,
+        shares: u64,
+        min_target_shares: u64,
+        now: i64,
+    ) -> Result<u64> {
+        check_condition!(from_outcome != to_outcome, SameOutcome);
+
+        let mut after = *self;
+        let payout = after.sell_shares(from_outcome, shares, now)?;
+        let target_shares = after.buy_shares(to_outcome, payout, now)?;
+        check_condition!(target_shares >= min_target_shares, SlippageExceeded);
+        *self = after;
+
+        Ok(target_shares)
+    }
+
+    /// Remove `amount` (at most `Σ reserves`) from the reserve ledger, starting with
+    /// `outcome_index` and spreading the rest over the other outcomes pro rata.
+    fn debit_reserves(&mut self, outcome_index: usize, amount: u64) {
+        let n = self.num_outcomes as usize;
+
+        let from_outcome = amount.min(self.reserves[outcome_index]);
+        self.reserves[outcome_index] -= from_outcome;
+
+        let mut remaining = amount - from_outcome;
+        let others: u64 = self.reserves[..n].iter().sum();
+        if remaining == 0 || others == 0 {
+            return;
         }
 
-        // Compute price: (exp(q_i/b) / sum) * 1e9
-        // This gives the probability/price scaled by 1e9
-        let price = exp_qi_b
-            .checked_mul(D9_U128)
-            .ok_or(error!(ErrorCode::MathOverflow))?
-            .checked_div(sum_exp)
-            .ok_or(error!(ErrorCode::MathOverflow))?;
+        let excess = remaining;
+        for i in 0..n {
+            let share = ((self.reserves[i] as u128 * excess as u128) / others as u128) as u64;
+            let share = share.min(remaining);
+            self.reserves[i] -= share;
+            remaining -= share;
+        }
+        // Rounding leftovers come from whichever outcomes still hold reserves
+        for i in 0..n {
+            let take = remaining.min(self.reserves[i]);
+            self.reserves[i] -= take;
+            remaining -= take;
+        }
+    }
 
-        // Clamp to u64::MAX if somehow exceeds (shouldn't happen in practice)
-        if price > u64::MAX as u128 {
-            Ok(u64::MAX)
-        } else {
-            Ok(price as u64)
+    /// The largest `amount_in` that [`Market::buy_shares`] will accept for an outcome.
+    ///
+    /// This is [`Market::max_safe_buy`], further capped by the remaining headroom under
+    /// `max_outcome_reserve` (if set).
+    ///
+    /// Returns 0 if the outcome can't be bought at all.
+    pub fn max_buy(&self, outcome_index: u8, now: i64) -> Result<u64> {
+        let index = self.checked_outcome(outcome_index)?;
+        let mut max_amount_in = self.max_safe_buy(outcome_index, now)?;
+
+        if self.max_outcome_reserve > 0 {
+            let headroom = self
+                .max_outcome_reserve
+                .saturating_sub(self.reserves[index]);
+            max_amount_in = max_amount_in.min(headroom);
+        }
+
+        Ok(max_amount_in)
+    }
+
+    /// The largest `amount_in` the LMSR math can take for an outcome, ignoring the
+    /// `max_outcome_reserve` circuit breaker. A UI can cap its input at this.
+    ///
+    /// This is the tighter of:
+    /// - the `fp_exp` domain, which ends once `amount_in / b` exceeds 20
+    /// - the supply limit of [`MAX_EXP_ARG`] `* b`
+    ///
+    /// `buy_shares` rejects anything larger with `BuyAmountOutOfRange` or
+    /// `SupplyWouldSaturate` respectively.
+    ///
+    /// The supply limit depends on the shares a buy would mint, so it is found by bisecting over
+    /// buys on a copy of the market. That is up to 64 buys, meant for clients rather than on-chain.
+    pub fn max_safe_buy(&self, outcome_index: u8, now: i64) -> Result<u64> {
+        self.checked_outcome(outcome_index)?;
+
+        let b = self.scale as u128;
+        check_condition!(b > 0, LiquidityParameterIsZero);
+
+        // amount_in * 1e9 / b <= FP_EXP_MAX_ARG
+        let max_amount_in = (b * (FP_EXP_MAX_ARG / D9_I128) as u128).min(u64::MAX as u128) as u64;
+
+        let mut uncapped = *self;
+        uncapped.max_outcome_reserve = 0;
+        let saturates = |amount_in: u64| {
+            let mut copy = uncapped;
+            copy.buy_shares(outcome_index, amount_in, now)
+                .is_err_and(|e| e == ErrorCode::SupplyWouldSaturate.into())
+        };
+        if !saturates(max_amount_in) {
+            return Ok(max_amount_in);
+        }
+
+        // Largest amount that doesn't saturate, with saturates(high) always true
+        let (mut low, mut high) = (0u64, max_amount_in);
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if saturates(mid) {
+                high = mid;
+            } else {
+                low = mid;
+            }
         }
+
+        Ok(low)
+    }
+
+    /// [`Market::buy_shares`] for as much of `amount_in` as the market can take, rather than
+    /// rejecting a buy past the `fp_exp` domain, the supply limit or `max_outcome_reserve`.
+    ///
+    /// Returns the shares minted and the lamports filled, at most `amount_in`. Only the filled
+    /// lamports are paid in, the rest is the caller's to keep.
+    pub fn buy_capped(
+        &mut self,
+        outcome_index: u8,
+        amount_in: u64,
+        now: i64,
+    ) -> Result<(u64, u64)> {
+        let filled = amount_in.min(self.max_fill(outcome_index, now)?);
+        let shares_out = self.buy_shares(outcome_index, filled, now)?;
+
+        Ok((shares_out, filled))
+    }
+
+    /// The dual of [`Market::buy_shares`]: mint exactly `shares_out` of an outcome and charge
+    /// what they cost, rejecting with `SlippageExceeded` if that is more than `max_amount_in`.
+    ///
+    /// The charge is [`Market::cost_of_buy`], the cost delta of raising `supplies[outcome_index]`
+    /// by `shares_out` rounded up, grossed up so that what is left after the buy fee covers it.
+    /// `shares_out` must be a whole number of outcome tokens (`InvalidShareAmount`), and the
+    /// same reserve cap and supply limit as `buy_shares` apply.
+    ///
+    /// Updates the same fields as `buy_shares`, with supplies[outcome_index] increasing by exactly
+    /// `shares_out`.
+    ///
+    /// Return the lamports charged, including the fee
+    pub fn buy_exact_shares(
+        &mut self,
+        outcome_index: u8,
+        shares_out: u64,
+        max_amount_in: u64,
+        now: i64,
+    ) -> Result<u64> {
+        let index = self.checked_outcome(outcome_index)?;
+        self.validate_math_state()?;
+        check_condition!(shares_out > 0, SharesAreZero);
+        self.shares_to_tokens(shares_out)?;
+
+        let b = self.scale as u128;
+        let new_supply = self.supplies[index]
+            .checked_add(shares_out)
+            .ok_or(error!(ErrorCode::MathOverflow))?;
+        check_condition!(
+            new_supply as u128 <= b * MAX_EXP_ARG as u128,
+            SupplyWouldSaturate
+        );
+
+        // amount_in - buy_fee(amount_in) >= cost, the inverse of the fee taken by `buy_shares`
+        let cost = self.cost_of_buy(outcome_index, shares_out)?;
+        check_condition!(cost > 0, DepositIsZero);
+        let fee_bps = self.trade_fees(now).buy_bps.min(MAX_TRADE_FEE_BPS) as u128;
+        let amount_in =
+            (cost as u128 * BPS_DENOMINATOR as u128).div_ceil(BPS_DENOMINATOR as u128 - fee_bps);
+        let amount_in = u64::try_from(amount_in).map_err(|_| error!(ErrorCode::MathOverflow))?;
+        check_condition!(amount_in <= max_amount_in, SlippageExceeded);
+
+        let fee = self.trade_fees(now).buy_fee(amount_in);
+        let net = amount_in - fee;
+        if self.max_outcome_reserve > 0 {
+            let new_reserve = self.reserves[index]
+                .checked_add(net)
+                .ok_or(error!(ErrorCode::MathOverflow))?;
+            check_condition!(
+                new_reserve <= self.max_outcome_reserve,
+                OutcomeReserveCapExceeded
+            );
+        }
+
+        self.supplies[index] = new_supply;
+        self.reserves[index] = self.reserves[index]
+            .checked_add(net)
+            .ok_or(error!(ErrorCode::MathOverflow))?;
+        self.accrued_protocol_fees = self
+            .accrued_protocol_fees
+            .checked_add(fee)
+            .ok_or(error!(ErrorCode::MathOverflow))?;
+        self.record_rounding(net, cost)?;
+
+        Ok(amount_in)
+    }
+
+    /// A buy [`Market::buy_shares`] accepts, close to [`Market::max_buy`] but in closed form so
+    /// it is cheap enough for on-chain use.
+    ///
+    /// The supply limit is priced with [`Market::cost_of_buy`] on the remaining headroom, less
+    /// `b / FILL_SUPPLY_MARGIN` shares so the rounding in the shares minted can't take the
+    /// supply over. The fee is added on top, and the reserve cap applies to the total like in
+    /// `max_buy`. The fill stops within about a millionth of `max_buy` at either precision.
+    fn max_fill(&self, outcome_index: u8, now: i64) -> Result<u64> {
+        let index = self.checked_outcome(outcome_index)?;
+        self.validate_math_state()?;
+        let b = self.scale as u128;
+
+        // amount_in * 1e9 / b <= FP_EXP_MAX_ARG, on the lamports left after the fee
+        let domain = b * (FP_EXP_MAX_ARG / D9_I128) as u128;
+
+        let headroom = (b * MAX_EXP_ARG as u128)
+            .saturating_sub(self.supplies[index] as u128)
+            .saturating_sub(b / FILL_SUPPLY_MARGIN + 1);
+        let headroom = u64::try_from(headroom).map_err(|_| error!(ErrorCode::MathOverflow))?;
+        let net = domain.min(self.cost_of_buy(outcome_index, headroom)? as u128);
+
+        // amount_in - buy_fee(amount_in) <= net
+        let fee_bps = self.trade_fees(now).buy_bps.min(MAX_TRADE_FEE_BPS) as u128;
+        let mut gross = net * BPS_DENOMINATOR as u128 / (BPS_DENOMINATOR as u128 - fee_bps);
+
+        if self.max_outcome_reserve > 0 {
+            gross = gross.min(
+                self.max_outcome_reserve
+                    .saturating_sub(self.reserves[index]) as u128,
+            );
+        }
+
+        Ok(gross.min(u64::MAX as u128) as u64)
+    }
+
+    /// Compute LMSR price/probability for an outcome.
+    /// Returns u64 scaled by 1e9 for safe math (i.e. 1.0 = 1_000_000_000).
+    ///
+    /// LMSR price formula:
+    /// p_i = exp(q_i / b) / Σ exp(q_j / b)
+    ///
+    /// Where:
+    /// - q_i is the quantity of shares for outcome i (supply)
+    /// - b is the liquidity parameter
+    /// - The sum is over all outcomes
+    ///
+    /// This gives the price/probability for each outcome.
+    /// Prices always sum to exactly 1.0 (100%) across all outcomes.
+    ///
+    /// A fresh market with every supply at zero skips the exp evaluations: each outcome is priced
+    /// at `1e9 / N`, with the remainder on outcome 0 so the prices sum to exactly 1e9.
+    ///
+    /// Binary markets take the logistic fast path of [`binary_terms`].
+    pub fn price(&self, outcome_index: u8) -> Result<u64> {
+        self.price_with(outcome_index, true)
+    }
+
+    /// [`Market::price`] through the general N-outcome path even for binary markets, to check
+    /// the binary fast path against.
+    #[cfg(feature = "test-utils")]
+    pub fn price_general(&self, outcome_index: u8) -> Result<u64> {
+        self.price_with(outcome_index, false)
+    }
+
+    fn price_with(&self, outcome_index: u8, binary_fast_path: bool) -> Result<u64> {
+        let outcome_index = self.checked_outcome(outcome_index)?;
+        self.validate_math_state()?;
+
+        let b = self.scale as u128;
+
+        if self.is_empty() {
+            return Ok(self.uniform_price(outcome_index));
+        }
+
+        // exp(q_i/b) / Σ exp(q_j/b) is the same ratio of the shifted terms, see `log_sum_exp`
+        let fp = self.fp_scale();
+        if binary_fast_path && self.is_binary() {
+            let (terms, _) = binary_terms(&self.supplies, b, fp)?;
+            return price_ratio(terms[outcome_index], terms[0] + terms[1]);
+        }
+        let (sum_exp, max_arg) = self.log_sum_exp_with(false)?;
+        let exp_qi_b = exp_shifted(exp_arg(self.supplies[outcome_index], b, fp)?, max_arg, fp)?;
+
+        // Compute price: (exp(q_i/b) / sum) * 1e9
+        // This gives the probability/price scaled by 1e9
+        price_ratio(exp_qi_b, sum_exp)
+    }
+
+    /// `1e9 / N`, with the remainder on outcome 0, the price of every outcome of an empty market
+    fn uniform_price(&self, outcome_index: usize) -> u64 {
+        let n = self.num_outcomes as u128;
+        let price = D9_U128 / n + if outcome_index == 0 { D9_U128 % n } else { 0 };
+        price as u64
+    }
+
+    /// [`Market::price`] of every outcome, in index order. Evaluates the normalization sum once
+    /// rather than once per outcome.
+    pub fn prices(&self) -> Result<Vec<u64>> {
+        Ok(self.prices_range(0, self.num_outcomes as usize)?.0)
+    }
+
+    /// [`Market::price`] of outcomes `start..start + len`, with the shifted normalization sum
+    /// `Σ exp(q_j/b - m)` they are divided by (see [`log_sum_exp`]), scaled by [`Market::fp_one`].
+    ///
+    /// The sum still takes an exp per outcome, but outcomes outside the range skip their own exp
+    /// and division, so a client can split the prices of a heavy market across several reads.
+    /// Pages taken from the same market state report the same sum, so a client can compare them
+    /// to make sure no trade landed in between. An empty market reports a zero sum, as its
+    /// uniform prices don't need one.
+    pub fn prices_range(&self, start: usize, len: usize) -> Result<(Vec<u64>, u128)> {
+        self.validate_math_state()?;
+        let end = start
+            .checked_add(len)
+            .filter(|&end| end <= self.num_outcomes as usize)
+            .ok_or(error!(ErrorCode::InvalidOutcomeIndex))?;
+
+        if self.is_empty() {
+            let prices = (start..end).map(|i| self.uniform_price(i)).collect();
+            return Ok((prices, 0));
+        }
+
+        // The binary fast path yields the same terms, its leader's being exactly `one`
+        let b = self.scale as u128;
+        let fp = self.fp_scale();
+        let (sum_exp, max_arg) = self.log_sum_exp()?;
+        let prices = (start..end)
+            .map(|i| {
+                let exp_qi_b = exp_shifted(exp_arg(self.supplies[i], b, fp)?, max_arg, fp)?;
+                price_ratio(exp_qi_b, sum_exp)
+            })
+            .collect::<Result<Vec<u64>>>()?;
+
+        Ok((prices, sum_exp))
+    }
+
+    /// Marginal cost of the next share of an outcome (scaled by 1e9 like a price), from a finite
+    /// difference of the cost function: `b * (ln S(q + δe_i) - ln S(q)) / δ`.
+    ///
+    /// In LMSR `∂C/∂q_i` is exactly `price(i)`, so this is an independent cross-check of the
+    /// `cost` and `price` derivations: a gap beyond rounding is a math bug. It always runs at 1e18
+    /// so the difference of two logs keeps its precision, with `δ = max(1, b / 1e9)` share units,
+    /// small enough that the finite difference error (about `p * (1 - p) * δ / 2b`) is under a unit.
+    pub fn marginal_cost(&self, outcome_index: u8) -> Result<u64> {
+        let outcome_index = self.checked_outcome(outcome_index)?;
+        check_condition!(self.scale > 0, LiquidityParameterIsZero);
+
+        let mut before = *self;
+        before.high_precision = 1;
+        let step = (self.scale / D9_U128 as u64).max(1);
+        let mut after = before;
+        after.supplies[outcome_index] = after.supplies[outcome_index]
+            .checked_add(step)
+            .ok_or(error!(ErrorCode::MathOverflow))?;
+
+        let ln_before = before.ln_sum_exp()?;
+        let ln_after = after.ln_sum_exp()?;
+
+        // b * Δln / δ is the marginal cost scaled by 1e18, rescale to 1e9
+        let marginal = mul_div(self.scale as i128, ln_after - ln_before, step as i128)?;
+        let marginal = mul_div(marginal, D9_I128, D18_I128)?;
+
+        u64::try_from(marginal).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+
+    /// Precision audit: `Σ price(i) - 1e9`, which would be exactly zero with infinite precision.
+    ///
+    /// Every price is `floor(exp(q_i/b) * 1e9 / Σ exp(q_j/b))` over the same sum, so each loses
+    /// less than one unit and the residual is bounded by `-(N - 1) <= residual <= 0`, see
+    /// [`price_sum_tolerance`]. Anything outside that bound is a bug. A state outside the `fp_exp`
+    /// domain (e.g. a corrupted supply above `20 * b`) can't be priced at all and returns
+    /// `MathOverflow`, which monitoring should treat as an alert too.
+    pub fn price_sum_residual(&self) -> Result<i64> {
+        let n = self.num_outcomes as usize;
+        check_condition!(n <= MAX_OUTCOMES, InvalidOutcomeIndex);
+
+        let mut sum: i64 = 0;
+        for i in 0..n {
+            sum = sum
+                .checked_add(self.price(i as u8)? as i64)
+                .ok_or(error!(ErrorCode::MathOverflow))?;
+        }
+
+        Ok(sum - D9_I128 as i64)
+    }
+
+    /// What-if query: the price of `query_outcome` (scaled by 1e9) if `amount_in` lamports were
+    /// spent on `buy_outcome`, without mutating the market.
+    ///
+    /// Applies [`Market::buy_shares`] to a copy, so it fails exactly when the real buy would.
+    pub fn price_after_hypothetical_buy(
+        &self,
+        buy_outcome: u8,
+        amount_in: u64,
+        query_outcome: u8,
+        now: i64,
+    ) -> Result<u64> {
+        let mut hypothetical = *self;
+        hypothetical.buy_shares(buy_outcome, amount_in, now)?;
+
+        hypothetical.price(query_outcome)
+    }
+
+    /// Liquidity depth: the lamports that can be spent buying an outcome before its price rises
+    /// by more than `max_impact_bps` relative to the current price, e.g. 100 for "available within
+    /// 1% impact". Read only, the market is not mutated.
+    ///
+    /// A buy of outcome `i` only moves its own term of the LMSR sum, so taking its price from
+    /// `p` to `p'` costs exactly `b * ln((1 - p) / (1 - p'))`, with no search over buys. The buy
+    /// fee is added on top. A target price at or above 1.0 can't be reached, in which case the
+    /// depth is [`Market::max_buy`].
+    pub fn depth(&self, outcome_index: u8, max_impact_bps: u16, now: i64) -> Result<u64> {
+        self.checked_outcome(outcome_index)?;
+
+        let b = self.scale as u128;
+        check_condition!(b > 0, LiquidityParameterIsZero);
+
+        let price = self.price(outcome_index)? as u128;
+        let target =
+            price * (BPS_DENOMINATOR as u128 + max_impact_bps as u128) / BPS_DENOMINATOR as u128;
+        if target >= D9_U128 {
+            return self.max_buy(outcome_index, now);
+        }
+
+        // (1 - p) / (1 - p') >= 1, scaled by `one`
+        let fp = self.fp_scale();
+        let ratio = (D9_U128 - price)
+            .checked_mul(fp.one as u128)
+            .ok_or(error!(ErrorCode::MathOverflow))?
+            / (D9_U128 - target);
+        let net = mul_div(b as i128, ln_at(ratio, fp)?, fp.one)?;
+        let net = u64::try_from(net).map_err(|_| error!(ErrorCode::MathOverflow))?;
+
+        // amount_in - buy_fee(amount_in) = net
+        let fee_bps = self.trade_fees(now).buy_bps.min(MAX_TRADE_FEE_BPS) as u128;
+        let gross = net as u128 * BPS_DENOMINATOR as u128 / (BPS_DENOMINATOR as u128 - fee_bps);
+
+        u64::try_from(gross).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+
+    /// One number to compare depth across markets: the [`Market::depth`] of the highest priced
+    /// outcome (the lowest index on a tie) at [`LIQUIDITY_DEPTH_IMPACT_BPS`], the lamports it
+    /// takes to move the favorite's price by 1%. It grows linearly with `scale`.
+    pub fn liquidity_depth(&self, now: i64) -> Result<u64> {
+        self.validate_num_outcomes()?;
+
+        let mut top = (0u8, 0u64);
+        for i in 0..self.num_outcomes {
+            let price = self.price(i)?;
+            if price > top.1 {
+                top = (i, price);
+            }
+        }
+
+        self.depth(top.0, LIQUIDITY_DEPTH_IMPACT_BPS, now)
+    }
+
+    /// How fast an outcome's price moves as its shares are bought, `dp_i/dq_i = p_i (1 - p_i) / b`
+    /// at the current state: the derivative of the logistic price, largest at `p = 0.5` and
+    /// vanishing towards 0 and 1. A low value means the price is stable against trades.
+    ///
+    /// Scaled by 1e18, i.e. the change of the 1e9 scaled price per 1e9 shares.
+    pub fn price_sensitivity(&self, outcome_index: u8) -> Result<u64> {
+        let price = self.price(outcome_index)? as u128;
+        let sensitivity = price * (D9_U128 - price) / self.scale as u128;
+
+        u64::try_from(sensitivity).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+
+    /// Mark-to-market lamport value of the outstanding shares of each outcome:
+    /// `supplies[i] * price(i)`, with both the 1e9 share scale and the 1e9 price scale removed.
+    ///
+    /// Unlike `reserves` (the lamports paid in), this is the current valuation of the liability.
+    /// Across all outcomes `Σ open_interest <= cost() <= Σ open_interest + b * ln(N)`, the gap
+    /// being `b` times the entropy of the prices.
+    ///
+    /// Entries past `num_outcomes` are zero.
+    pub fn open_interest(&self) -> Result<[u64; MAX_OUTCOMES]> {
+        let n = self.num_outcomes as usize;
+        check_condition!(n <= MAX_OUTCOMES, InvalidOutcomeIndex);
+
+        let mut open_interest = [0u64; MAX_OUTCOMES];
+        for (i, value) in open_interest.iter_mut().enumerate().take(n) {
+            let price = self.price(i as u8)? as u128;
+            let value_u128 = (self.supplies[i] as u128)
+                .checked_mul(price)
+                .ok_or(error!(ErrorCode::MathOverflow))?
+                / D9_U128;
+            *value = u64::try_from(value_u128).map_err(|_| error!(ErrorCode::MathOverflow))?;
+        }
+
+        Ok(open_interest)
+    }
+
+    /// Collateralization (TVL / [`Market::required_collateral`], scaled by 1e9) and seconds until
+    /// `resolve_at`. A market that owes nothing yet, fresh and unseeded, reads `u64::MAX`.
+    ///
+    /// `vault_lamports` is the vault balance above its rent-exempt minimum.
+    pub fn health_factor(&self, vault_lamports: u64, now: i64) -> Result<MarketHealth> {
+        let required = self.required_collateral()?;
+        let collateralization = if required == 0 {
+            u64::MAX
+        } else {
+            let ratio = (self.tvl(vault_lamports) as u128)
+                .checked_mul(D9_U128)
+                .ok_or(error!(ErrorCode::MathOverflow))?
+                / required as u128;
+            u64::try_from(ratio).unwrap_or(u64::MAX)
+        };
+
+        Ok(MarketHealth {
+            collateralization,
+            seconds_to_resolve: self.resolve_at.saturating_sub(now).max(0),
+        })
+    }
+
+    /// What buying `amount_in` lamports of an outcome would mint and the price it would leave,
+    /// without changing the market. Fails where [`Market::buy_shares`] would.
+    pub fn quote_buy(&self, outcome_index: u8, amount_in: u64, now: i64) -> Result<BuyQuote> {
+        let mut after = *self;
+        let shares_out = after.buy_shares(outcome_index, amount_in, now)?;
+
+        Ok(BuyQuote {
+            shares_out,
+            new_price: after.price(outcome_index)?,
+        })
+    }
+
+    /// Multi-line human-readable summary for CLIs and debugging: label, admin, status, price and
+    /// supply of each outcome, cost and time to resolve. Prices are shown as fractions of 1 and
+    /// lamport amounts as is. The trading status and time to resolve are as of `now`.
+    #[cfg(not(target_os = "solana"))]
+    pub fn describe(&self, now: i64) -> String {
+        use std::fmt::Write;
+
+        let status = match self.resolution() {
+            Some(MarketResolution::Void) => "voided, refunding".to_string(),
+            Some(MarketResolution::Winner(_)) if self.refund_mode == 1 => {
+                "resolved with no winning holders, refunding".to_string()
+            }
+            Some(MarketResolution::Winner(outcome)) => format!("resolved to outcome {outcome}"),
+            None if now >= self.resolve_at => "awaiting resolution".to_string(),
+            None if now >= self.trading_close_at() => "halted before resolution".to_string(),
+            None if now < self.trading_open_at => {
+                format!("paused until {}", self.trading_open_at)
+            }
+            None => "open".to_string(),
+        };
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "Market {:?}: {} outcomes, b = {} lamports, {} precision",
+            String::from_utf8_lossy(self.label.trimmed()),
+            self.num_outcomes,
+            self.scale,
+            if self.high_precision == 1 {
+                "1e18"
+            } else {
+                "1e9"
+            },
+        );
+        let _ = writeln!(out, "  admin:   {}", self.admin);
+        let _ = writeln!(out, "  status:  {status}");
+        match self.prices() {
+            Ok(prices) => {
+                for (i, price) in prices.iter().enumerate() {
+                    let _ = writeln!(
+                        out,
+                        "  outcome {i:>2}: price {}.{:09}, supply {}",
+                        price / D9_U128 as u64,
+                        price % D9_U128 as u64,
+                        self.supplies[i],
+                    );
+                }
+            }
+            Err(err) => {
+                let _ = writeln!(out, "  prices:  unavailable ({err})");
+            }
+        }
+        match self.cost() {
+            Ok(cost) => {
+                let _ = writeln!(out, "  cost:    {cost} lamports");
+            }
+            Err(err) => {
+                let _ = writeln!(out, "  cost:    unavailable ({err})");
+            }
+        }
+        let day = DAY_IN_SECONDS as i64;
+        let remaining = self.resolve_at.saturating_sub(now).max(0);
+        let _ = write!(
+            out,
+            "  resolve: at {} (in {}d {:02}h {:02}m {:02}s)",
+            self.resolve_at,
+            remaining / day,
+            remaining % day / 3_600,
+            remaining % 3_600 / 60,
+            remaining % 60,
+        );
+
+        out
+    }
+
+    /// Point-in-time summary of the market for analytics and UIs.
+    ///
+    /// `vault_lamports` is the vault balance above its rent-exempt minimum.
+    pub fn snapshot(&self, vault_lamports: u64, now: i64) -> Result<MarketSnapshot> {
+        let n = self.num_outcomes as usize;
+        check_condition!(n <= MAX_OUTCOMES, InvalidOutcomeIndex);
+
+        let mut prices = [0u64; MAX_OUTCOMES];
+        let mut price_sensitivity = [0u64; MAX_OUTCOMES];
+        for i in 0..n {
+            prices[i] = self.price(i as u8)?;
+            price_sensitivity[i] = self.price_sensitivity(i as u8)?;
+        }
+
+        Ok(MarketSnapshot {
+            prices,
+            price_sensitivity,
+            cost: self.cost()?,
+            open_interest: self.open_interest()?,
+            health: self.health_factor(vault_lamports, now)?,
+            liquidity_depth: self.liquidity_depth(now)?,
+        })
+    }
+
+    /// `mints_initialized` with a bit set for every outcome.
+    pub fn all_mints_mask(&self) -> u16 {
+        ((1u32 << self.num_outcomes.min(MAX_OUTCOMES as u8)) - 1) as u16
+    }
+
+    /// Whether every outcome mint has been created.
+    pub fn mints_ready(&self) -> bool {
+        self.mints_initialized == self.all_mints_mask()
+    }
+
+    /// Record that the mint of outcome `index` was created by `init_outcome_mint`.
+    pub fn mark_mint_initialized(&mut self, index: u8) -> Result<()> {
+        self.checked_outcome(index)?;
+
+        let bit = 1u16 << index;
+        check_condition!(self.mints_initialized & bit == 0, MintAlreadyInitialized);
+        self.mints_initialized |= bit;
+
+        Ok(())
+    }
+
+    /// When trading closes: `trading_halt_before_resolve` seconds before `resolve_at`
+    pub fn trading_close_at(&self) -> i64 {
+        self.resolve_at
+            .saturating_sub(self.trading_halt_before_resolve.max(0))
+    }
+
+    /// Whether the market can be traded at `now`, the one check every buy and sell instruction
+    /// runs before trading. Trades are only allowed once every outcome mint exists, while
+    /// unresolved and within `[trading_open_at, trading_close_at())`. Fails with the first
+    /// condition that blocks trading:
+    /// - `MintsNotReady`: an outcome mint hasn't been created yet
+    /// - `MarketAlreadyResolved`
+    /// - `MarketPaused`: before `trading_open_at`
+    /// - `MarketExpired`: from `resolve_at` on, while awaiting resolution
+    /// - `TradingWindowClosed`: in the halt before `resolve_at`, see
+    ///   [`Market::trading_close_at`]
+    pub fn is_tradable(&self, now: i64) -> Result<()> {
+        check_condition!(self.mints_ready(), MintsNotReady);
+        check_condition!(self.resolved == 0, MarketAlreadyResolved);
+        check_condition!(now >= self.trading_open_at, MarketPaused);
+        check_condition!(now < self.resolve_at, MarketExpired);
+        check_condition!(now < self.trading_close_at(), TradingWindowClosed);
+
+        Ok(())
+    }
+
+    /// Move the start of the trading window. Setting it in the future closes trading until then,
+    /// e.g. for the halftime of a live game. The end of the window is always
+    /// [`Market::trading_close_at`].
+    pub fn set_trading_window(&mut self, trading_open_at: i64) -> Result<()> {
+        check_condition!(self.resolved == 0, MarketAlreadyResolved);
+        check_condition!(
+            trading_open_at < self.trading_close_at(),
+            InvalidTradingWindow
+        );
+
+        self.trading_open_at = trading_open_at;
+
+        Ok(())
+    }
+
+    /// The fees a trade at `now` pays: `fee_config`, with the buy and sell fees waived during the
+    /// fee holiday, which runs until `fee_free_until`.
+    pub fn trade_fees(&self, now: i64) -> FeeConfig {
+        if now < self.fee_free_until {
+            FeeConfig {
+                buy_bps: 0,
+                sell_bps: 0,
+                ..self.fee_config
+            }
+        } else {
+            self.fee_config
+        }
+    }
+
+    /// Waive trade fees until `fee_free_until`, e.g. to bootstrap a new market's liquidity. The
+    /// holiday can't outlast trading, so it ends by `resolve_at`; zero ends it.
+    pub fn set_fee_holiday(&mut self, fee_free_until: i64) -> Result<()> {
+        check_condition!(self.resolved == 0, MarketAlreadyResolved);
+        check_condition!(fee_free_until <= self.resolve_at, InvalidFeeHoliday);
+
+        self.fee_free_until = fee_free_until;
+
+        Ok(())
+    }
+
+    /// Whether any resolution path may resolve the market at `now`.
+    pub fn can_resolve(&self, now: i64) -> bool {
+        self.ensure_resolvable(now).is_ok()
+    }
+
+    /// Preconditions shared by every resolution path (admin, oracle, consensus): the market is not
+    /// already resolved and `resolve_at` has passed. Authority checks stay with each path's accounts.
+    pub fn ensure_resolvable(&self, now: i64) -> Result<()> {
+        check_condition!(self.resolved == 0, MarketAlreadyResolved);
+        check_condition!(now >= self.resolve_at, MarketNotReadyToResolve);
+
+        Ok(())
+    }
+
+    /// Record the resolution and halt trading. Every resolution path ends here, after
+    /// [`Market::ensure_resolvable`].
+    ///
+    /// If nobody holds the winning outcome (`supplies[winning_outcome] == 0`) there is no one to
+    /// pay out, so the market switches to refund mode rather than trapping the vault. A voided
+    /// market is refunded the same way, with `winning_outcome` set to [`VOID_OUTCOME`].
+    pub fn finalize_resolution(&mut self, resolution: MarketResolution) -> Result<()> {
+        match resolution {
+            MarketResolution::Winner(winning_outcome) => {
+                self.checked_outcome(winning_outcome)?;
+
+                self.winning_outcome = winning_outcome;
+                if self.supplies[winning_outcome as usize] == 0 {
+                    self.refund_mode = 1;
+                }
+            }
+            MarketResolution::Void => {
+                self.winning_outcome = VOID_OUTCOME;
+                self.refund_mode = 1;
+            }
+        }
+        self.resolved = 1;
+
+        Ok(())
+    }
+
+    /// Admin resolution to `winning_outcome`, or a void with [`VOID_OUTCOME`].
+    pub fn resolve(&mut self, winning_outcome: u8, now: i64) -> Result<()> {
+        self.ensure_resolvable(now)?;
+        self.finalize_resolution(MarketResolution::from_outcome(winning_outcome))
+    }
+
+    /// How the market was resolved, `None` while it is still open
+    pub fn resolution(&self) -> Option<MarketResolution> {
+        (self.resolved == 1).then(|| MarketResolution::from_outcome(self.winning_outcome))
+    }
+
+    /// Decimals of the outcome mints: `outcome_decimals`, or [`OUTCOME_MINT_DECIMALS`] if unset
+    pub fn mint_decimals(&self) -> u8 {
+        match self.outcome_decimals {
+            0 => OUTCOME_MINT_DECIMALS,
+            decimals => decimals.min(OUTCOME_MINT_DECIMALS),
+        }
+    }
+
+    /// Share units per base unit of an outcome token, `10^(9 - mint_decimals())`.
+    ///
+    /// The LMSR math counts shares at 1e9 per whole share whatever the mint decimals, so prices
+    /// and costs don't depend on them: a base unit of a 6 decimal mint is 1000 share units.
+    /// Buys round the shares minted down to a multiple of this, keeping every supply a whole
+    /// number of tokens. Share amounts in instructions, quotes and events are share units, and
+    /// only the mint and burn CPIs use [`Market::shares_to_tokens`].
+    pub fn share_unit(&self) -> u64 {
+        10u64.pow((OUTCOME_MINT_DECIMALS - self.mint_decimals()) as u32)
+    }
+
+    /// Outcome token base units for `shares`, `shares / share_unit()`. `InvalidShareAmount` if
+    /// `shares` is not a whole number of base units.
+    pub fn shares_to_tokens(&self, shares: u64) -> Result<u64> {
+        let unit = self.share_unit();
+        check_condition!(shares.is_multiple_of(unit), InvalidShareAmount);
+
+        Ok(shares / unit)
+    }
+
+    /// Check that an outcome mint's SPL supply matches `supplies[outcome_index]`.
+    ///
+    /// Both count the same shares: `supplies` in share units (1e9 per whole share), the mint in
+    /// token base units (`10^mint_decimals()` per whole share), so
+    /// `mint_supply [base units] * share_unit() == supplies[i] [share units]`. Buys mint exactly
+    /// the shares they add to `supplies`, and sells and redemptions burn exactly the shares they
+    /// remove, so the two stay equal. A normal-mode redemption then pays
+    /// `payout [lamports] = tvl [lamports] * shares [share units] / winning_supply() [share units]`,
+    /// and a mismatch would over- or under-pay, so `redeem` rejects it with `MintSupplyMismatch`.
+    /// Untokenized markets have no mint to check.
+    pub fn check_mint_supply(&self, outcome_index: u8, mint_supply: u64) -> Result<()> {
+        let outcome_index = self.checked_outcome(outcome_index)?;
+        check_condition!(
+            mint_supply as u128 * self.share_unit() as u128 == self.supplies[outcome_index] as u128,
+            MintSupplyMismatch
+        );
+
+        Ok(())
+    }
+
+    /// Total shares of the winning outcome, what normal mode redemptions are paid pro rata
+    /// against. `MarketNotResolved` while the market is open; a voided market has no winning
+    /// outcome and fails with `InvalidOutcomeIndex`.
+    pub fn winning_supply(&self) -> Result<u64> {
+        check_condition!(self.resolved == 1, MarketNotResolved);
+        let winning_outcome = self.checked_outcome(self.winning_outcome)?;
+
+        Ok(self.supplies[winning_outcome])
+    }
+
+    /// The outcome priced at or above [`OUTCOME_CONSENSUS_PERCENTAGE_THRESHOLD`], if any.
+    ///
+    /// The threshold is above 50%, so only the outcome with the largest supply can reach it and
+    /// a single price evaluation suffices.
+    pub fn consensus_leader(&self) -> Result<Option<u8>> {
+        let n = self.num_outcomes as usize;
+        check_condition!(n <= MAX_OUTCOMES, TooManyOutcomes);
+        let leader = (0..n).max_by_key(|&i| self.supplies[i]).unwrap_or_default() as u8;
+
+        if self.price(leader)? >= OUTCOME_CONSENSUS_PERCENTAGE_THRESHOLD {
+            Ok(Some(leader))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Start, keep or clear the consensus streak after a trade moved the prices.
+    ///
+    /// Prices only move on trades, and every trade calls this, so `consensus_since` is when the
+    /// current leader last crossed the threshold. Any trade taking it back below clears it.
+    pub fn track_consensus(&mut self, now: i64) -> Result<()> {
+        match self.consensus_leader()? {
+            Some(leader) if self.consensus_since != 0 && leader == self.consensus_outcome => {}
+            Some(leader) => {
+                self.consensus_outcome = leader;
+                self.consensus_since = now;
+            }
+            None => self.consensus_since = 0,
+        }
+
+        Ok(())
+    }
+
+    /// Resolution to the outcome priced at or above [`OUTCOME_CONSENSUS_PERCENTAGE_THRESHOLD`],
+    /// once it has stayed there for [`CONSENSUS_DWELL_SECONDS`]. Like every resolution path it
+    /// goes through [`Market::ensure_resolvable`], so it settles a market left unresolved after
+    /// `resolve_at` and never resolves one early, however long the leader has held.
+    ///
+    /// Rejects with `NoOutcomeHasConsensus` if no outcome is above the threshold, and with
+    /// `ConsensusDwellNotElapsed` if the leader crossed it too recently. Requiring the streak
+    /// means a flash buy that spikes the price just before trading closes can't force a
+    /// resolution.
+    pub fn try_resolve_by_consensus(&mut self, now: i64) -> Result<()> {
+        self.ensure_resolvable(now)?;
+        check_condition!(
+            self.consensus_since != 0 && self.consensus_leader()? == Some(self.consensus_outcome),
+            NoOutcomeHasConsensus
+        );
+        check_condition!(
+            now.saturating_sub(self.consensus_since) >= CONSENSUS_DWELL_SECONDS,
+            ConsensusDwellNotElapsed
+        );
+
+        self.finalize_resolution(MarketResolution::Winner(self.consensus_outcome))
+    }
+
+    /// Oracle resolution to the outcome reported by the oracle account, see [`Market::read_oracle`].
+    pub fn resolve_from_oracle(
+        &mut self,
+        oracle_key: &Pubkey,
+        oracle_data: &[u8],
+        now: i64,
+    ) -> Result<()> {
+        self.ensure_resolvable(now)?;
+        let winning_outcome = self.read_oracle(oracle_key, oracle_data)?;
+        self.finalize_resolution(MarketResolution::Winner(winning_outcome))
+    }
+
+    /// The winning outcome reported by the market's oracle account.
+    ///
+    /// `oracle_key` must be the oracle committed at init, and `oracle_data` must start with an
+    /// [`OracleReport`]. The outcome index itself is validated by [`Market::finalize_resolution`].
+    pub fn read_oracle(&self, oracle_key: &Pubkey, oracle_data: &[u8]) -> Result<u8> {
+        check_condition!(self.oracle != Pubkey::default(), OracleNotSet);
+        check_condition!(*oracle_key == self.oracle, InvalidOracle);
+
+        let report = OracleReport::deserialize(&mut &oracle_data[..])
+            .map_err(|_| error!(ErrorCode::InvalidOracleData))?;
+
+        Ok(report.winning_outcome)
+    }
+
+    /// Total value locked: the vault balance (above rent) less the fees already accrued.
+    pub fn tvl(&self, vault_lamports: u64) -> u64 {
+        vault_lamports.saturating_sub(self.accrued_protocol_fees)
+    }
+
+    /// The part of `subsidy` still backing payouts: all of it until the market resolves, and after
+    /// a resolution to a winner, whose holders redeem the whole vault. Refund mode only returns
+    /// each outcome's reserves, so there the subsidy is released as excess.
+    pub fn locked_subsidy(&self) -> u64 {
+        if self.refund_mode == 1 {
+            0
+        } else {
+            self.subsidy
+        }
+    }
+
+    /// The TVL less the [`Market::locked_subsidy`], what the surplus checks measure against
+    fn unsubsidized_tvl(&self, vault_lamports: u64) -> u64 {
+        self.tvl(vault_lamports)
+            .saturating_sub(self.locked_subsidy())
+    }
+
+    /// Lamports the vault must hold to back the market: what trading paid in by the cost function,
+    /// `C(q) - C(0)`, plus the `initial_liquidity`, and at least the largest outcome supply so its
+    /// holders can be paid one lamport per share unit if it wins.
+    ///
+    /// `C(0) = b * ln(N)` is the market maker's worst-case loss, which only a market seeded with
+    /// at least [`Market::max_loss`] has in the vault. Measuring an unseeded market against
+    /// `cost()` itself would leave it short by that much from its first trade. The difference is
+    /// taken between [`Market::cost_precise`] values, the same ones trades are priced with, and
+    /// rounded down.
+    pub fn required_collateral(&self) -> Result<u64> {
+        let n = self.num_outcomes as usize;
+        check_condition!(n <= MAX_OUTCOMES, InvalidOutcomeIndex);
+
+        let mut fresh = *self;
+        fresh.supplies[..n].fill(0);
+        let traded = self.cost_precise()?.saturating_sub(fresh.cost_precise()?) / D9_U128;
+        let traded = u64::try_from(traded).map_err(|_| error!(ErrorCode::MathOverflow))?;
+
+        let max_supply = self.supplies[..n].iter().copied().max().unwrap_or(0);
+        Ok(traded
+            .checked_add(self.initial_liquidity)
+            .ok_or(error!(ErrorCode::MathOverflow))?
+            .max(max_supply))
+    }
+
+    /// Rounding surplus the admin may sweep: the TVL above [`Market::required_collateral`], not
+    /// counting the subsidy.
+    ///
+    /// Buys round the shares minted down and sells round the payout down, so the vault slowly
+    /// collects more than trading owes by the cost function. Keeping the vault at the required
+    /// collateral keeps every sell payable, since sells telescope to at most `C(q) - C(0)`, and
+    /// the largest outcome backed a lamport per share unit.
+    ///
+    /// An unseeded market only has dust while no outcome's supply is above what was paid in,
+    /// i.e. while its outcomes are bought roughly evenly. One-sided, every lamport it holds backs
+    /// the leading outcome and nothing is swept.
+    ///
+    /// `vault_lamports` is the vault balance above its rent-exempt minimum.
+    pub fn dust(&self, vault_lamports: u64) -> Result<u64> {
+        Ok(self
+            .unsubsidized_tvl(vault_lamports)
+            .saturating_sub(self.required_collateral()?))
+    }
+
+    /// What `sweep_dust` may take: the recorded `accrued_rounding_surplus`, capped by
+    /// [`Market::dust`] so the vault never drops below the required collateral.
+    ///
+    /// Lamports above it that weren't kept from rounding, such as SOL sent straight to the vault,
+    /// are left for [`Market::withdrawable_excess`].
+    pub fn sweepable_surplus(&self, vault_lamports: u64) -> Result<u64> {
+        Ok(self
+            .accrued_rounding_surplus
+            .min(self.dust(vault_lamports)?))
+    }
+
+    /// Lamports in the vault that no trader can claim, e.g. SOL transferred directly to the vault
+    /// PDA: the TVL above everything the vault may still owe and the locked subsidy.
+    ///
+    /// Before resolution (and in refund mode) the vault may owe the largest of:
+    /// - `cost()`, which bounds the total sells can draw since their payouts telescope to it
+    /// - the largest outcome supply, paid one lamport per share unit if that outcome wins. This is
+    ///   `<= cost()` in exact arithmetic, but `cost()` is floored and only carries the market's
+    ///   fixed-point precision, so it is checked on its own rather than trusted
+    /// - `Σ reserves`, the deposits a refund would return
+    ///
+    /// Once resolved to a held outcome the whole TVL is the winners' redeemable pool, so nothing
+    /// is excess.
+    ///
+    /// `vault_lamports` is the vault balance above its rent-exempt minimum.
+    pub fn withdrawable_excess(&self, vault_lamports: u64) -> Result<u64> {
+        let n = self.num_outcomes as usize;
+        check_condition!(n <= MAX_OUTCOMES, InvalidOutcomeIndex);
+
+        if self.resolved == 1 && self.refund_mode == 0 {
+            return Ok(0);
+        }
+
+        let max_supply = self.supplies[..n].iter().copied().max().unwrap_or(0);
+        let total_reserves = self.reserves[..n]
+            .iter()
+            .try_fold(0u64, |sum, &r| sum.checked_add(r))
+            .ok_or(error!(ErrorCode::MathOverflow))?;
+        let owed = self.cost()?.max(max_supply).max(total_reserves);
+
+        Ok(self.unsubsidized_tvl(vault_lamports).saturating_sub(owed))
+    }
+
+    /// Set aside the TVL fee for the time elapsed since `last_fee_accrual`, returning the lamports
+    /// added to `accrued_protocol_fees`.
+    ///
+    /// `fee = tvl * fee_config.tvl_fee * elapsed / YEAR_IN_SECONDS`, with two bounds:
+    /// - accrual stops at `resolve_at` or at resolution, after which the vault belongs to the
+    ///   winners. Every resolution path accrues right before resolving, so the fees are final
+    ///   before anyone can redeem, and redemptions only ever draw on [`Market::tvl`].
+    /// - the fee never eats into the lamports needed to pay the largest outcome supply one
+    ///   lamport per share unit, so redemptions stay fully backed, nor into the locked subsidy
+    ///
+    /// `vault_lamports` is the vault balance above its rent-exempt minimum.
+    pub fn accrue_tvl_fee(&mut self, vault_lamports: u64, now: i64) -> Result<u64> {
+        check_condition!(
+            self.fee_config.tvl_fee as u128 <= MAX_TVL_FEE,
+            InvalidTvlFee
+        );
+        if self.resolved == 1 {
+            return Ok(0);
+        }
+
+        let accrue_until = now.min(self.resolve_at);
+        let elapsed = accrue_until.saturating_sub(self.last_fee_accrual);
+        if elapsed <= 0 {
+            return Ok(0);
+        }
+
+        let tvl = self.tvl(vault_lamports);
+        let fee = U256::from(tvl)
+            .checked_mul(U256::from(self.fee_config.tvl_fee))
+            .and_then(|v| v.checked_mul(U256::from(elapsed as u64)))
+            .ok_or(error!(ErrorCode::MathOverflow))?
+            / (U256::from(YEAR_IN_SECONDS) * D18_U256);
+
+        let max_supply = self.supplies.iter().copied().max().unwrap_or(0);
+        let surplus = self
+            .unsubsidized_tvl(vault_lamports)
+            .saturating_sub(max_supply);
+        let fee = fee.min(U256::from(surplus)).as_u64();
+
+        self.accrued_protocol_fees = self
+            .accrued_protocol_fees
+            .checked_add(fee)
+            .ok_or(error!(ErrorCode::MathOverflow))?;
+        self.last_fee_accrual = accrue_until;
+
+        Ok(fee)
+    }
+
+    /// Burn `shares` of an outcome from a resolved market and compute the lamports paid for them.
+    ///
+    /// `shares` and `supplies` are in the same unit, share units of 1e9 per whole share, see
+    /// [`Market::check_mint_supply`].
+    ///
+    /// - Normal mode: only the winning outcome redeems, for `vault_available * shares / supply`.
+    ///   Redeeming reduces the supply and the vault together, so every holder gets the same rate.
+    ///   The vault includes any `subsidy`, which raises that rate above what trades paid in.
+    /// - Refund mode: every outcome redeems, for `reserves[i] * shares / supplies[i]`.
+    ///   The lamports deposited into each outcome are returned pro rata to its holders.
+    ///
+    /// `vault_available` is the vault balance above its rent-exempt minimum. Accrued fees are
+    /// excluded from it.
+    pub fn redeem(&mut self, outcome_index: u8, shares: u64, vault_available: u64) -> Result<u64> {
+        check_condition!(self.resolved == 1, MarketNotResolved);
+        let outcome_index = self.checked_outcome(outcome_index)?;
+        check_condition!(shares > 0, BurnIsZero);
+
+        let vault_available = self.tvl(vault_available);
+
+        let (supply, pool) = if self.refund_mode == 1 {
+            (
+                self.supplies[outcome_index],
+                self.reserves[outcome_index].min(vault_available),
+            )
+        } else {
+            check_condition!(
+                outcome_index == self.winning_outcome as usize,
+                OutcomeNotWinner
+            );
+            (self.winning_supply()?, vault_available)
+        };
+        check_condition!(shares <= supply, BurnIsMoreThanSupply);
+
+        let payout = ((pool as u128)
+            .checked_mul(shares as u128)
+            .ok_or(error!(ErrorCode::MathOverflow))?
+            / supply as u128) as u64;
+
+        self.supplies[outcome_index] -= shares;
+        if self.refund_mode == 1 {
+            self.reserves[outcome_index] -= payout;
+        }
+
+        Ok(payout)
+    }
+
+    /// Record the current prices as the reference for [`Market::price_delta_since_snapshot`].
+    ///
+    /// Permissionless, so it is rate limited to once per [`DAY_IN_SECONDS`].
+    pub fn snapshot_prices(&mut self, now: i64) -> Result<()> {
+        check_condition!(
+            self.snapshot_at == 0
+                || self
+                    .snapshot_at
+                    .checked_add(DAY_IN_SECONDS as i64)
+                    .is_some_and(|next| now >= next),
+            SnapshotTooSoon
+        );
+
+        let n = self.num_outcomes as usize;
+        check_condition!(n <= MAX_OUTCOMES, InvalidOutcomeIndex);
+        for i in 0..n {
+            self.price_snapshot[i] = self.price(i as u8)?;
+        }
+        self.snapshot_at = now;
+
+        Ok(())
+    }
+
+    /// Signed change of an outcome's price relative to `price_snapshot`, in basis points.
+    /// e.g. a price moving from 0.50 to 0.55 is +1000 (+10%).
+    pub fn price_delta_since_snapshot(&self, outcome_index: u8) -> Result<i64> {
+        let index = self.checked_outcome(outcome_index)?;
+
+        let snapshot_price = self.price_snapshot[index] as i128;
+        check_condition!(self.snapshot_at != 0 && snapshot_price > 0, NoPriceSnapshot);
+
+        let price = self.price(outcome_index)? as i128;
+        let delta_bps = (price - snapshot_price) * 10_000 / snapshot_price;
+
+        i64::try_from(delta_bps).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+
+    /// Bring `price_cumulative` up to `now`, weighting the current prices by the seconds since
+    /// `price_cumulative_at`. Must run before anything moves the prices.
+    pub fn accumulate_prices(&mut self, now: i64) -> Result<()> {
+        for i in 0..self.num_outcomes as usize {
+            self.price_cumulative[i] = self.price_cumulative_now(i as u8, now)?;
+        }
+        self.price_cumulative_at = now.max(self.price_cumulative_at);
+
+        Ok(())
+    }
+
+    /// `price_cumulative[outcome_index]` as of `now`, including the seconds since the last
+    /// update at the current price, without mutating the market. This is the value a client
+    /// captures (with `now`) as one end of a [`Market::twap_between`] window.
+    pub fn price_cumulative_now(&self, outcome_index: u8, now: i64) -> Result<u64> {
+        let index = self.checked_outcome(outcome_index)?;
+
+        let elapsed = now.saturating_sub(self.price_cumulative_at).max(0) as u64;
+        if elapsed == 0 {
+            return Ok(self.price_cumulative[index]);
+        }
+
+        Ok(self.price_cumulative[index]
+            .wrapping_add(self.price(outcome_index)?.wrapping_mul(elapsed)))
+    }
+
+    /// Time-weighted average price (scaled by 1e9) of an outcome between two captured
+    /// `(price_cumulative, unix_timestamp)` points, from the deltas alone.
+    ///
+    /// The accumulator is stored as u64 (a u128 would change the zero copy alignment) and the
+    /// delta is taken modulo 2^64, so a window may span a wrap. Clients can pass either the raw
+    /// u64 values or values they widened to u128.
+    pub fn twap_between(
+        &self,
+        start: (u128, i64),
+        end: (u128, i64),
+        outcome_index: u8,
+    ) -> Result<u64> {
+        self.checked_outcome(outcome_index)?;
+        check_condition!(end.1 > start.1, InvalidTwapWindow);
+
+        let delta = (end.0 as u64).wrapping_sub(start.0 as u64);
+        let elapsed = end
+            .1
+            .checked_sub(start.1)
+            .ok_or(error!(ErrorCode::InvalidTwapWindow))? as u64;
+
+        Ok(delta / elapsed)
     }
 }
@@ -1,3 +1,7 @@
+pub mod bettor_position;
 pub mod market;
+pub mod registry;
 
+pub use bettor_position::*;
 pub use market::*;
+pub use registry::*;
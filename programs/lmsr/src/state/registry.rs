@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+use common::constants::REGISTRY_PAGE_CAPACITY;
+
+/// Program-wide index of markets, so frontends can list them without scanning every account.
+///
+/// Singleton PDA of [`common::constants::REGISTRY_SEED`], created once by `init_registry`. The
+/// market keys themselves live in [`RegistryPage`]s of [`REGISTRY_PAGE_CAPACITY`] each, so no
+/// account grows without bound: market `i` is entry `i % REGISTRY_PAGE_CAPACITY` of page
+/// `i / REGISTRY_PAGE_CAPACITY`.
+#[account]
+#[derive(InitSpace, Default)]
+pub struct Registry {
+    /// Markets registered so far, across all pages
+    pub market_count: u64,
+
+    /// Bump for this [`Registry`]
+    pub bump: u8,
+}
+
+impl Registry {
+    pub const SIZE: usize = 8 + Registry::INIT_SPACE;
+
+    /// Page the next registered market goes into
+    pub fn next_page(&self) -> u32 {
+        (self.market_count / REGISTRY_PAGE_CAPACITY as u64) as u32
+    }
+
+    /// Number of pages holding at least one market
+    pub fn page_count(&self) -> u32 {
+        self.market_count.div_ceil(REGISTRY_PAGE_CAPACITY as u64) as u32
+    }
+}
+
+/// One page of [`Registry`] entries, PDA of [`common::constants::REGISTRY_SEED`] and the page
+/// index as little endian u32. Created by the `init_market` that registers its first market.
+#[account]
+#[derive(InitSpace, Default)]
+pub struct RegistryPage {
+    /// Index of this page
+    pub page: u32,
+
+    /// Registered markets, in creation order
+    #[max_len(REGISTRY_PAGE_CAPACITY)]
+    pub markets: Vec<Pubkey>,
+}
+
+impl RegistryPage {
+    pub const SIZE: usize = 8 + RegistryPage::INIT_SPACE;
+}
+
+// A page is created by CPI from `init_market`, which caps new accounts at 10 KB
+const _: () = assert!(RegistryPage::SIZE <= MAX_PERMITTED_DATA_INCREASE);
@@ -1,5 +1,13 @@
 use anchor_lang::prelude::*;
 use bytemuck::{Pod, Zeroable};
+use common::constants::{
+    BPS_DENOMINATOR, MAX_CREATION_FEE, MAX_OUTCOMES, MAX_RESOLVE_HORIZON, MAX_TRADE_FEE_BPS,
+    MAX_TVL_FEE, MINIMUM_OUTCOMES_PER_MARKET, MIN_MARKET_DURATION, OUTCOME_MINT_DECIMALS,
+    REFERENCE_TRADE_LAMPORTS, VOID_OUTCOME,
+};
+use common::{check_condition, errors::ErrorCode};
+
+use crate::state::Market;
 
 /// The maximum length of a fixed size string in bytes.
 pub const MAX_PADDED_STRING_LENGTH: usize = 128;
@@ -43,9 +51,354 @@ impl FixedSizeString {
         Self { value }
     }
 
+    /// The meaningful bytes of the string, up to the first padding (zero) byte.
+    pub fn trimmed(&self) -> &[u8] {
+        let length = self
+            .value
+            .iter()
+            .position(|&byte| byte == 0)
+            .unwrap_or(MAX_PADDED_STRING_LENGTH);
+
+        &self.value[..length]
+    }
+
+    /// Returns a copy with every byte after the meaningful bytes zeroed, so that
+    /// logically-equal strings have identical `value` buffers.
+    pub fn normalized(&self) -> Self {
+        let trimmed = self.trimmed();
+
+        let mut value = [0u8; MAX_PADDED_STRING_LENGTH];
+        value[..trimmed.len()].copy_from_slice(trimmed);
+
+        Self { value }
+    }
+
+    /// Hash of the normalized value, used as a PDA seed.
+    /// Residual bytes after the padding never affect the derived address.
     pub fn as_bytes(&self) -> [u8; 32] {
-        anchor_lang::solana_program::hash::hash(&self.value).to_bytes()
+        anchor_lang::solana_program::hash::hash(&self.normalized().value).to_bytes()
+    }
+}
+
+/// Equality ignores any residual bytes after the first padding byte.
+impl PartialEq for FixedSizeString {
+    fn eq(&self, other: &Self) -> bool {
+        self.trimmed() == other.trimmed()
+    }
+}
+
+impl Eq for FixedSizeString {}
+
+/// Hashes only the meaningful bytes, consistent with [`PartialEq`].
+impl std::hash::Hash for FixedSizeString {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.trimmed().hash(state);
+    }
+}
+
+/// Fees of a [`crate::state::Market`], set at init and fixed for its lifetime.
+///
+/// Every fee stays in the vault, set aside in `accrued_protocol_fees`, so it is excluded from the TVL
+/// that backs outcome shares.
+#[derive(
+    InitSpace,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    AnchorSerialize,
+    AnchorDeserialize,
+    Pod,
+    Zeroable,
+)]
+#[repr(C)]
+pub struct FeeConfig {
+    /// Fee on the lamports paid into a buy, in basis points. At most `MAX_TRADE_FEE_BPS`.
+    pub buy_bps: u16,
+
+    /// Fee on the lamports paid out by a sell, in basis points. At most `MAX_TRADE_FEE_BPS`.
+    pub sell_bps: u16,
+
+    /// Padding for zero copy alignment
+    pub _padding: [u8; 4],
+
+    /// Lamports the admin pays into the vault at init. At most `MAX_CREATION_FEE`.
+    pub creation_fee: u64,
+
+    /// Annual fee on the vault's TVL, D18{1/year}. At most `MAX_TVL_FEE`, 0 disables it.
+    pub tvl_fee: u64,
+}
+
+impl FeeConfig {
+    pub fn validate(&self) -> Result<()> {
+        check_condition!(
+            self.buy_bps <= MAX_TRADE_FEE_BPS && self.sell_bps <= MAX_TRADE_FEE_BPS,
+            TradeFeeTooHigh
+        );
+        check_condition!(self.creation_fee <= MAX_CREATION_FEE, CreationFeeTooHigh);
+        check_condition!(self.tvl_fee as u128 <= MAX_TVL_FEE, InvalidTvlFee);
+
+        Ok(())
     }
+
+    /// Fee on `amount_in` lamports paid into a buy, rounded down
+    pub fn buy_fee(&self, amount_in: u64) -> u64 {
+        bps_of(amount_in, self.buy_bps)
+    }
+
+    /// Fee on `payout` lamports paid out by a sell, rounded down
+    pub fn sell_fee(&self, payout: u64) -> u64 {
+        bps_of(payout, self.sell_bps)
+    }
+}
+
+fn bps_of(amount: u64, bps: u16) -> u64 {
+    // At most `amount` for bps <= BPS_DENOMINATOR, so the cast can't truncate
+    (amount as u128 * bps.min(BPS_DENOMINATOR as u16) as u128 / BPS_DENOMINATOR as u128) as u64
+}
+
+/// Parameters for creating a [`crate::state::Market`].
+#[derive(Debug, Default, Clone, Copy, AnchorSerialize, AnchorDeserialize)]
+pub struct InitMarketParams {
+    /// Number of outcomes (N)
+    pub num_outcomes: u8,
+
+    /// LMSR liquidity parameter 'b' (in lamports)
+    ///
+    /// This is the only knob for early-trade volatility: prices depend on the differences between
+    /// supplies, so seeding every outcome with the same supply would not soften the first trade.
+    /// The admin's worst case loss is `b * ln(N)`.
+    pub scale: u64,
+
+    /// When the market will resolve and halt trading, as a unix timestamp in seconds.
+    /// Must be in the future relative to the cluster clock.
+    pub resolve_at: i64,
+
+    /// Unique label the market PDA is derived from. Reusing one fails with `MarketLabelAlreadyExists`.
+    pub label: FixedSizeString,
+
+    /// Lamports the admin must seed into the vault at init. 0 disables the requirement.
+    pub require_initial_liquidity: u64,
+
+    /// Extra lamports the admin pays into the vault as a subsidy, e.g. for liquidity mining.
+    /// Recorded as [`crate::state::Market::subsidy`], they go to the winners and can't be
+    /// withdrawn as excess. 0 for no subsidy.
+    pub initial_subsidy: u64,
+
+    /// Maximum lamports a single outcome's reserve may reach. 0 disables the cap.
+    pub max_outcome_reserve: u64,
+
+    /// Oracle account that may resolve the market via `resolve_from_oracle`, see [`OracleReport`].
+    /// None leaves resolution to the admin.
+    pub oracle: Option<Pubkey>,
+
+    /// Trade, creation and TVL fees, see [`FeeConfig`]. The default charges nothing.
+    pub fee_config: FeeConfig,
+
+    /// Run the LMSR math at 1e18 instead of 1e9, for markets large enough that the 1e9 rounding
+    /// matters. Costs more compute units per trade.
+    pub high_precision: bool,
+
+    /// Mint an SPL token per outcome for the shares. False records each holder's shares in a
+    /// [`crate::state::BettorPosition`] PDA instead, traded with `buy_position`, `sell_position`
+    /// and `redeem_position`.
+    ///
+    /// Tokens can be transferred and used by other programs (AMMs, lending, wallets), but each
+    /// market pays rent for N mints and each holder for a token account per outcome. Positions
+    /// need no mints and one account per holder, but only their owner can sell or redeem them.
+    pub tokenized: bool,
+
+    /// Waive the buy and sell fees until this unix timestamp, at most `resolve_at`. 0 charges
+    /// them from the start. The admin can change it later with `set_fee_holiday`.
+    pub fee_free_until: i64,
+
+    /// Halt trading this many seconds before `resolve_at`, so no one can trade on a result learned
+    /// just before resolution. Must leave some trading time, 0 trades until `resolve_at`.
+    pub trading_halt_before_resolve: i64,
+
+    /// Decimals of the outcome mints, at most 9. 0 uses the default of 9. The LMSR math stays at
+    /// 1e9 per share either way, fewer decimals only make the shares minted coarser, see
+    /// [`crate::state::Market::share_unit`].
+    pub outcome_decimals: u8,
+}
+
+impl InitMarketParams {
+    /// Validate the parameters against the current time, before anything is written.
+    ///
+    /// A market needs at least [`MINIMUM_OUTCOMES_PER_MARKET`] outcomes. A single outcome would
+    /// always be priced at 1.0 and every trade would just be a deposit into the vault, which has
+    /// no use as a market (and no use as an escrow either, since nothing ever resolves against it).
+    ///
+    /// `resolve_at` must be a positive unix timestamp in the future, at most
+    /// [`MAX_RESOLVE_HORIZON`] away, so timestamp arithmetic on it can't overflow.
+    ///
+    /// `scale` must be able to price a buy of [`REFERENCE_TRADE_LAMPORTS`]. Below roughly 1/18th
+    /// of that the first buy alone takes the supply past [`crate::state::MAX_EXP_ARG`] times `b`,
+    /// and the market would only fail once someone tried to trade. The same buy must also mint at
+    /// least one token at `outcome_decimals`, which rules out the coarsest decimals.
+    pub fn validate(&self, now: i64) -> Result<()> {
+        check_condition!(
+            self.num_outcomes >= MINIMUM_OUTCOMES_PER_MARKET,
+            NotEnoughOutcomes
+        );
+        // Rejects zero, negative and far future timestamps before any time math runs on them
+        check_condition!(
+            self.resolve_at > 0
+                && self
+                    .resolve_at
+                    .checked_sub(now)
+                    .is_some_and(|duration| duration <= MAX_RESOLVE_HORIZON),
+            InvalidResolveTime
+        );
+        check_condition!(self.resolve_at > now, ResolveTimeInPast);
+        check_condition!(
+            now.checked_add(MIN_MARKET_DURATION)
+                .is_some_and(|earliest| earliest < self.resolve_at),
+            MarketTooQuick
+        );
+        check_condition!(self.num_outcomes as usize <= MAX_OUTCOMES, TooManyOutcomes);
+        check_condition!(
+            self.label.value.len() <= MAX_PADDED_STRING_LENGTH,
+            InvalidLabelLength
+        );
+        self.fee_config.validate()?;
+        check_condition!(self.fee_free_until <= self.resolve_at, InvalidFeeHoliday);
+        check_condition!(
+            self.trading_halt_before_resolve >= 0
+                && self
+                    .resolve_at
+                    .checked_sub(self.trading_halt_before_resolve)
+                    .is_some_and(|close| close > now),
+            InvalidTradingWindow
+        );
+        check_condition!(self.scale > 0, LiquidityParameterIsZero);
+        check_condition!(
+            self.outcome_decimals <= OUTCOME_MINT_DECIMALS,
+            InvalidOutcomeDecimals
+        );
+
+        // Simulate a reference trade on the empty market, so a `scale` that cannot price one
+        // fails here instead of on the first buy
+        let market = Market {
+            num_outcomes: self.num_outcomes,
+            scale: self.scale,
+            high_precision: self.high_precision as u8,
+            outcome_decimals: self.outcome_decimals,
+            ..Default::default()
+        };
+        market
+            .quote_buy(0, REFERENCE_TRADE_LAMPORTS, now)
+            .map_err(|_| error!(ErrorCode::ScaleTooSmall))?;
+
+        Ok(())
+    }
+}
+
+/// One outcome of a basket trade, see `sell_basket`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub struct BasketLeg {
+    pub outcome_index: u8,
+    pub shares: u64,
+}
+
+/// How a [`crate::state::Market`] was resolved, see [`crate::state::Market::resolution`].
+///
+/// Stored in the zero copy account as `winning_outcome`, with [`VOID_OUTCOME`] for `Void`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub enum MarketResolution {
+    /// The outcome that happened. Its holders split the vault, or every outcome is refunded if
+    /// nobody holds it.
+    Winner(u8),
+
+    /// The event was cancelled or can't be adjudicated. Every outcome is refunded, each holder
+    /// getting back their share of what was paid into the outcome they hold.
+    Void,
+}
+
+impl MarketResolution {
+    /// The resolution `resolve_market` is asked for: [`VOID_OUTCOME`] voids, anything else
+    /// names the winner
+    pub fn from_outcome(winning_outcome: u8) -> Self {
+        if winning_outcome == VOID_OUTCOME {
+            Self::Void
+        } else {
+            Self::Winner(winning_outcome)
+        }
+    }
+}
+
+/// Data layout of an oracle account committed to a [`crate::state::Market`].
+///
+/// The account data starts with the Borsh encoding of this struct, without an Anchor
+/// discriminator. Trailing bytes are ignored so oracles can extend the layout.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub struct OracleReport {
+    /// Index of the outcome the oracle reports as the winner
+    pub winning_outcome: u8,
+}
+
+/// Point-in-time summary of a [`crate::state::Market`], see [`crate::state::Market::snapshot`].
+/// Per-outcome entries past `num_outcomes` are zero.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub struct MarketSnapshot {
+    /// Price of each outcome, scaled by 1e9
+    pub prices: [u64; MAX_OUTCOMES],
+
+    /// `dp/dq` of each outcome, scaled by 1e18, see
+    /// [`crate::state::Market::price_sensitivity`]
+    pub price_sensitivity: [u64; MAX_OUTCOMES],
+
+    /// LMSR cost function in lamports
+    pub cost: u64,
+
+    /// Mark-to-market lamport value of the outstanding shares of each outcome
+    pub open_interest: [u64; MAX_OUTCOMES],
+
+    /// Collateralization and time status, see [`crate::state::Market::health_factor`]
+    pub health: MarketHealth,
+
+    /// Lamports to move the favorite's price by 1%, see
+    /// [`crate::state::Market::liquidity_depth`]
+    pub liquidity_depth: u64,
+}
+
+/// Result of [`crate::state::Market::quote_buy`], the return data of `quote_cpi`.
+///
+/// Borsh encoded: `shares_out` then `new_price`, both little-endian u64.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub struct BuyQuote {
+    /// Shares (1e9 units) the buy would mint
+    pub shares_out: u64,
+
+    /// Price of the bought outcome after the buy, scaled by 1e9
+    pub new_price: u64,
+}
+
+/// Result of [`crate::state::Market::buy_capped`], the return data of `buy_capped`.
+///
+/// Borsh encoded: `shares_out` then `amount_filled`, both little-endian u64.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub struct CappedFill {
+    /// Shares (1e9 units) minted
+    pub shares_out: u64,
+
+    /// Lamports of `amount_in` that were filled and paid in, fee included. The rest never left
+    /// the buyer.
+    pub amount_filled: u64,
+}
+
+/// Summary of a [`crate::state::Market`]'s health for operators.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub struct MarketHealth {
+    /// TVL / [`crate::state::Market::required_collateral`], scaled by 1e9. Below 1e9 either the
+    /// vault holds less than trading paid in, which points to an accounting bug, or a market
+    /// seeded with less than `max_loss()` couldn't pay its largest outcome a lamport per share unit.
+    pub collateralization: u64,
+
+    /// Seconds until `resolve_at`, zero once it has passed
+    pub seconds_to_resolve: i64,
 }
 
 #[derive(Debug, Default)]
@@ -0,0 +1,68 @@
+mod utils;
+
+use common::errors::ErrorCode;
+use lmsr::types::InitMarketParams;
+use solana_sdk::signer::Signer;
+use utils::*;
+
+#[test]
+fn test_admin_withdraw_excess_only_takes_direct_transfers() {
+    let (mut svm, admin) = setup();
+    // Seed the worst case loss so the vault starts fully backed
+    let params = InitMarketParams {
+        require_initial_liquidity: 700_000_000,
+        ..market_params("withdraw_excess")
+    };
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+
+    let buyer = funded_keypair(&mut svm);
+    send_ix(
+        &mut svm,
+        buy_shares_ix(&buyer.pubkey(), &accounts.market, 0, 1_000_000_000, 0),
+        &buyer,
+    )
+    .unwrap();
+
+    let vault_rent = svm.minimum_balance_for_rent_exemption(0);
+    let excess_before = load_market(&svm, &accounts.market)
+        .withdrawable_excess(svm.get_balance(&accounts.market_vault).unwrap() - vault_rent)
+        .unwrap();
+
+    // Someone sends SOL straight to the vault PDA
+    let airdrop = 3_000_000_000;
+    svm.airdrop(&accounts.market_vault, airdrop).unwrap();
+
+    // Only admin can withdraw
+    assert!(send_ix(
+        &mut svm,
+        admin_withdraw_excess_ix(&buyer.pubkey(), &accounts.market),
+        &buyer,
+    )
+    .is_err());
+
+    let vault_before = svm.get_balance(&accounts.market_vault).unwrap();
+    send_ix(
+        &mut svm,
+        admin_withdraw_excess_ix(&admin.pubkey(), &accounts.market),
+        &admin,
+    )
+    .unwrap();
+    let withdrawn = vault_before - svm.get_balance(&accounts.market_vault).unwrap();
+    assert_eq!(withdrawn, airdrop + excess_before);
+    // The only other excess is the seed's margin above b * ln(2) ≈ 0.693 SOL, plus rounding
+    assert!(excess_before < 7_000_000, "{}", excess_before);
+
+    // The vault still backs the market, and nothing more can be withdrawn
+    let market = load_market(&svm, &accounts.market);
+    let vault_available = svm.get_balance(&accounts.market_vault).unwrap() - vault_rent;
+    assert!(vault_available >= market.cost().unwrap());
+    assert_error(
+        send_ix(
+            &mut svm,
+            admin_withdraw_excess_ix(&admin.pubkey(), &accounts.market),
+            &admin,
+        ),
+        ErrorCode::NothingToWithdraw,
+    );
+}
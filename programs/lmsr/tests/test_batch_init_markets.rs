@@ -0,0 +1,75 @@
+mod utils;
+
+use anchor_spl::token::spl_token;
+use common::errors::ErrorCode;
+use lmsr::types::InitMarketParams;
+use solana_program::program_pack::Pack;
+use solana_sdk::signer::Signer;
+use spl_token::solana_program;
+use utils::*;
+
+#[test]
+fn test_batch_init_markets() {
+    let (mut svm, admin) = setup();
+    let params = vec![
+        market_params("election_candidate_a"),
+        market_params("election_candidate_b"),
+        InitMarketParams {
+            num_outcomes: 3,
+            ..market_params("election_turnout")
+        },
+    ];
+
+    send_ix(
+        &mut svm,
+        batch_init_markets_ix(&admin.pubkey(), params.clone()),
+        &admin,
+    )
+    .unwrap();
+
+    for market_params in params.iter() {
+        let accounts = market_accounts(&market_params.label, market_params.num_outcomes);
+
+        let market = load_market(&svm, &accounts.market);
+        assert_eq!(market.admin, admin.pubkey());
+        assert_eq!(market.num_outcomes, market_params.num_outcomes);
+        assert_eq!(market.label, market_params.label);
+
+        let vault = svm.get_account(&accounts.market_vault).unwrap();
+        assert_eq!(vault.owner, lmsr::id());
+
+        for mint in accounts.outcome_mints.iter() {
+            let mint_account = svm.get_account(mint).unwrap();
+            let mint = spl_token::state::Mint::unpack(&mint_account.data).unwrap();
+            assert_eq!(mint.mint_authority.unwrap(), accounts.market);
+        }
+    }
+}
+
+#[test]
+fn test_batch_init_markets_is_atomic() {
+    let (mut svm, admin) = setup();
+    let params = vec![
+        market_params("batch_ok_a"),
+        market_params("batch_ok_b"),
+        // Invalid: a market needs at least two outcomes
+        InitMarketParams {
+            num_outcomes: 1,
+            ..market_params("batch_invalid")
+        },
+    ];
+
+    assert_error(
+        send_ix(
+            &mut svm,
+            batch_init_markets_ix(&admin.pubkey(), params.clone()),
+            &admin,
+        ),
+        ErrorCode::NotEnoughOutcomes,
+    );
+
+    for market_params in params.iter() {
+        let accounts = market_accounts(&market_params.label, market_params.num_outcomes);
+        assert!(svm.get_account(&accounts.market).is_none());
+    }
+}
@@ -0,0 +1,611 @@
+mod utils;
+
+use anchor_lang::AnchorDeserialize;
+use anchor_spl::token::spl_token;
+use common::errors::ErrorCode;
+use lmsr::events::SharesBought;
+use lmsr::types::{BuyQuote, CappedFill, FeeConfig, InitMarketParams};
+use solana_program::program_pack::Pack;
+use solana_sdk::signer::Signer;
+use spl_token::solana_program;
+use utils::*;
+
+/// Init a market and run real `buy_shares` transactions through the program, checking the
+/// lamport transfer, the mint CPI and the market state together.
+#[test]
+fn test_buy_shares_end_to_end() {
+    let (mut svm, admin) = setup();
+    let params = market_params("buy_end_to_end");
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+
+    let buyer = funded_keypair(&mut svm);
+    let vault_before = svm.get_balance(&accounts.market_vault).unwrap();
+    let buyer_before = svm.get_balance(&buyer.pubkey()).unwrap();
+
+    // The expected result, computed off-chain on the same state
+    let mut expected = load_market(&svm, &accounts.market);
+    let expected_shares = expected
+        .buy_shares(0, 1_000_000_000, START_TIMESTAMP)
+        .unwrap();
+
+    let result = send_ix(
+        &mut svm,
+        buy_shares_ix(&buyer.pubkey(), &accounts.market, 0, 1_000_000_000, 0),
+        &buyer,
+    )
+    .unwrap();
+    let shares = u64::from_le_bytes(result.return_data.data.try_into().unwrap());
+    assert_eq!(shares, expected_shares);
+
+    // Market state
+    let market = load_market(&svm, &accounts.market);
+    assert_eq!(market.supplies, expected.supplies);
+    assert_eq!(market.reserves[0], 1_000_000_000);
+    assert_eq!(market.supplies[1], 0);
+
+    // Lamports moved into the vault, the buyer also paid the fee and the token account rent
+    let vault_after = svm.get_balance(&accounts.market_vault).unwrap();
+    assert_eq!(vault_after - vault_before, 1_000_000_000);
+    assert!(buyer_before - svm.get_balance(&buyer.pubkey()).unwrap() > 1_000_000_000);
+
+    // Shares minted to the buyer's associated token account
+    assert_eq!(
+        outcome_balance(&svm, &buyer.pubkey(), &accounts.market, 0),
+        shares
+    );
+    assert_eq!(
+        outcome_balance(&svm, &buyer.pubkey(), &accounts.market, 1),
+        0
+    );
+
+    // A second buy reuses the token account and the mint supply tracks the market
+    let result = send_ix(
+        &mut svm,
+        buy_shares_ix(&buyer.pubkey(), &accounts.market, 0, 500_000_000, 0),
+        &buyer,
+    )
+    .unwrap();
+    let more = u64::from_le_bytes(result.return_data.data.try_into().unwrap());
+    let market = load_market(&svm, &accounts.market);
+    assert_eq!(market.supplies[0], shares + more);
+    assert_eq!(
+        outcome_balance(&svm, &buyer.pubkey(), &accounts.market, 0),
+        market.supplies[0]
+    );
+    let mint = svm.get_account(&accounts.outcome_mints[0]).unwrap();
+    let mint = spl_token::state::Mint::unpack(&mint.data).unwrap();
+    assert_eq!(mint.supply, market.supplies[0]);
+
+    // Slippage protection rejects without moving anything
+    assert_error(
+        send_ix(
+            &mut svm,
+            buy_shares_ix(&buyer.pubkey(), &accounts.market, 0, 1_000_000, u64::MAX),
+            &buyer,
+        ),
+        ErrorCode::SlippageExceeded,
+    );
+    assert_eq!(
+        load_market(&svm, &accounts.market).supplies,
+        market.supplies
+    );
+}
+
+#[test]
+fn test_buy_shares_rejects_corrupted_num_outcomes() {
+    let (mut svm, admin) = setup();
+    let params = market_params("corrupted_outcomes");
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+
+    let mut market = load_market(&svm, &accounts.market);
+    market.num_outcomes = 17;
+    store_market(&mut svm, &accounts.market, &market);
+
+    // Caught by the invariant check before the LMSR loops index past the arrays
+    assert_error(
+        send_ix(
+            &mut svm,
+            buy_shares_ix(&admin.pubkey(), &accounts.market, 0, 1_000_000_000, 0),
+            &admin,
+        ),
+        ErrorCode::TooManyOutcomes,
+    );
+
+    market.num_outcomes = 0;
+    store_market(&mut svm, &accounts.market, &market);
+    assert_error(
+        send_ix(
+            &mut svm,
+            buy_shares_ix(&admin.pubkey(), &accounts.market, 0, 1_000_000_000, 0),
+            &admin,
+        ),
+        ErrorCode::NotEnoughOutcomes,
+    );
+}
+
+#[test]
+fn test_buy_shares_trading_window() {
+    let (mut svm, admin) = setup();
+    let params = market_params("trading_window");
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+
+    let buyer = funded_keypair(&mut svm);
+    let buy = buy_shares_ix(&buyer.pubkey(), &accounts.market, 0, 1_000_000_000, 0);
+    let open_at = params.resolve_at - 5;
+
+    let outsider = funded_keypair(&mut svm);
+    assert!(send_ix(
+        &mut svm,
+        set_trading_window_ix(&outsider.pubkey(), &accounts.market, open_at),
+        &outsider,
+    )
+    .is_err());
+    send_ix(
+        &mut svm,
+        set_trading_window_ix(&admin.pubkey(), &accounts.market, open_at),
+        &admin,
+    )
+    .unwrap();
+
+    // Before the window opens
+    warp_to(&mut svm, open_at - 1);
+    assert_error(
+        send_ix(&mut svm, buy.clone(), &buyer),
+        ErrorCode::MarketPaused,
+    );
+
+    // During
+    warp_to(&mut svm, open_at);
+    send_ix(&mut svm, buy.clone(), &buyer).unwrap();
+    assert!(outcome_balance(&svm, &buyer.pubkey(), &accounts.market, 0) > 0);
+
+    // After, once resolve_at is reached
+    warp_to(&mut svm, params.resolve_at);
+    assert_error(send_ix(&mut svm, buy, &buyer), ErrorCode::MarketExpired);
+}
+
+#[test]
+fn test_buy_shares_missing_outcome_mint() {
+    let (mut svm, admin) = setup();
+    let params = market_params("missing_mint");
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(
+        &mut svm,
+        init_market_without_mints_ix(&admin.pubkey(), params),
+        &admin,
+    )
+    .unwrap();
+    send_ix(
+        &mut svm,
+        init_outcome_mint_ix(&admin.pubkey(), &accounts.market, 0),
+        &admin,
+    )
+    .unwrap();
+    assert!(svm.get_account(&accounts.outcome_mints[1]).is_none());
+
+    // Outcome 1's mint was never created: rejected up front, not inside the token CPI
+    let buyer = funded_keypair(&mut svm);
+    assert_error(
+        send_ix(
+            &mut svm,
+            buy_shares_ix(&buyer.pubkey(), &accounts.market, 1, 1_000_000_000, 0),
+            &buyer,
+        ),
+        ErrorCode::OutcomeMintNotInitialized,
+    );
+
+    // Outcome 0's mint exists, but trading waits for every mint
+    assert_error(
+        send_ix(
+            &mut svm,
+            buy_shares_ix(&buyer.pubkey(), &accounts.market, 0, 1_000_000_000, 0),
+            &buyer,
+        ),
+        ErrorCode::MintsNotReady,
+    );
+}
+
+/// A 6 decimal market mints one token base unit per 1000 share units and trades like a 9 decimal one
+#[test]
+fn test_buy_shares_six_decimal_mints() {
+    let (mut svm, admin) = setup();
+    let params = InitMarketParams {
+        outcome_decimals: 6,
+        ..market_params("six_decimals")
+    };
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+
+    let mut expected = load_market(&svm, &accounts.market);
+    let expected_shares = expected
+        .buy_shares(0, 1_000_000_000, START_TIMESTAMP)
+        .unwrap();
+
+    let buyer = funded_keypair(&mut svm);
+    let result = send_ix(
+        &mut svm,
+        buy_shares_ix(&buyer.pubkey(), &accounts.market, 0, 1_000_000_000, 0),
+        &buyer,
+    )
+    .unwrap();
+    let shares = u64::from_le_bytes(result.return_data.data.try_into().unwrap());
+    assert_eq!(shares, expected_shares);
+    assert_eq!(shares % 1_000, 0);
+
+    let market = load_market(&svm, &accounts.market);
+    assert_eq!(market.supplies, expected.supplies);
+    let mint = svm.get_account(&accounts.outcome_mints[0]).unwrap();
+    let mint = spl_token::state::Mint::unpack(&mint.data).unwrap();
+    assert_eq!(mint.decimals, 6);
+    assert_eq!(mint.supply, shares / 1_000);
+    assert_eq!(
+        outcome_balance(&svm, &buyer.pubkey(), &accounts.market, 0),
+        shares / 1_000
+    );
+}
+
+#[test]
+fn test_buy_shares_rejects_spoofed_vault() {
+    let (mut svm, admin) = setup();
+    let params = market_params("spoofed_vault");
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+
+    // Another address in the vault's place fails the PDA check
+    let buyer = funded_keypair(&mut svm);
+    let mut buy = buy_shares_ix(&buyer.pubkey(), &accounts.market, 0, 1_000_000_000, 0);
+    let vault_meta = buy
+        .accounts
+        .iter_mut()
+        .find(|meta| meta.pubkey == accounts.market_vault)
+        .unwrap();
+    vault_meta.pubkey = buyer.pubkey();
+    assert!(send_ix(&mut svm, buy, &buyer).is_err());
+
+    // The vault's address, but an account the program doesn't own
+    let mut vault = svm.get_account(&accounts.market_vault).unwrap();
+    vault.owner = solana_sdk::system_program::ID;
+    svm.set_account(accounts.market_vault, vault).unwrap();
+    assert_error(
+        send_ix(
+            &mut svm,
+            buy_shares_ix(&buyer.pubkey(), &accounts.market, 0, 1_000_000_000, 0),
+            &buyer,
+        ),
+        ErrorCode::InvalidAccountOwner,
+    );
+    assert_error(
+        send_ix(
+            &mut svm,
+            admin_withdraw_excess_ix(&admin.pubkey(), &accounts.market),
+            &admin,
+        ),
+        ErrorCode::InvalidAccountOwner,
+    );
+}
+
+/// The quote a calling program would read back with `get_return_data` after CPI-ing `quote_cpi`
+#[test]
+fn test_quote_cpi_return_data() {
+    let (mut svm, admin) = setup();
+    let params = market_params("quote_cpi");
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+
+    let result = send_ix(
+        &mut svm,
+        quote_cpi_ix(&accounts.market, 0, 1_000_000_000),
+        &admin,
+    )
+    .unwrap();
+    assert_eq!(result.return_data.program_id, lmsr::id());
+    let quote = BuyQuote::try_from_slice(&result.return_data.data).unwrap();
+
+    // The quote leaves the market alone, and the buy it describes matches it
+    let market = load_market(&svm, &accounts.market);
+    assert_eq!(market.supplies[0], 0);
+    assert_eq!(
+        quote,
+        market.quote_buy(0, 1_000_000_000, START_TIMESTAMP).unwrap()
+    );
+
+    let buyer = funded_keypair(&mut svm);
+    send_ix(
+        &mut svm,
+        buy_shares_ix(&buyer.pubkey(), &accounts.market, 0, 1_000_000_000, 0),
+        &buyer,
+    )
+    .unwrap();
+    assert_eq!(
+        outcome_balance(&svm, &buyer.pubkey(), &accounts.market, 0),
+        quote.shares_out
+    );
+    assert_eq!(
+        load_market(&svm, &accounts.market).price(0).unwrap(),
+        quote.new_price
+    );
+}
+
+#[test]
+fn test_buy_shares_charges_market_fee() {
+    let (mut svm, admin) = setup();
+    let params = InitMarketParams {
+        fee_config: FeeConfig {
+            buy_bps: 50,
+            creation_fee: 10_000_000,
+            ..Default::default()
+        },
+        ..market_params("buy_fee")
+    };
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+
+    // The creation fee is in the vault and set aside
+    let market = load_market(&svm, &accounts.market);
+    assert_eq!(market.fee_config, params.fee_config);
+    assert_eq!(market.accrued_protocol_fees, 10_000_000);
+
+    let buyer = funded_keypair(&mut svm);
+    let vault_before = svm.get_balance(&accounts.market_vault).unwrap();
+    send_ix(
+        &mut svm,
+        buy_shares_ix(&buyer.pubkey(), &accounts.market, 0, 1_000_000_000, 0),
+        &buyer,
+    )
+    .unwrap();
+
+    // The whole amount reaches the vault, 0.5% of it as fee
+    let market = load_market(&svm, &accounts.market);
+    assert_eq!(
+        svm.get_balance(&accounts.market_vault).unwrap() - vault_before,
+        1_000_000_000
+    );
+    assert_eq!(market.accrued_protocol_fees, 10_000_000 + 5_000_000);
+    assert_eq!(market.reserves[0], 995_000_000);
+}
+
+#[test]
+fn test_buy_shares_rejects_price_impact_above_cap() {
+    let (mut svm, admin) = setup();
+    let params = market_params("impact_cap");
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+    let buyer = funded_keypair(&mut svm);
+
+    // A fat-fingered 5 SOL buy would take outcome 0 from 0.50 to over 0.99
+    assert_error(
+        send_ix(
+            &mut svm,
+            buy_shares_capped_ix(&buyer.pubkey(), &accounts.market, 0, 5_000_000_000, 0, 500),
+            &buyer,
+        ),
+        ErrorCode::PriceImpactTooHigh,
+    );
+    assert_eq!(load_market(&svm, &accounts.market).supplies[0], 0);
+
+    // 0.01 SOL moves it by about 1%
+    send_ix(
+        &mut svm,
+        buy_shares_capped_ix(&buyer.pubkey(), &accounts.market, 0, 10_000_000, 0, 500),
+        &buyer,
+    )
+    .unwrap();
+    assert!(load_market(&svm, &accounts.market).supplies[0] > 0);
+}
+
+/// A resubmitted order executes again at the moved price, and both fills carry the client's
+/// order id in their event so the client can see the duplicate
+#[test]
+fn test_buy_shares_event_carries_client_order_id() {
+    let (mut svm, admin) = setup();
+    let params = market_params("order_ids");
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+
+    let buyer = funded_keypair(&mut svm);
+    let order = buy_shares_order_ix(
+        &buyer.pubkey(),
+        &accounts.market,
+        0,
+        1_000_000_000,
+        0,
+        0,
+        Some(42),
+    );
+    let fills: Vec<SharesBought> = [
+        send_ix(&mut svm, order.clone(), &buyer),
+        send_ix(&mut svm, order, &buyer),
+    ]
+    .iter()
+    .map(|result| {
+        let mut emitted = events::<SharesBought>(result);
+        assert_eq!(emitted.len(), 1);
+        emitted.remove(0)
+    })
+    .collect();
+
+    for fill in &fills {
+        assert_eq!(fill.client_order_id, Some(42));
+        assert_eq!(fill.market, accounts.market);
+        assert_eq!(fill.buyer, buyer.pubkey());
+        assert_eq!((fill.outcome_index, fill.amount_in), (0, 1_000_000_000));
+    }
+    // The second fill bought at the price the first one moved
+    assert!(fills[1].shares_out < fills[0].shares_out);
+    assert_eq!(
+        outcome_balance(&svm, &buyer.pubkey(), &accounts.market, 0),
+        fills[0].shares_out + fills[1].shares_out
+    );
+
+    // Without an id the event says so
+    let anonymous = send_ix(
+        &mut svm,
+        buy_shares_ix(&buyer.pubkey(), &accounts.market, 1, 1_000_000_000, 0),
+        &buyer,
+    );
+    assert_eq!(events::<SharesBought>(&anonymous)[0].client_order_id, None);
+}
+
+/// A vault PDA of another market is rejected, even though it is a real vault owned by the program
+#[test]
+fn test_buy_shares_rejects_other_markets_vault() {
+    let (mut svm, admin) = setup();
+    let params = market_params("own_vault");
+    let other = market_params("other_vault");
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    let other_accounts = market_accounts(&other.label, other.num_outcomes);
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), other), &admin).unwrap();
+
+    let buyer = funded_keypair(&mut svm);
+    let mut ix = buy_shares_ix(&buyer.pubkey(), &accounts.market, 0, 1_000_000_000, 0);
+    for meta in ix.accounts.iter_mut() {
+        if meta.pubkey == accounts.market_vault {
+            meta.pubkey = other_accounts.market_vault;
+        }
+    }
+    let other_vault_before = svm.get_balance(&other_accounts.market_vault).unwrap();
+    assert_anchor_error(
+        send_ix(&mut svm, ix, &buyer),
+        anchor_lang::error::ErrorCode::ConstraintSeeds,
+    );
+    assert_eq!(
+        svm.get_balance(&other_accounts.market_vault).unwrap(),
+        other_vault_before
+    );
+    assert_eq!(load_market(&svm, &accounts.market).supplies[0], 0);
+}
+
+/// A buy past `max_buy` fills what it can and only takes the filled lamports from the buyer
+#[test]
+fn test_buy_capped_partially_fills_and_keeps_the_rest() {
+    let (mut svm, admin) = setup();
+    let params = InitMarketParams {
+        fee_config: FeeConfig {
+            buy_bps: 50,
+            ..Default::default()
+        },
+        ..market_params("buy_capped")
+    };
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+
+    let buyer = funded_keypair(&mut svm);
+    let before = load_market(&svm, &accounts.market);
+    let max_buy = before.max_buy(0, START_TIMESTAMP).unwrap();
+    let amount_in = max_buy + 5_000_000_000;
+    assert!(send_ix(
+        &mut svm,
+        buy_shares_ix(&buyer.pubkey(), &accounts.market, 0, amount_in, 0),
+        &buyer,
+    )
+    .is_err());
+
+    let buyer_before = svm.get_balance(&buyer.pubkey()).unwrap();
+    let vault_before = svm.get_balance(&accounts.market_vault).unwrap();
+    let result = send_ix(
+        &mut svm,
+        buy_capped_ix(&buyer.pubkey(), &accounts.market, 0, amount_in, 0),
+        &buyer,
+    )
+    .unwrap();
+    let fill = CappedFill::deserialize(&mut &result.return_data.data[..]).unwrap();
+
+    // Only the filled lamports moved, the rest never left the buyer (beyond the token account
+    // rent and the 5000 lamport signature fee)
+    assert!(fill.amount_filled <= max_buy && fill.amount_filled > max_buy / 2);
+    assert_eq!(
+        svm.get_balance(&accounts.market_vault).unwrap() - vault_before,
+        fill.amount_filled
+    );
+    let ata_rent = svm.minimum_balance_for_rent_exemption(spl_token::state::Account::LEN);
+    assert_eq!(
+        buyer_before - svm.get_balance(&buyer.pubkey()).unwrap(),
+        fill.amount_filled + ata_rent + 5_000
+    );
+
+    // The filled part is accounted like a plain buy of that amount
+    let mut expected = before;
+    assert_eq!(
+        expected
+            .buy_shares(0, fill.amount_filled, START_TIMESTAMP)
+            .unwrap(),
+        fill.shares_out
+    );
+    let after = load_market(&svm, &accounts.market);
+    assert_eq!(after.supplies, expected.supplies);
+    assert_eq!(after.reserves, expected.reserves);
+    assert_eq!(after.accrued_protocol_fees, expected.accrued_protocol_fees);
+    assert_eq!(
+        outcome_balance(&svm, &buyer.pubkey(), &accounts.market, 0),
+        fill.shares_out
+    );
+}
+
+/// An exact-output buy mints exactly the shares asked for and charges what the market computes
+#[test]
+fn test_buy_exact_shares() {
+    let (mut svm, admin) = setup();
+    let params = InitMarketParams {
+        fee_config: FeeConfig {
+            buy_bps: 50,
+            ..Default::default()
+        },
+        ..market_params("buy_exact")
+    };
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+
+    let buyer = funded_keypair(&mut svm);
+    let mut expected = load_market(&svm, &accounts.market);
+    let expected_in = expected
+        .buy_exact_shares(1, 2_000_000_000, u64::MAX, START_TIMESTAMP)
+        .unwrap();
+
+    // One lamport under the charge is rejected
+    assert_error(
+        send_ix(
+            &mut svm,
+            buy_exact_shares_ix(
+                &buyer.pubkey(),
+                &accounts.market,
+                1,
+                2_000_000_000,
+                expected_in - 1,
+            ),
+            &buyer,
+        ),
+        ErrorCode::SlippageExceeded,
+    );
+
+    let vault_before = svm.get_balance(&accounts.market_vault).unwrap();
+    let result = send_ix(
+        &mut svm,
+        buy_exact_shares_ix(
+            &buyer.pubkey(),
+            &accounts.market,
+            1,
+            2_000_000_000,
+            expected_in,
+        ),
+        &buyer,
+    )
+    .unwrap();
+    let amount_in = u64::from_le_bytes(result.return_data.data.try_into().unwrap());
+    assert_eq!(amount_in, expected_in);
+    assert_eq!(
+        svm.get_balance(&accounts.market_vault).unwrap() - vault_before,
+        amount_in
+    );
+
+    let market = load_market(&svm, &accounts.market);
+    assert_eq!(market.supplies, expected.supplies);
+    assert_eq!(market.supplies[1], 2_000_000_000);
+    assert_eq!(
+        outcome_balance(&svm, &buyer.pubkey(), &accounts.market, 1),
+        2_000_000_000
+    );
+}
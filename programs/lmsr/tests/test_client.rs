@@ -0,0 +1,347 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::Discriminator;
+use common::constants::{FieldLayout, MARKET_ACCOUNT_SIZE, MARKET_LAYOUT, MAX_OUTCOMES};
+use lmsr::state::Market;
+use lmsr::types::{FeeConfig, FixedSizeString};
+
+/// When the trades happen. No market here has a fee holiday, so any time charges the same fees.
+#[cfg(feature = "client")]
+const NOW: i64 = 0;
+
+/// Serialize a market the way it is stored on-chain: discriminator followed by the zero copy bytes.
+fn account_data(market: &Market) -> Vec<u8> {
+    let mut data = Market::DISCRIMINATOR.to_vec();
+    data.extend_from_slice(bytemuck::bytes_of(market));
+    data
+}
+
+#[test]
+fn test_market_field_offsets() {
+    let admin = Pubkey::new_unique();
+    let market = Market {
+        resolve_at: 0x0102_0304_0506_0708,
+        admin,
+        resolved: 1,
+        ..Default::default()
+    };
+    let data = account_data(&market);
+    assert_eq!(data.len(), Market::SIZE);
+
+    assert_eq!(
+        &data[Market::RESOLVE_AT_OFFSET..Market::RESOLVE_AT_OFFSET + 8],
+        &market.resolve_at.to_le_bytes()
+    );
+    assert_eq!(
+        &data[Market::ADMIN_OFFSET..Market::ADMIN_OFFSET + 32],
+        admin.as_ref()
+    );
+    assert_eq!(data[Market::RESOLVED_OFFSET], 1);
+}
+
+/// The entry of [`MARKET_LAYOUT`] named `name`
+fn layout(name: &str) -> FieldLayout {
+    *MARKET_LAYOUT
+        .iter()
+        .find(|field| field.name == name)
+        .unwrap_or_else(|| panic!("{name} missing from MARKET_LAYOUT"))
+}
+
+#[test]
+fn test_market_layout_matches_struct() {
+    // Contiguous, in order, and covering the whole account
+    let mut end = 0;
+    for field in MARKET_LAYOUT {
+        assert_eq!(
+            field.offset, end,
+            "{} does not follow the previous field",
+            field.name
+        );
+        end = field.range().end;
+    }
+    assert_eq!(end, MARKET_ACCOUNT_SIZE);
+    assert_eq!(Market::SIZE, MARKET_ACCOUNT_SIZE);
+
+    let market = Market::default();
+    macro_rules! assert_field {
+        ($($field:ident),+ $(,)?) => {
+            $(
+                assert_eq!(
+                    layout(stringify!($field)),
+                    FieldLayout {
+                        name: stringify!($field),
+                        offset: 8 + std::mem::offset_of!(Market, $field),
+                        size: std::mem::size_of_val(&market.$field),
+                    }
+                );
+            )+
+            // Every field but the discriminator is listed above
+            assert_eq!(MARKET_LAYOUT.len(), 1 + [$(stringify!($field)),+].len());
+        };
+    }
+    assert_eq!(layout("discriminator").range(), 0..8);
+    assert_field!(
+        reserves,
+        supplies,
+        price_snapshot,
+        price_cumulative,
+        scale,
+        initialized_at,
+        resolve_at,
+        trading_open_at,
+        snapshot_at,
+        price_cumulative_at,
+        initial_liquidity,
+        max_outcome_reserve,
+        fee_config,
+        accrued_protocol_fees,
+        last_fee_accrual,
+        admin,
+        oracle,
+        label,
+        num_outcomes,
+        bump,
+        vault_bump,
+        resolved,
+        winning_outcome,
+        refund_mode,
+        mints_initialized,
+        high_precision,
+        in_progress,
+        tokenized,
+        consensus_outcome,
+        outcome_decimals,
+        _padding,
+        consensus_since,
+        accrued_rounding_surplus,
+        fee_free_until,
+        subsidy,
+        trading_halt_before_resolve,
+    );
+}
+
+#[test]
+fn test_market_round_trips_through_layout() {
+    let u64_array = |seed: u64| std::array::from_fn::<u64, MAX_OUTCOMES, _>(|i| seed + i as u64);
+    let market = Market {
+        reserves: u64_array(1_000),
+        supplies: u64_array(2_000),
+        price_snapshot: u64_array(3_000),
+        price_cumulative: u64_array(4_000),
+        scale: 1_000_000_000,
+        initialized_at: 1_700_000_000,
+        resolve_at: 1_800_000_000,
+        trading_open_at: 1_700_000_100,
+        snapshot_at: 1_700_000_200,
+        price_cumulative_at: 1_700_000_300,
+        initial_liquidity: 5_000_000_000,
+        max_outcome_reserve: 6_000_000_000,
+        fee_config: FeeConfig {
+            buy_bps: 50,
+            sell_bps: 60,
+            _padding: [0; 4],
+            creation_fee: 7,
+            tvl_fee: 70,
+        },
+        accrued_protocol_fees: 8,
+        last_fee_accrual: -9,
+        admin: Pubkey::new_unique(),
+        oracle: Pubkey::new_unique(),
+        label: FixedSizeString::new("layout"),
+        num_outcomes: 3,
+        bump: 254,
+        vault_bump: 253,
+        resolved: 1,
+        winning_outcome: 2,
+        refund_mode: 1,
+        mints_initialized: 0b111,
+        high_precision: 1,
+        in_progress: 0,
+        tokenized: 1,
+        consensus_outcome: 2,
+        outcome_decimals: 6,
+        _padding: [0; 3],
+        consensus_since: 1_700_000_400,
+        accrued_rounding_surplus: 10,
+        fee_free_until: 1_700_000_500,
+        subsidy: 11,
+        trading_halt_before_resolve: 3_600,
+    };
+    let data = account_data(&market);
+    let field = |name: &str| &data[layout(name).range()];
+    let u64_at = |name: &str| u64::from_le_bytes(field(name).try_into().unwrap());
+    let i64_at = |name: &str| i64::from_le_bytes(field(name).try_into().unwrap());
+    let u64_array_at = |name: &str| -> Vec<u64> {
+        field(name)
+            .chunks_exact(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+            .collect()
+    };
+
+    // Decode every field from its documented offset, as an off-chain client would
+    assert_eq!(field("discriminator"), Market::DISCRIMINATOR);
+    assert_eq!(u64_array_at("reserves"), market.reserves);
+    assert_eq!(u64_array_at("supplies"), market.supplies);
+    assert_eq!(u64_array_at("price_snapshot"), market.price_snapshot);
+    assert_eq!(u64_array_at("price_cumulative"), market.price_cumulative);
+    assert_eq!(u64_at("scale"), market.scale);
+    assert_eq!(u64_at("initialized_at"), market.initialized_at);
+    assert_eq!(i64_at("resolve_at"), market.resolve_at);
+    assert_eq!(i64_at("trading_open_at"), market.trading_open_at);
+    assert_eq!(i64_at("snapshot_at"), market.snapshot_at);
+    assert_eq!(i64_at("price_cumulative_at"), market.price_cumulative_at);
+    assert_eq!(u64_at("initial_liquidity"), market.initial_liquidity);
+    assert_eq!(u64_at("max_outcome_reserve"), market.max_outcome_reserve);
+    let fee_config = field("fee_config");
+    assert_eq!(fee_config[0..2], market.fee_config.buy_bps.to_le_bytes());
+    assert_eq!(fee_config[2..4], market.fee_config.sell_bps.to_le_bytes());
+    assert_eq!(
+        fee_config[8..16],
+        market.fee_config.creation_fee.to_le_bytes()
+    );
+    assert_eq!(fee_config[16..24], market.fee_config.tvl_fee.to_le_bytes());
+    assert_eq!(
+        u64_at("accrued_protocol_fees"),
+        market.accrued_protocol_fees
+    );
+    assert_eq!(i64_at("last_fee_accrual"), market.last_fee_accrual);
+    assert_eq!(field("admin"), market.admin.as_ref());
+    assert_eq!(field("oracle"), market.oracle.as_ref());
+    assert_eq!(field("label"), market.label.value);
+    assert_eq!(field("num_outcomes"), [market.num_outcomes]);
+    assert_eq!(field("bump"), [market.bump]);
+    assert_eq!(field("vault_bump"), [market.vault_bump]);
+    assert_eq!(field("resolved"), [market.resolved]);
+    assert_eq!(field("winning_outcome"), [market.winning_outcome]);
+    assert_eq!(field("refund_mode"), [market.refund_mode]);
+    assert_eq!(
+        field("mints_initialized"),
+        market.mints_initialized.to_le_bytes()
+    );
+    assert_eq!(field("high_precision"), [market.high_precision]);
+    assert_eq!(field("in_progress"), [market.in_progress]);
+    assert_eq!(field("tokenized"), [market.tokenized]);
+    assert_eq!(field("consensus_outcome"), [market.consensus_outcome]);
+    assert_eq!(field("outcome_decimals"), [market.outcome_decimals]);
+    assert_eq!(i64_at("consensus_since"), market.consensus_since);
+    assert_eq!(
+        u64_at("accrued_rounding_surplus"),
+        market.accrued_rounding_surplus
+    );
+    assert_eq!(i64_at("fee_free_until"), market.fee_free_until);
+    assert_eq!(u64_at("subsidy"), market.subsidy);
+    assert_eq!(
+        i64_at("trading_halt_before_resolve"),
+        market.trading_halt_before_resolve
+    );
+
+    // Copying only the documented fields into a blank account loses nothing
+    let mut encoded = vec![0u8; MARKET_ACCOUNT_SIZE];
+    for entry in MARKET_LAYOUT {
+        encoded[entry.range()].copy_from_slice(field(entry.name));
+    }
+    assert_eq!(encoded, data);
+    let decoded: Market = bytemuck::pod_read_unaligned(&encoded[8..]);
+    assert_eq!(bytemuck::bytes_of(&decoded), bytemuck::bytes_of(&market));
+}
+
+#[cfg(feature = "client")]
+#[test]
+fn test_market_filters() {
+    use lmsr::client::MarketFilters;
+
+    let admin = Pubkey::new_unique();
+    let open = Market {
+        admin,
+        ..Default::default()
+    };
+    let resolved = Market {
+        admin,
+        resolved: 1,
+        ..Default::default()
+    };
+    let other_admin = Market {
+        admin: Pubkey::new_unique(),
+        ..Default::default()
+    };
+
+    let matches = |filters: &[lmsr::client::MarketFilter], market: &Market| {
+        let data = account_data(market);
+        filters.iter().all(|filter| filter.matches(&data))
+    };
+
+    let all = MarketFilters::new().build();
+    assert!(matches(&all, &open) && matches(&all, &resolved) && matches(&all, &other_admin));
+    assert!(!all.iter().all(|filter| filter.matches(&[0u8; 64])));
+
+    let by_admin = MarketFilters::new().admin(&admin).build();
+    assert!(matches(&by_admin, &open) && matches(&by_admin, &resolved));
+    assert!(!matches(&by_admin, &other_admin));
+
+    let unresolved = MarketFilters::new().unresolved().build();
+    assert!(matches(&unresolved, &open) && matches(&unresolved, &other_admin));
+    assert!(!matches(&unresolved, &resolved));
+}
+
+#[cfg(feature = "client")]
+#[test]
+fn test_market_clone_and_debug() {
+    let market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+
+    // Clone is what off-chain tooling calls, so exercise it rather than the implicit copy
+    #[allow(clippy::clone_on_copy)]
+    let mut copy = market.clone();
+    copy.buy_shares(0, 1_000_000_000, NOW).unwrap();
+    assert_eq!(market.supplies[0], 0);
+    assert!(copy.supplies[0] > 0);
+
+    let printed = format!("{:?}", copy);
+    assert!(printed.starts_with("Market {"), "{}", printed);
+    assert!(printed.contains("num_outcomes: 2"), "{}", printed);
+
+    // The derive leaves the on-chain layout alone
+    assert_eq!(account_data(&copy).len(), Market::SIZE);
+}
+
+#[cfg(feature = "client")]
+#[test]
+fn test_market_json_round_trip() {
+    use lmsr::client::MarketJson;
+
+    let admin = Pubkey::new_unique();
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 3,
+        admin,
+        label: FixedSizeString::new("json"),
+        resolve_at: 1_800_000_000,
+        fee_config: FeeConfig {
+            buy_bps: 50,
+            ..Default::default()
+        },
+        tokenized: 1,
+        ..Default::default()
+    };
+    market.buy_shares(1, 2_000_000_000, NOW).unwrap();
+    market.buy_shares(2, 500_000_000, NOW).unwrap();
+
+    let dto = MarketJson::from_market(&market).unwrap();
+    assert_eq!(dto.label, "json");
+    assert_eq!(dto.admin, admin.to_string());
+    assert_eq!(dto.supplies, market.supplies[..3]);
+    assert_eq!(dto.accrued_protocol_fees, market.accrued_protocol_fees);
+    assert!(dto.tokenized && !dto.resolved);
+    for (i, price) in dto.prices.iter().enumerate() {
+        assert_eq!(*price, market.price(i as u8).unwrap());
+    }
+    assert_eq!(dto.cost, market.cost().unwrap());
+
+    // Through JSON and back loses nothing
+    let json = dto.to_json().unwrap();
+    assert!(json.contains("\"label\":\"json\""), "{}", json);
+    assert!(json.contains("\"prices\":["), "{}", json);
+    assert_eq!(MarketJson::from_json(&json).unwrap(), dto);
+}
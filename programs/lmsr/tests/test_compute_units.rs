@@ -0,0 +1,48 @@
+mod utils;
+
+use solana_sdk::signer::Signer;
+use utils::*;
+
+/// Log the CU consumed by `fp_exp`/`fp_ln` at both ends of their domain.
+///
+/// The best case is a fresh market (all supplies zero), where `fp_exp(0)` converges on the first
+/// term and `fp_ln(2)` is a table lookup. The worst case pushes q/b close to the 20 bound, where
+/// `fp_exp` multiplies in e for every whole unit and `fp_ln` range-reduces the largest possible sum.
+#[test]
+fn test_fp_compute_units() {
+    let (mut svm, admin) = setup();
+    let params = market_params("cu_market");
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+
+    let best_price = send_ix(&mut svm, get_price_ix(&accounts.market, 0), &admin)
+        .unwrap()
+        .compute_units_consumed;
+    let best_cost = send_ix(&mut svm, get_cost_ix(&accounts.market), &admin)
+        .unwrap()
+        .compute_units_consumed;
+
+    let mut market = load_market(&svm, &accounts.market);
+    market.supplies[0] = 19_900_000_000;
+    market.supplies[1] = 19_800_000_000;
+    store_market(&mut svm, &accounts.market, &market);
+
+    let worst_price = send_ix(&mut svm, get_price_ix(&accounts.market, 0), &admin)
+        .unwrap()
+        .compute_units_consumed;
+    let worst_cost = send_ix(&mut svm, get_cost_ix(&accounts.market), &admin)
+        .unwrap()
+        .compute_units_consumed;
+
+    println!("get_price CU: best {}, worst {}", best_price, worst_price);
+    println!("get_cost CU: best {}, worst {}", best_cost, worst_cost);
+    println!(
+        "variance: price {} CU, cost {} CU",
+        worst_price - best_price,
+        worst_cost - best_cost
+    );
+
+    assert!(worst_price > best_price);
+    assert!(worst_cost > best_cost);
+}
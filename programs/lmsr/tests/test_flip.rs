@@ -0,0 +1,72 @@
+mod utils;
+
+use anchor_spl::associated_token::get_associated_token_address;
+use common::errors::ErrorCode;
+use lmsr::types::InitMarketParams;
+use solana_sdk::signer::Signer;
+use utils::*;
+
+#[test]
+fn test_flip_short_of_min_target_shares_reverts_both_legs() {
+    let (mut svm, admin) = setup();
+    let params = InitMarketParams {
+        num_outcomes: 3,
+        ..market_params("flip")
+    };
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+
+    let trader = funded_keypair(&mut svm);
+    send_ix(
+        &mut svm,
+        buy_shares_ix(&trader.pubkey(), &accounts.market, 0, 1_000_000_000, 0),
+        &trader,
+    )
+    .unwrap();
+    let held = outcome_balance(&svm, &trader.pubkey(), &accounts.market, 0);
+
+    let before = load_market(&svm, &accounts.market);
+    let mut flipped = before;
+    let expected = flipped.flip(0, 2, held, 0, START_TIMESTAMP).unwrap();
+
+    // One share unit more than the flip yields rejects the sell along with the buy
+    assert_error(
+        send_ix(
+            &mut svm,
+            flip_ix(&trader.pubkey(), &accounts.market, 0, 2, held, expected + 1),
+            &trader,
+        ),
+        ErrorCode::SlippageExceeded,
+    );
+    let market = load_market(&svm, &accounts.market);
+    assert_eq!(market.supplies, before.supplies);
+    assert_eq!(market.reserves, before.reserves);
+    assert_eq!(
+        outcome_balance(&svm, &trader.pubkey(), &accounts.market, 0),
+        held
+    );
+    assert!(svm
+        .get_account(&get_associated_token_address(
+            &trader.pubkey(),
+            &outcome_mint(&accounts.market, 2)
+        ))
+        .is_none());
+
+    send_ix(
+        &mut svm,
+        flip_ix(&trader.pubkey(), &accounts.market, 0, 2, held, expected),
+        &trader,
+    )
+    .unwrap();
+    assert_eq!(
+        outcome_balance(&svm, &trader.pubkey(), &accounts.market, 0),
+        0
+    );
+    assert_eq!(
+        outcome_balance(&svm, &trader.pubkey(), &accounts.market, 2),
+        expected
+    );
+    let market = load_market(&svm, &accounts.market);
+    assert_eq!(market.supplies[0], 0);
+    assert_eq!(market.supplies[2], expected);
+}
@@ -0,0 +1,349 @@
+mod utils;
+
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use common::constants::REGISTRY_PAGE_CAPACITY;
+use common::errors::ErrorCode;
+use common::utils::{outcome_mints, user_outcome_atas};
+use lmsr::state::Registry;
+use lmsr::types::{FixedSizeString, InitMarketParams};
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::signer::{keypair::Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use spl_token::solana_program;
+use utils::*;
+
+#[test]
+fn test_init_market_with_initial_liquidity() {
+    let (mut svm, admin) = setup();
+    let params = InitMarketParams {
+        require_initial_liquidity: 5_000_000_000,
+        ..market_params("seeded_market")
+    };
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+
+    let vault_rent = svm.minimum_balance_for_rent_exemption(0);
+
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+
+    let vault = svm.get_account(&accounts.market_vault).unwrap();
+    assert_eq!(vault.lamports, vault_rent + 5_000_000_000);
+
+    let market = load_market(&svm, &accounts.market);
+    assert_eq!(market.initial_liquidity, 5_000_000_000);
+    assert_eq!(market.supplies, [0; 16]);
+}
+
+#[test]
+fn test_fresh_market_starts_zeroed() {
+    let (mut svm, admin) = setup();
+    for (label, tokenized) in [("fresh_tokenized", true), ("fresh_positions", false)] {
+        let params = InitMarketParams {
+            num_outcomes: 5,
+            tokenized,
+            ..market_params(label)
+        };
+        let accounts = market_accounts(&params.label, params.num_outcomes);
+        let ix = if tokenized {
+            init_market_ix(&admin.pubkey(), params)
+        } else {
+            init_market_without_mints_ix(&admin.pubkey(), params)
+        };
+        send_ix(&mut svm, ix, &admin).unwrap();
+
+        let market = load_market(&svm, &accounts.market);
+        assert_eq!(market.num_outcomes, 5);
+        assert_eq!(market.supplies, [0; 16]);
+        assert_eq!(market.reserves, [0; 16]);
+    }
+}
+
+#[test]
+fn test_outcome_mints_match_init_market() {
+    let (mut svm, admin) = setup();
+    let params = InitMarketParams {
+        num_outcomes: 4,
+        ..market_params("four_outcomes")
+    };
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+
+    let mints = outcome_mints(&lmsr::id(), &accounts.market, 4);
+    assert_eq!(mints.len(), 4);
+    for mint in &mints {
+        let mint_account = svm.get_account(mint).unwrap();
+        assert_eq!(mint_account.owner, spl_token::ID);
+        let mint = spl_token::state::Mint::unpack(&mint_account.data).unwrap();
+        assert_eq!(mint.mint_authority.unwrap(), accounts.market);
+        assert_eq!(mint.decimals, 9);
+    }
+}
+
+#[test]
+fn test_user_outcome_atas() {
+    let user = Pubkey::new_unique();
+    let accounts = market_accounts(&FixedSizeString::new("portfolio"), 3);
+
+    let atas = user_outcome_atas(&user, &lmsr::id(), &accounts.market, 3);
+    assert_eq!(atas.len(), 3);
+    for (ata, mint) in atas.iter().zip(&accounts.outcome_mints) {
+        // The associated token program's own derivation
+        let expected = Pubkey::find_program_address(
+            &[user.as_ref(), spl_token::ID.as_ref(), mint.as_ref()],
+            &anchor_spl::associated_token::ID,
+        )
+        .0;
+        assert_eq!(*ata, expected);
+    }
+    assert_eq!(
+        atas[1],
+        get_associated_token_address(&user, &accounts.outcome_mints[1])
+    );
+}
+
+#[test]
+fn test_init_market_insufficient_initial_liquidity() {
+    let (mut svm, _) = setup();
+    let admin = Keypair::new();
+    svm.airdrop(&admin.pubkey(), 1_000_000_000).unwrap();
+
+    let params = InitMarketParams {
+        require_initial_liquidity: 10_000_000_000,
+        ..market_params("underfunded_market")
+    };
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+
+    assert_error(
+        send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin),
+        ErrorCode::InsufficientFunds,
+    );
+
+    assert!(svm.get_account(&accounts.market).is_none());
+}
+
+#[test]
+fn test_init_market_rejects_fewer_than_two_outcomes() {
+    let (mut svm, admin) = setup();
+
+    for (num_outcomes, label) in [(0, "zero_outcomes"), (1, "one_outcome")] {
+        let params = InitMarketParams {
+            num_outcomes,
+            ..market_params(label)
+        };
+        assert_error(
+            send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin),
+            ErrorCode::NotEnoughOutcomes,
+        );
+    }
+}
+
+#[test]
+fn test_init_market_rejects_past_resolve_at() {
+    let (mut svm, admin) = setup();
+    let params = InitMarketParams {
+        // Ten seconds after the epoch, what a duration passed as a timestamp looks like
+        resolve_at: 10,
+        ..market_params("resolves_in_the_past")
+    };
+
+    assert_error(
+        send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin),
+        ErrorCode::ResolveTimeInPast,
+    );
+}
+
+#[test]
+fn test_init_market_rejects_unusable_scale() {
+    let (mut svm, admin) = setup();
+    let params = InitMarketParams {
+        // b of 0.0001 SOL, a 0.01 SOL buy is 100 b
+        scale: 100_000,
+        ..market_params("unusable_scale")
+    };
+
+    assert_error(
+        send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin),
+        ErrorCode::ScaleTooSmall,
+    );
+}
+
+#[test]
+fn test_init_market_rejects_duplicate_label() {
+    let (mut svm, admin) = setup();
+    send_ix(
+        &mut svm,
+        init_market_ix(&admin.pubkey(), market_params("duplicate")),
+        &admin,
+    )
+    .unwrap();
+
+    // Any parameters, from any admin, collide on the label
+    let other_admin = funded_keypair(&mut svm);
+    let params = InitMarketParams {
+        num_outcomes: 3,
+        ..market_params("duplicate")
+    };
+    assert_error(
+        send_ix(
+            &mut svm,
+            init_market_ix(&other_admin.pubkey(), params),
+            &other_admin,
+        ),
+        ErrorCode::MarketLabelAlreadyExists,
+    );
+    assert_error(
+        send_ix(
+            &mut svm,
+            batch_init_markets_ix(&other_admin.pubkey(), vec![params]),
+            &other_admin,
+        ),
+        ErrorCode::MarketLabelAlreadyExists,
+    );
+}
+
+#[test]
+fn test_init_sixteen_outcome_market_in_one_transaction() {
+    let (mut svm, admin) = setup();
+    let params = InitMarketParams {
+        num_outcomes: 16,
+        ..market_params("sixteen_at_once")
+    };
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    let ix = init_market_ix(&admin.pubkey(), params);
+
+    let tx = Transaction::new_signed_with_payer(
+        std::slice::from_ref(&ix),
+        Some(&admin.pubkey()),
+        &[&admin],
+        svm.latest_blockhash(),
+    );
+    assert_eq!(tx.message.account_keys.len(), 23);
+    // One signature and its compact length prefix, then the message
+    let tx_size = 1 + 64 + tx.message.serialize().len();
+    assert!(tx_size <= PACKET_DATA_SIZE, "{}", tx_size);
+
+    let meta = send_ix(&mut svm, ix, &admin).unwrap();
+    assert!(meta.compute_units_consumed < 200_000);
+    assert_eq!(
+        load_market(&svm, &accounts.market).mints_initialized,
+        u16::MAX
+    );
+}
+
+#[test]
+fn test_init_sixteen_outcome_market_with_lazy_mints() {
+    let (mut svm, admin) = setup();
+    let params = InitMarketParams {
+        num_outcomes: 16,
+        ..market_params("sixteen_outcomes")
+    };
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(
+        &mut svm,
+        init_market_without_mints_ix(&admin.pubkey(), params),
+        &admin,
+    )
+    .unwrap();
+    assert_eq!(load_market(&svm, &accounts.market).mints_initialized, 0);
+
+    let buyer = funded_keypair(&mut svm);
+    let buy = buy_shares_ix(&buyer.pubkey(), &accounts.market, 0, 1_000_000_000, 0);
+
+    // One mint per transaction, trading stays blocked until the last one
+    for index in 0..15 {
+        send_ix(
+            &mut svm,
+            init_outcome_mint_ix(&admin.pubkey(), &accounts.market, index),
+            &admin,
+        )
+        .unwrap();
+        assert_error(
+            send_ix(&mut svm, buy.clone(), &buyer),
+            ErrorCode::MintsNotReady,
+        );
+    }
+    assert_error(
+        send_ix(
+            &mut svm,
+            init_outcome_mint_ix(&admin.pubkey(), &accounts.market, 3),
+            &admin,
+        ),
+        ErrorCode::MintAlreadyInitialized,
+    );
+
+    send_ix(
+        &mut svm,
+        init_outcome_mint_ix(&admin.pubkey(), &accounts.market, 15),
+        &admin,
+    )
+    .unwrap();
+    assert_eq!(
+        load_market(&svm, &accounts.market).mints_initialized,
+        u16::MAX
+    );
+
+    send_ix(&mut svm, buy, &buyer).unwrap();
+    assert!(outcome_balance(&svm, &buyer.pubkey(), &accounts.market, 0) > 0);
+}
+
+#[test]
+fn test_registry_lists_created_markets() {
+    let (mut svm, admin) = setup();
+    send_ix(&mut svm, init_registry_ix(&admin.pubkey()), &admin).unwrap();
+    assert_eq!(load_registry(&svm).market_count, 0);
+
+    let labels = ["listed_a", "listed_b", "listed_c"];
+    for label in labels {
+        send_ix(
+            &mut svm,
+            init_registered_market_ix(&admin.pubkey(), market_params(label), 0),
+            &admin,
+        )
+        .unwrap();
+    }
+    // Markets created without the registry stay unlisted
+    send_ix(
+        &mut svm,
+        init_market_ix(&admin.pubkey(), market_params("unlisted")),
+        &admin,
+    )
+    .unwrap();
+
+    let registry = load_registry(&svm);
+    assert_eq!(registry.market_count, 3);
+    assert_eq!(registry.page_count(), 1);
+    let page = load_registry_page(&svm, 0);
+    assert_eq!(page.page, 0);
+    assert_eq!(
+        page.markets,
+        labels
+            .iter()
+            .map(|label| market_accounts(&market_params(label).label, 2).market)
+            .collect::<Vec<_>>()
+    );
+
+    // The next entry still belongs on page 0
+    assert_error(
+        send_ix(
+            &mut svm,
+            init_registered_market_ix(&admin.pubkey(), market_params("wrong_page"), 1),
+            &admin,
+        ),
+        ErrorCode::InvalidRegistryPage,
+    );
+}
+
+#[test]
+fn test_registry_pages() {
+    let mut registry = Registry::default();
+    assert_eq!((registry.next_page(), registry.page_count()), (0, 0));
+
+    registry.market_count = REGISTRY_PAGE_CAPACITY as u64 - 1;
+    assert_eq!((registry.next_page(), registry.page_count()), (0, 1));
+    registry.market_count += 1;
+    assert_eq!((registry.next_page(), registry.page_count()), (1, 1));
+    registry.market_count += 1;
+    assert_eq!((registry.next_page(), registry.page_count()), (1, 2));
+}
@@ -0,0 +1,2550 @@
+use anchor_lang::prelude::{AnchorSerialize, Pubkey};
+use common::constants::{CONSENSUS_DWELL_SECONDS, LIQUIDITY_DEPTH_IMPACT_BPS, VOID_OUTCOME};
+use common::errors::ErrorCode;
+use lmsr::state::{price_sum_tolerance, Market, MAX_EXP_ARG};
+use lmsr::types::{BasketLeg, FeeConfig, FixedSizeString, MarketResolution, OracleReport};
+
+/// When the trades happen, unless a test needs a particular time. None of these markets has a fee
+/// holiday that runs past it.
+const NOW: i64 = 0;
+
+#[test]
+fn test_max_outcome_reserve_cap() {
+    let mut market = Market {
+        scale: 1_000_000_000, // 1 SOL liquidity parameter
+        num_outcomes: 2,
+        max_outcome_reserve: 3_000_000_000,
+        ..Default::default()
+    };
+
+    // The cap is tighter than the fp_exp domain (20 * b), so it's the reported limit
+    assert_eq!(market.max_buy(0, NOW).unwrap(), 3_000_000_000);
+
+    market.buy_shares(0, 1_000_000_000, NOW).unwrap();
+    assert_eq!(market.max_buy(0, NOW).unwrap(), 2_000_000_000);
+    // The cap is per outcome
+    assert_eq!(market.max_buy(1, NOW).unwrap(), 3_000_000_000);
+
+    // Buying exactly up to the cap succeeds
+    market
+        .buy_shares(0, market.max_buy(0, NOW).unwrap(), NOW)
+        .unwrap();
+    assert_eq!(market.reserves[0], market.max_outcome_reserve);
+    assert_eq!(market.max_buy(0, NOW).unwrap(), 0);
+
+    // cap + 1 is rejected without mutating state
+    let supplies = market.supplies;
+    assert_eq!(
+        market.buy_shares(0, 1_000, NOW).unwrap_err(),
+        ErrorCode::OutcomeReserveCapExceeded.into()
+    );
+    assert_eq!(market.supplies, supplies);
+    assert_eq!(market.reserves[0], market.max_outcome_reserve);
+
+    // Other outcomes are still tradable
+    market.buy_shares(1, 2_000_000_000, NOW).unwrap();
+}
+
+/// A buy past `max_buy` fills up to about `max_buy` instead of failing
+#[test]
+fn test_buy_capped_fills_up_to_max_buy() {
+    for high_precision in [0, 1] {
+        for scale in [10_000_000, 1_000_000_000, 1_000_000_000_000] {
+            for (head_start, buy_bps) in [(0, 0), (5, 0), (12, 100)] {
+                let mut market = Market {
+                    scale,
+                    num_outcomes: 3,
+                    high_precision,
+                    fee_config: FeeConfig {
+                        buy_bps,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                };
+                market.supplies[1] = head_start * scale;
+                let max_buy = market.max_buy(1, NOW).unwrap();
+                let too_much = max_buy.saturating_mul(2);
+                let mut rejected = market;
+                assert!(rejected.buy_shares(1, too_much, NOW).is_err());
+
+                let mut filled_market = market;
+                let (shares_out, filled) = filled_market.buy_capped(1, too_much, NOW).unwrap();
+                assert!(filled <= max_buy);
+                assert!(
+                    max_buy - filled <= max_buy / 1_000_000 + 1,
+                    "scale {scale} start {head_start}: filled {filled} of {max_buy}"
+                );
+
+                // The filled part is accounted exactly like a plain buy of that amount
+                let mut plain = market;
+                assert_eq!(plain.buy_shares(1, filled, NOW).unwrap(), shares_out);
+                assert_eq!(plain.supplies, filled_market.supplies);
+                assert_eq!(plain.reserves, filled_market.reserves);
+                assert_eq!(
+                    plain.accrued_protocol_fees,
+                    filled_market.accrued_protocol_fees
+                );
+            }
+        }
+    }
+
+    // A buy that fits is filled in full
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+    let mut plain = market;
+    assert_eq!(
+        market.buy_capped(0, 1_000_000_000, NOW).unwrap(),
+        (
+            plain.buy_shares(0, 1_000_000_000, NOW).unwrap(),
+            1_000_000_000
+        )
+    );
+
+    // The reserve cap limits the fill too
+    let mut capped = Market {
+        max_outcome_reserve: 300_000_000,
+        ..market
+    };
+    let (_, filled) = capped.buy_capped(1, 1_000_000_000, NOW).unwrap();
+    assert_eq!(filled, 300_000_000);
+    assert_eq!(capped.max_buy(1, NOW).unwrap(), 0);
+}
+
+#[test]
+fn test_max_buy_without_cap() {
+    let market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+
+    // Bounded by the supply limit, which binds before the fp_exp domain (20 * b)
+    let max_buy = market.max_buy(0, NOW).unwrap();
+    assert!(max_buy < 20_000_000_000);
+    assert!(max_buy > 17_000_000_000);
+    assert_eq!(
+        market.max_buy(2, NOW).unwrap_err(),
+        ErrorCode::InvalidOutcomeIndex.into()
+    );
+}
+
+#[test]
+fn test_max_safe_buy() {
+    for high_precision in [0u8, 1] {
+        let mut market = Market::new_for_test(3, 1_000_000_000, 0).unwrap();
+        market.high_precision = high_precision;
+        market.buy_shares(1, 4_000_000_000, NOW).unwrap();
+
+        // Exactly the limit buys, one lamport more is rejected instead of saturating
+        for outcome in 0..3u8 {
+            let max_safe_buy = market.max_safe_buy(outcome, NOW).unwrap();
+            assert!(max_safe_buy > 0);
+            market
+                .clone()
+                .buy_shares(outcome, max_safe_buy, NOW)
+                .unwrap();
+            assert_eq!(
+                market
+                    .clone()
+                    .buy_shares(outcome, max_safe_buy + 1, NOW)
+                    .unwrap_err(),
+                ErrorCode::SupplyWouldSaturate.into()
+            );
+        }
+    }
+
+    // The reserve cap is policy, not math: max_buy honors it, max_safe_buy doesn't
+    let mut capped = Market::new_for_test(2, 1_000_000_000, 0).unwrap();
+    let uncapped_limit = capped.max_safe_buy(0, NOW).unwrap();
+    capped.max_outcome_reserve = 3_000_000_000;
+    assert_eq!(capped.max_buy(0, NOW).unwrap(), 3_000_000_000);
+    assert_eq!(capped.max_safe_buy(0, NOW).unwrap(), uncapped_limit);
+    assert_eq!(
+        capped.max_safe_buy(2, NOW).unwrap_err(),
+        ErrorCode::InvalidOutcomeIndex.into()
+    );
+}
+
+#[test]
+fn test_open_interest() {
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 3,
+        ..Default::default()
+    };
+
+    // Fresh market: no shares outstanding
+    assert_eq!(market.open_interest().unwrap(), [0; 16]);
+
+    market.buy_shares(0, 1_000_000_000, NOW).unwrap();
+    market.buy_shares(1, 500_000_000, NOW).unwrap();
+    market.buy_shares(0, 250_000_000, NOW).unwrap();
+
+    let open_interest = market.open_interest().unwrap();
+    for (i, value) in open_interest.iter().enumerate().take(3) {
+        let hand_computed = (market.supplies[i] as u128 * market.price(i as u8).unwrap() as u128
+            / 1_000_000_000) as u64;
+        assert_eq!(*value, hand_computed);
+    }
+    assert_eq!(open_interest[2], 0);
+    assert_eq!(open_interest[3..], [0; 13]);
+
+    // cost = Σ q_i p_i + b * H(p), and 0 <= H(p) <= ln(N)
+    let total: u64 = open_interest.iter().sum();
+    let cost = market.cost().unwrap();
+    let max_entropy_term = (market.scale as f64 * 3f64.ln()) as u64;
+    assert!(total <= cost, "{} > {}", total, cost);
+    assert!(cost <= total + max_entropy_term);
+
+    let snapshot = market.snapshot(cost, 0).unwrap();
+    assert_eq!(snapshot.open_interest, open_interest);
+    assert_eq!(snapshot.cost, cost);
+    assert_eq!(snapshot.prices[1], market.price(1).unwrap());
+    assert_eq!(
+        snapshot.liquidity_depth,
+        market.liquidity_depth(NOW).unwrap()
+    );
+    assert_eq!(
+        snapshot.price_sensitivity[1],
+        market.price_sensitivity(1).unwrap()
+    );
+    assert_eq!(snapshot.price_sensitivity[3..], [0; 13]);
+}
+
+#[test]
+fn test_resolve_to_zero_supply_outcome_refunds() {
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        resolve_at: 100,
+        ..Default::default()
+    };
+
+    // Two holders buy outcome 0, nobody buys outcome 1
+    let shares_a = market.buy_shares(0, 1_000_000_000, NOW).unwrap();
+    let shares_b = market.buy_shares(0, 500_000_000, NOW).unwrap();
+    let deposited = market.reserves[0];
+    let vault = deposited;
+
+    assert_eq!(
+        market.redeem(0, shares_a, vault).unwrap_err(),
+        ErrorCode::MarketNotResolved.into()
+    );
+    assert_eq!(
+        market.resolve(1, 99).unwrap_err(),
+        ErrorCode::MarketNotReadyToResolve.into()
+    );
+
+    market.resolve(1, 100).unwrap();
+    assert_eq!(market.refund_mode, 1);
+    assert_eq!(
+        market.resolve(0, 100).unwrap_err(),
+        ErrorCode::MarketAlreadyResolved.into()
+    );
+
+    // Every holder of the losing-but-funded outcome gets its deposit back pro rata
+    let refund_a = market.redeem(0, shares_a, vault).unwrap();
+    let refund_b = market.redeem(0, shares_b, vault - refund_a).unwrap();
+    assert!(deposited - (refund_a + refund_b) <= 1);
+    assert!(refund_a > refund_b);
+    assert_eq!(market.supplies[0], 0);
+    assert!(market.reserves[0] <= 1);
+}
+
+#[test]
+fn test_redeem_only_winner_outside_refund_mode() {
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+
+    let winning_shares = market.buy_shares(0, 1_000_000_000, NOW).unwrap();
+    let losing_shares = market.buy_shares(1, 1_000_000_000, NOW).unwrap();
+    let vault = market.reserves[0] + market.reserves[1];
+
+    market.resolve(0, 0).unwrap();
+    assert_eq!(market.refund_mode, 0);
+
+    assert_eq!(
+        market.redeem(1, losing_shares, vault).unwrap_err(),
+        ErrorCode::OutcomeNotWinner.into()
+    );
+    assert_eq!(
+        market.redeem(0, winning_shares + 1, vault).unwrap_err(),
+        ErrorCode::BurnIsMoreThanSupply.into()
+    );
+
+    // The sole winning holder takes the whole vault
+    assert_eq!(market.redeem(0, winning_shares, vault).unwrap(), vault);
+    assert_eq!(market.supplies[0], 0);
+}
+
+#[test]
+fn test_winning_supply() {
+    let mut market = Market::new_for_test(3, 1_000_000_000, 0).unwrap();
+    market.buy_shares(0, 500_000_000, NOW).unwrap();
+    let winning_shares = market.buy_shares(2, 1_000_000_000, NOW).unwrap();
+
+    assert_eq!(
+        market.winning_supply().unwrap_err(),
+        ErrorCode::MarketNotResolved.into()
+    );
+
+    market.resolve(2, 0).unwrap();
+    assert_eq!(market.winning_supply().unwrap(), winning_shares);
+
+    let mut voided = Market::new_for_test(3, 1_000_000_000, 0).unwrap();
+    voided.resolve(VOID_OUTCOME, 0).unwrap();
+    assert_eq!(
+        voided.winning_supply().unwrap_err(),
+        ErrorCode::InvalidOutcomeIndex.into()
+    );
+}
+
+/// The mint supply cross-check accepts only the exact `supplies[i]`, in token base units
+#[test]
+fn test_check_mint_supply() {
+    let mut market = Market::new_for_test(3, 1_000_000_000, 0).unwrap();
+    let shares = market.buy_shares(1, 1_000_000_000, NOW).unwrap();
+
+    market.check_mint_supply(1, shares).unwrap();
+    market.check_mint_supply(0, 0).unwrap();
+    for mint_supply in [shares - 1, shares + 1] {
+        assert_eq!(
+            market.check_mint_supply(1, mint_supply).unwrap_err(),
+            ErrorCode::MintSupplyMismatch.into()
+        );
+    }
+    assert_eq!(
+        market.check_mint_supply(3, 0).unwrap_err(),
+        ErrorCode::InvalidOutcomeIndex.into()
+    );
+
+    // Redemption burns from both sides, so they still agree afterwards
+    market.resolve(1, 0).unwrap();
+    market.redeem(1, shares / 2, 2_000_000_000).unwrap();
+    market.check_mint_supply(1, shares - shares / 2).unwrap();
+}
+
+/// Buying exact shares raises the supply by exactly that much, for what `buy_shares` would charge
+#[test]
+fn test_buy_exact_shares() {
+    for buy_bps in [0u16, 250] {
+        let mut market = Market::new_for_test(3, 1_000_000_000, 0).unwrap();
+        market.fee_config.buy_bps = buy_bps;
+        market.buy_shares(1, 400_000_000, NOW).unwrap();
+
+        let before = market;
+        let amount_in = market
+            .buy_exact_shares(0, 1_000_000_000, u64::MAX, NOW)
+            .unwrap();
+        assert_eq!(market.supplies[0], before.supplies[0] + 1_000_000_000);
+        assert_eq!(&market.supplies[1..], &before.supplies[1..]);
+
+        // The cost delta plus the fee
+        let fee = market.trade_fees(NOW).buy_fee(amount_in);
+        assert!(amount_in - fee >= before.cost_of_buy(0, 1_000_000_000).unwrap());
+        assert_eq!(market.reserves[0], before.reserves[0] + amount_in - fee);
+        assert_eq!(
+            market.accrued_protocol_fees,
+            before.accrued_protocol_fees + fee
+        );
+
+        // Spent the other way round, the same lamports buy the same shares up to the few units
+        // `buy_shares` rounds away
+        let mut dual = before;
+        let dual_shares = dual.buy_shares(0, amount_in, NOW).unwrap();
+        assert!(dual_shares.abs_diff(1_000_000_000) <= 10, "{}", dual_shares);
+
+        // Over the limit nothing moves
+        let mut limited = before;
+        assert_eq!(
+            limited
+                .buy_exact_shares(0, 1_000_000_000, amount_in - 1, NOW)
+                .unwrap_err(),
+            ErrorCode::SlippageExceeded.into()
+        );
+        assert_eq!(limited.supplies, before.supplies);
+    }
+
+    let mut market = Market::new_for_test(2, 1_000_000_000, 0).unwrap();
+    assert_eq!(
+        market.buy_exact_shares(0, 0, u64::MAX, NOW).unwrap_err(),
+        ErrorCode::SharesAreZero.into()
+    );
+    assert_eq!(
+        market
+            .buy_exact_shares(0, 18_000_000_001, u64::MAX, NOW)
+            .unwrap_err(),
+        ErrorCode::SupplyWouldSaturate.into()
+    );
+    market.outcome_decimals = 6;
+    assert_eq!(
+        market
+            .buy_exact_shares(0, 1_500, u64::MAX, NOW)
+            .unwrap_err(),
+        ErrorCode::InvalidShareAmount.into()
+    );
+}
+
+/// The mint decimals only change how coarse the shares minted are, the 1e9 LMSR math is the same
+#[test]
+fn test_outcome_decimals_leave_prices_unchanged() {
+    let nine = Market::new_for_test(3, 1_000_000_000, 0).unwrap();
+    let six = Market {
+        outcome_decimals: 6,
+        ..nine
+    };
+    assert_eq!((nine.mint_decimals(), nine.share_unit()), (9, 1));
+    assert_eq!((six.mint_decimals(), six.share_unit()), (6, 1_000));
+
+    // The same supplies price and cost the same
+    let (mut nine, mut six) = (nine, six);
+    for market in [&mut nine, &mut six] {
+        market.supplies[..3].copy_from_slice(&[1_000_000_000, 250_000_000, 0]);
+    }
+    assert_eq!(six.prices().unwrap(), nine.prices().unwrap());
+    assert_eq!(six.cost_precise().unwrap(), nine.cost_precise().unwrap());
+
+    // A buy mints the same shares, rounded down to whole 6 decimal tokens
+    let nine_shares = nine.buy_shares(1, 700_000_000, NOW).unwrap();
+    let six_shares = six.buy_shares(1, 700_000_000, NOW).unwrap();
+    assert_eq!(six_shares, nine_shares - nine_shares % 1_000);
+    assert_eq!(
+        six.shares_to_tokens(six_shares).unwrap(),
+        six_shares / 1_000
+    );
+    assert_eq!(six.supplies[1] % 1_000, 0);
+    six.check_mint_supply(1, six.supplies[1] / 1_000).unwrap();
+
+    // Token amounts must be whole base units
+    assert_eq!(
+        six.shares_to_tokens(1_500).unwrap_err(),
+        ErrorCode::InvalidShareAmount.into()
+    );
+    assert_eq!(nine.shares_to_tokens(1_500).unwrap(), 1_500);
+
+    // Back at equal supplies the prices agree again
+    nine.supplies[1] = six.supplies[1];
+    assert_eq!(six.prices().unwrap(), nine.prices().unwrap());
+}
+
+/// Pages of `prices_range` concatenate to `prices()`, which is `price()` of every outcome
+#[test]
+fn test_prices_range_pages_reproduce_prices() {
+    for (num_outcomes, high_precision) in [(16u8, 0u8), (16, 1), (5, 0), (2, 1)] {
+        let mut market = Market::new_for_test(num_outcomes, 1_000_000_000, 0).unwrap();
+        market.high_precision = high_precision;
+
+        for trade in 0..2 {
+            let prices = market.prices().unwrap();
+            let expected: Vec<u64> = (0..num_outcomes)
+                .map(|i| market.price(i).unwrap())
+                .collect();
+            assert_eq!(prices, expected);
+
+            for page in [1, 3, 7] {
+                let mut paged = Vec::new();
+                let mut sums = Vec::new();
+                for start in (0..num_outcomes as usize).step_by(page) {
+                    let len = page.min(num_outcomes as usize - start);
+                    let (slice, sum_exp) = market.prices_range(start, len).unwrap();
+                    paged.extend(slice);
+                    sums.push(sum_exp);
+                }
+                assert_eq!(paged, prices);
+                assert!(sums.iter().all(|&sum| sum == sums[0]));
+                assert_eq!(sums[0] == 0, trade == 0);
+            }
+
+            for i in 0..num_outcomes {
+                market
+                    .buy_shares(i, 10_000_000 * (i as u64 % 4 + 1), NOW)
+                    .unwrap();
+            }
+        }
+
+        assert_eq!(
+            market
+                .prices_range(num_outcomes as usize - 1, 2)
+                .unwrap_err(),
+            ErrorCode::InvalidOutcomeIndex.into()
+        );
+        assert_eq!(
+            market.prices_range(usize::MAX, 2).unwrap_err(),
+            ErrorCode::InvalidOutcomeIndex.into()
+        );
+    }
+}
+
+#[test]
+fn test_describe() {
+    let mut market = Market::new_for_test(3, 1_000_000_000, 10_000).unwrap();
+    market.label = FixedSizeString::new("describe_me");
+    market.buy_shares(1, 250_000_000, NOW).unwrap();
+    market.trading_open_at = 1_000;
+    market.trading_halt_before_resolve = 600;
+
+    let description = market.describe(5_000);
+    println!("{description}");
+    assert!(description.contains("\"describe_me\""));
+    assert!(description.contains("3 outcomes"));
+    assert!(description.contains("status:  open"));
+    assert_eq!(description.matches("outcome ").count(), 3);
+    assert!(description.contains(&format!("supply {}", market.supplies[1])));
+    assert!(description.ends_with("resolve: at 10000 (in 0d 01h 23m 20s)"));
+
+    let status = |market: &Market, now: i64| {
+        let description = market.describe(now);
+        let line = description.lines().find(|line| line.contains("status:"));
+        line.unwrap().trim().to_string()
+    };
+    assert_eq!(status(&market, 999), "status:  paused until 1000");
+    assert_eq!(status(&market, 1_000), "status:  open");
+    assert_eq!(status(&market, 9_399), "status:  open");
+    assert_eq!(status(&market, 9_400), "status:  halted before resolution");
+    assert_eq!(status(&market, 10_000), "status:  awaiting resolution");
+    assert!(market.describe(20_000).ends_with("(in 0d 00h 00m 00s)"));
+
+    market.resolve(1, 10_000).unwrap();
+    assert_eq!(status(&market, 10_000), "status:  resolved to outcome 1");
+}
+
+#[test]
+fn test_price_delta_since_snapshot() {
+    const DAY: i64 = 86_400;
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        market.price_delta_since_snapshot(0).unwrap_err(),
+        ErrorCode::NoPriceSnapshot.into()
+    );
+
+    // Day 0: even odds
+    market.snapshot_prices(DAY).unwrap();
+    assert_eq!(market.price_snapshot[0], 500_000_000);
+    assert_eq!(market.price_delta_since_snapshot(0).unwrap(), 0);
+
+    market.buy_shares(0, 500_000_000, NOW).unwrap();
+    let expected = |market: &Market, i: usize| {
+        let price = market.price(i as u8).unwrap() as i64;
+        let snapshot = market.price_snapshot[i] as i64;
+        (price - snapshot) * 10_000 / snapshot
+    };
+    let delta_0 = market.price_delta_since_snapshot(0).unwrap();
+    let delta_1 = market.price_delta_since_snapshot(1).unwrap();
+    assert!(delta_0 > 0 && delta_1 < 0);
+    assert_eq!(delta_0, expected(&market, 0));
+    // Binary prices sum to 1, so the moves mirror each other off an even snapshot
+    assert!((delta_0 + delta_1).abs() <= 1);
+
+    // Rate limited to once a day
+    assert_eq!(
+        market.snapshot_prices(2 * DAY - 1).unwrap_err(),
+        ErrorCode::SnapshotTooSoon.into()
+    );
+
+    // Day 1: the new snapshot becomes the reference
+    market.snapshot_prices(2 * DAY).unwrap();
+    assert_eq!(market.snapshot_at, 2 * DAY);
+    assert_eq!(market.price_delta_since_snapshot(0).unwrap(), 0);
+
+    market.buy_shares(1, 1_000_000_000, NOW).unwrap();
+    let delta_0 = market.price_delta_since_snapshot(0).unwrap();
+    assert!(delta_0 < 0);
+    assert_eq!(delta_0, expected(&market, 0));
+    assert_eq!(
+        market.price_delta_since_snapshot(1).unwrap(),
+        expected(&market, 1)
+    );
+}
+
+#[test]
+fn test_buy_shares_amount_extremes() {
+    // Tiny b: the exp argument amount_in / b is far outside the fp_exp domain
+    let mut market = Market {
+        scale: 1,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+    assert_eq!(
+        market.buy_shares(0, u64::MAX, NOW).unwrap_err(),
+        ErrorCode::BuyAmountOutOfRange.into()
+    );
+    assert_eq!(market.supplies, [0; 16]);
+
+    // One lamport past the domain edge is rejected before any shares are computed
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+    assert_eq!(
+        market.buy_shares(0, 20_000_000_001, NOW).unwrap_err(),
+        ErrorCode::BuyAmountOutOfRange.into()
+    );
+
+    // Huge b: the exp argument is small but the shares minted no longer fit in a u64
+    let mut market = Market {
+        scale: u64::MAX,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+    assert_eq!(
+        market.buy_shares(0, u64::MAX, NOW).unwrap_err(),
+        ErrorCode::MathOverflow.into()
+    );
+    // Deposits too small to move exp(amount_in / b) off 1.0 mint nothing
+    assert_eq!(
+        market.buy_shares(0, 1_000_000_000, NOW).unwrap_err(),
+        ErrorCode::DepositIsZero.into()
+    );
+    assert!(market.buy_shares(0, 1_000_000_000_000, NOW).unwrap() > 0);
+}
+
+#[test]
+fn test_buy_shares_supply_limit() {
+    let b = 1_000_000_000u64;
+    let mut market = Market {
+        scale: b,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+
+    // Buys up to the boundary succeed
+    let max_buy = market.max_buy(0, NOW).unwrap();
+    let mut copy = market;
+    assert_eq!(
+        copy.buy_shares(0, max_buy + 1, NOW).unwrap_err(),
+        ErrorCode::SupplyWouldSaturate.into()
+    );
+    assert_eq!(copy.supplies, [0; 16]);
+
+    market.buy_shares(0, max_buy / 2, NOW).unwrap();
+    let remaining = market.max_buy(0, NOW).unwrap();
+    market.buy_shares(0, remaining, NOW).unwrap();
+    assert!(market.supplies[0] <= MAX_EXP_ARG * b);
+    assert!(market.supplies[0] > (MAX_EXP_ARG - 1) * b);
+
+    // The next one is cleanly rejected without mutating state
+    let supplies = market.supplies;
+    let reserves = market.reserves;
+    assert_eq!(
+        market.buy_shares(0, 1_000_000, NOW).unwrap_err(),
+        ErrorCode::SupplyWouldSaturate.into()
+    );
+    assert_eq!(market.supplies, supplies);
+    assert_eq!(market.reserves, reserves);
+
+    // Prices and cost are still well-defined at the limit
+    assert!(market.price_sum_residual().unwrap().unsigned_abs() <= price_sum_tolerance(2));
+    market.cost().unwrap();
+
+    // The other outcome is nearly free now, so even small buys mint many shares, and the
+    // limit applies to them too
+    market.buy_shares(1, 1_000_000, NOW).unwrap();
+    assert_eq!(
+        market.buy_shares(1, 1_000_000_000, NOW).unwrap_err(),
+        ErrorCode::SupplyWouldSaturate.into()
+    );
+}
+
+#[test]
+fn test_read_oracle() {
+    let oracle = Pubkey::new_unique();
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 3,
+        ..Default::default()
+    };
+    let report = OracleReport { winning_outcome: 2 }.try_to_vec().unwrap();
+
+    assert_eq!(
+        market.read_oracle(&oracle, &report).unwrap_err(),
+        ErrorCode::OracleNotSet.into()
+    );
+
+    market.oracle = oracle;
+    assert_eq!(
+        market
+            .read_oracle(&Pubkey::new_unique(), &report)
+            .unwrap_err(),
+        ErrorCode::InvalidOracle.into()
+    );
+    assert_eq!(
+        market.read_oracle(&oracle, &[]).unwrap_err(),
+        ErrorCode::InvalidOracleData.into()
+    );
+
+    // Trailing bytes past the report are ignored
+    let mut extended = report.clone();
+    extended.extend_from_slice(&[0xff; 31]);
+    assert_eq!(market.read_oracle(&oracle, &report).unwrap(), 2);
+    assert_eq!(market.read_oracle(&oracle, &extended).unwrap(), 2);
+
+    // An out of range report is rejected when resolving
+    let bogus = OracleReport { winning_outcome: 3 }.try_to_vec().unwrap();
+    let winning_outcome = market.read_oracle(&oracle, &bogus).unwrap();
+    assert_eq!(
+        market.resolve(winning_outcome, 0).unwrap_err(),
+        ErrorCode::InvalidOutcomeIndex.into()
+    );
+}
+
+#[test]
+fn test_void_refunds_pro_rata() {
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 3,
+        ..Default::default()
+    };
+    assert_eq!(market.resolution(), None);
+
+    // Two holders of outcome 0 and one of outcome 1, paying in different amounts
+    let a = market.buy_shares(0, 900_000_000, NOW).unwrap();
+    let b = market.buy_shares(0, 300_000_000, NOW).unwrap();
+    let c = market.buy_shares(1, 2_000_000_000, NOW).unwrap();
+    let vault = market.cost().unwrap() + 1_000_000;
+
+    market.resolve(VOID_OUTCOME, 0).unwrap();
+    assert_eq!(market.resolution(), Some(MarketResolution::Void));
+    assert_eq!(market.winning_outcome, VOID_OUTCOME);
+    assert_eq!(
+        market.resolve(VOID_OUTCOME, 0).unwrap_err(),
+        ErrorCode::MarketAlreadyResolved.into()
+    );
+
+    // Every outcome redeems, nobody is a winner: each outcome's deposits go back to its holders
+    // in proportion to their shares
+    let refund_a = market.redeem(0, a, vault).unwrap();
+    let refund_b = market.redeem(0, b, vault).unwrap();
+    let refund_c = market.redeem(1, c, vault).unwrap();
+    assert!((refund_a + refund_b).abs_diff(1_200_000_000) <= 1);
+    assert_eq!(refund_c, 2_000_000_000);
+    let expected_a = 1_200_000_000u128 * a as u128 / (a + b) as u128;
+    assert!(refund_a.abs_diff(expected_a as u64) <= 1);
+
+    // The winner path is unchanged, and the sentinel is no outcome index for it
+    let mut won = Market::new_for_test(3, 1_000_000_000, 0).unwrap();
+    won.buy_shares(2, 1_000_000_000, NOW).unwrap();
+    won.resolve(2, 0).unwrap();
+    assert_eq!(won.resolution(), Some(MarketResolution::Winner(2)));
+    assert_eq!(won.refund_mode, 0);
+    assert_eq!(
+        MarketResolution::from_outcome(VOID_OUTCOME),
+        MarketResolution::Void
+    );
+}
+
+#[test]
+fn test_resolve_paths_share_guard() {
+    let oracle = Pubkey::new_unique();
+    let report = OracleReport { winning_outcome: 1 }.try_to_vec().unwrap();
+    let fresh = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        resolve_at: 100,
+        oracle,
+        ..Default::default()
+    };
+
+    assert!(!fresh.can_resolve(99));
+    assert!(fresh.can_resolve(100));
+
+    // Both paths reject resolving early through the shared guard
+    assert_eq!(
+        fresh.clone().resolve(1, 99).unwrap_err(),
+        ErrorCode::MarketNotReadyToResolve.into()
+    );
+    assert_eq!(
+        fresh
+            .clone()
+            .resolve_from_oracle(&oracle, &report, 99)
+            .unwrap_err(),
+        ErrorCode::MarketNotReadyToResolve.into()
+    );
+
+    // Consensus too
+    let mut decided = fresh;
+    decided.buy_shares(1, 2_500_000_000, NOW).unwrap();
+    decided.track_consensus(1).unwrap();
+    assert_eq!(
+        decided.clone().try_resolve_by_consensus(99).unwrap_err(),
+        ErrorCode::MarketNotReadyToResolve.into()
+    );
+
+    // Whichever path resolved first, no path can resolve again
+    let mut by_admin = fresh;
+    by_admin.resolve(0, 100).unwrap();
+    let mut by_oracle = fresh;
+    by_oracle
+        .resolve_from_oracle(&oracle, &report, 100)
+        .unwrap();
+    assert_eq!(by_oracle.winning_outcome, 1);
+    let mut by_consensus = decided;
+    by_consensus
+        .try_resolve_by_consensus(1 + CONSENSUS_DWELL_SECONDS)
+        .unwrap();
+    assert_eq!(by_consensus.winning_outcome, 1);
+
+    for market in [&mut by_admin, &mut by_oracle, &mut by_consensus] {
+        assert!(!market.can_resolve(200));
+        assert_eq!(
+            market.resolve(1, 200).unwrap_err(),
+            ErrorCode::MarketAlreadyResolved.into()
+        );
+        assert_eq!(
+            market
+                .resolve_from_oracle(&oracle, &report, 200)
+                .unwrap_err(),
+            ErrorCode::MarketAlreadyResolved.into()
+        );
+        assert_eq!(
+            market.try_resolve_by_consensus(200).unwrap_err(),
+            ErrorCode::MarketAlreadyResolved.into()
+        );
+    }
+    assert_eq!(by_admin.winning_outcome, 0);
+}
+
+/// Only a price held above the consensus threshold for the dwell time resolves the market
+#[test]
+fn test_resolve_by_consensus_needs_sustained_consensus() {
+    let mut market = Market::new_for_test(2, 1_000_000_000, 0).unwrap();
+    market.track_consensus(10).unwrap();
+    assert_eq!(market.consensus_since, 0);
+    assert_eq!(
+        market.clone().try_resolve_by_consensus(10).unwrap_err(),
+        ErrorCode::NoOutcomeHasConsensus.into()
+    );
+
+    // A spike over 95% starts the streak but can't resolve in the same transaction
+    let spike = market.buy_shares(1, 2_500_000_000, NOW).unwrap();
+    market.track_consensus(100).unwrap();
+    assert_eq!((market.consensus_outcome, market.consensus_since), (1, 100));
+    assert_eq!(
+        market.clone().try_resolve_by_consensus(100).unwrap_err(),
+        ErrorCode::ConsensusDwellNotElapsed.into()
+    );
+
+    // Further trades above the threshold keep the streak, selling back below ends it
+    market.buy_shares(1, 100_000_000, NOW).unwrap();
+    market.track_consensus(200).unwrap();
+    assert_eq!(market.consensus_since, 100);
+    market.sell_shares(1, spike, NOW).unwrap();
+    market.track_consensus(300).unwrap();
+    assert_eq!(market.consensus_since, 0);
+    assert_eq!(
+        market
+            .clone()
+            .try_resolve_by_consensus(100 + CONSENSUS_DWELL_SECONDS)
+            .unwrap_err(),
+        ErrorCode::NoOutcomeHasConsensus.into()
+    );
+
+    // Held for the whole dwell time it resolves, but only once resolve_at has passed
+    market.buy_shares(1, 2_500_000_000, NOW).unwrap();
+    market.track_consensus(400).unwrap();
+    let early = Market {
+        resolve_at: 400 + 2 * CONSENSUS_DWELL_SECONDS,
+        ..market
+    };
+    assert_eq!(
+        early
+            .clone()
+            .try_resolve_by_consensus(400 + CONSENSUS_DWELL_SECONDS)
+            .unwrap_err(),
+        ErrorCode::MarketNotReadyToResolve.into()
+    );
+    assert_eq!(
+        market
+            .clone()
+            .try_resolve_by_consensus(400 + CONSENSUS_DWELL_SECONDS - 1)
+            .unwrap_err(),
+        ErrorCode::ConsensusDwellNotElapsed.into()
+    );
+    market
+        .try_resolve_by_consensus(400 + CONSENSUS_DWELL_SECONDS)
+        .unwrap();
+    assert_eq!((market.resolved, market.winning_outcome), (1, 1));
+    assert_eq!(
+        market
+            .try_resolve_by_consensus(400 + CONSENSUS_DWELL_SECONDS)
+            .unwrap_err(),
+        ErrorCode::MarketAlreadyResolved.into()
+    );
+}
+
+#[test]
+fn test_validate_num_outcomes() {
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+    market.validate_num_outcomes().unwrap();
+
+    market.num_outcomes = 16;
+    market.validate_num_outcomes().unwrap();
+
+    // One past the arrays would index out of bounds in every math loop
+    market.num_outcomes = 17;
+    assert_eq!(
+        market.validate_num_outcomes().unwrap_err(),
+        ErrorCode::TooManyOutcomes.into()
+    );
+
+    market.num_outcomes = 1;
+    assert_eq!(
+        market.validate_num_outcomes().unwrap_err(),
+        ErrorCode::NotEnoughOutcomes.into()
+    );
+}
+
+/// Each state the fixed-point math can't evaluate fails `cost`, `price` and `buy_shares` alike,
+/// with its own error
+#[test]
+fn test_validate_math_state() {
+    let valid = Market::new_for_test(2, 1_000_000_000, 0).unwrap();
+    valid.validate_math_state().unwrap();
+
+    let assert_rejected = |market: Market, error: ErrorCode| {
+        let error: anchor_lang::error::Error = error.into();
+        assert_eq!(market.validate_math_state().unwrap_err(), error);
+        assert_eq!(market.cost().unwrap_err(), error);
+        assert_eq!(market.price(0).unwrap_err(), error);
+        assert_eq!(market.clone().buy_shares(0, 1_000, NOW).unwrap_err(), error);
+    };
+
+    assert_rejected(
+        Market {
+            num_outcomes: 1,
+            ..valid
+        },
+        ErrorCode::NotEnoughOutcomes,
+    );
+    assert_rejected(
+        Market { scale: 0, ..valid },
+        ErrorCode::LiquidityParameterIsZero,
+    );
+
+    // Supplies up to the edge of the fp_exp domain still evaluate, even past the trading cap
+    let mut edge = valid;
+    edge.supplies[1] = 20 * valid.scale;
+    edge.cost().unwrap();
+    let mut saturated = valid;
+    saturated.supplies[1] = 20 * valid.scale + 1;
+    assert_rejected(saturated, ErrorCode::SupplySaturated);
+
+    // At 1e18 the bound is the same q / b
+    saturated.high_precision = 1;
+    assert_rejected(saturated, ErrorCode::SupplySaturated);
+    edge.high_precision = 1;
+    edge.cost().unwrap();
+}
+
+#[test]
+fn test_out_of_range_outcome_index_is_uniform() {
+    let mut market = Market::new_for_test(3, 1_000_000_000, 0).unwrap();
+    market.buy_shares(0, 1_000_000_000, NOW).unwrap();
+    assert_eq!(market.checked_outcome(2).unwrap(), 2);
+
+    let invalid: anchor_lang::error::Error = ErrorCode::InvalidOutcomeIndex.into();
+    let check = |market: &Market, index: u8| {
+        let mut mutable = *market;
+        let results = [
+            market.checked_outcome(index).map(|_| ()),
+            market.price(index).map(|_| ()),
+            market.marginal_cost(index).map(|_| ()),
+            market.max_buy(index, NOW).map(|_| ()),
+            market.quote_buy(index, 1_000, NOW).map(|_| ()),
+            market
+                .price_after_hypothetical_buy(index, 1_000, 0, NOW)
+                .map(|_| ()),
+            market
+                .price_after_hypothetical_buy(0, 1_000, index, NOW)
+                .map(|_| ()),
+            market.price_delta_since_snapshot(index).map(|_| ()),
+            market.price_cumulative_now(index, 0).map(|_| ()),
+            market.twap_between((0, 0), (1, 1), index).map(|_| ()),
+            mutable.buy_shares(index, 1_000, NOW).map(|_| ()),
+            mutable.sell_shares(index, 1, NOW).map(|_| ()),
+            mutable.mark_mint_initialized(index),
+            mutable.finalize_resolution(MarketResolution::Winner(index)),
+        ];
+        for (i, result) in results.into_iter().enumerate() {
+            assert_eq!(result.unwrap_err(), invalid, "method {}", i);
+        }
+
+        let mut resolved = *market;
+        resolved.resolved = 1;
+        assert_eq!(resolved.redeem(index, 1, 1_000).unwrap_err(), invalid);
+    };
+
+    check(&market, 3);
+    check(&market, u8::MAX);
+
+    // A corrupted outcome count can't make an index look valid
+    market.num_outcomes = 17;
+    check(&market, 0);
+}
+
+/// LMSR prices depend only on the differences between supplies, so a symmetric supply floor
+/// leaves the first trade's price impact unchanged. The liquidity parameter `b` is the cushion.
+#[test]
+fn test_price_impact_is_cushioned_by_scale_not_supply_floor() {
+    let first_trade_impact = |market: &mut Market| {
+        let before = market.price(0).unwrap();
+        market.buy_shares(0, 1_000_000_000, NOW).unwrap();
+        market.price(0).unwrap() - before
+    };
+
+    let mut unfloored = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+    let mut floored = Market {
+        supplies: [2_000_000_000; 16],
+        ..unfloored
+    };
+    assert_eq!(floored.price(0).unwrap(), unfloored.price(0).unwrap());
+
+    let unfloored_impact = first_trade_impact(&mut unfloored);
+    let floored_impact = first_trade_impact(&mut floored);
+    assert!(unfloored_impact.abs_diff(floored_impact) <= 1_000);
+
+    let mut deeper = Market {
+        scale: 10_000_000_000,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+    let deeper_impact = first_trade_impact(&mut deeper);
+    assert!(deeper_impact * 5 < unfloored_impact);
+}
+
+#[test]
+fn test_price_after_hypothetical_buy() {
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 3,
+        ..Default::default()
+    };
+    market.buy_shares(1, 300_000_000, NOW).unwrap();
+
+    let before = market;
+    let what_if: Vec<u64> = (0..3)
+        .map(|i| {
+            market
+                .price_after_hypothetical_buy(0, 700_000_000, i, NOW)
+                .unwrap()
+        })
+        .collect();
+    // Querying doesn't touch the market
+    assert_eq!(market.supplies, before.supplies);
+    assert_eq!(market.reserves, before.reserves);
+
+    market.buy_shares(0, 700_000_000, NOW).unwrap();
+    for (i, price) in what_if.iter().enumerate() {
+        assert_eq!(*price, market.price(i as u8).unwrap());
+    }
+
+    // Fails exactly when the buy would
+    assert_eq!(
+        market
+            .price_after_hypothetical_buy(0, 0, 1, NOW)
+            .unwrap_err(),
+        ErrorCode::DepositIsZero.into()
+    );
+    assert_eq!(
+        market
+            .price_after_hypothetical_buy(0, 1_000_000_000, 3, NOW)
+            .unwrap_err(),
+        ErrorCode::InvalidOutcomeIndex.into()
+    );
+}
+
+#[test]
+fn test_accrue_tvl_fee_one_day() {
+    const DAY: i64 = 86_400;
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        resolve_at: 30 * DAY,
+        fee_config: FeeConfig {
+            tvl_fee: 100_000_000_000_000_000, // 10%/year, MAX_TVL_FEE
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    market.buy_shares(0, 1_000_000_000, NOW).unwrap();
+
+    // 365 SOL locked for one day at 10%/year is 0.1 SOL
+    let vault = 365_000_000_000;
+    assert_eq!(market.accrue_tvl_fee(vault, DAY).unwrap(), 100_000_000);
+    assert_eq!(market.accrued_protocol_fees, 100_000_000);
+    assert_eq!(market.last_fee_accrual, DAY);
+    assert_eq!(market.tvl(vault), vault - 100_000_000);
+
+    // Nothing more accrues until time passes
+    assert_eq!(market.accrue_tvl_fee(vault, DAY).unwrap(), 0);
+
+    // Accrual stops at resolve_at, however late the crank runs
+    let fee = market.accrue_tvl_fee(vault, 365 * DAY).unwrap();
+    let tvl = (vault - 100_000_000) as u128;
+    assert_eq!(fee as u128, tvl * 29 / 10 / 365);
+    assert_eq!(market.last_fee_accrual, 30 * DAY);
+    assert_eq!(market.accrue_tvl_fee(vault, 366 * DAY).unwrap(), 0);
+}
+
+/// Fees settled at resolution stay in the vault: winners redeem exactly `vault - fees`
+#[test]
+fn test_redemptions_never_touch_fees_settled_at_resolution() {
+    const DAY: i64 = 86_400;
+    let mut market = Market::new_for_test(2, 1_000_000_000, 30 * DAY).unwrap();
+    market.fee_config.buy_bps = 100;
+    market.fee_config.tvl_fee = 100_000_000_000_000_000;
+
+    let first = market.buy_shares(0, 3_000_000_000, NOW).unwrap();
+    let second = market.buy_shares(0, 2_000_000_000, NOW).unwrap();
+    market.buy_shares(1, 1_000_000_000, NOW).unwrap();
+    let vault = 6_000_000_000 + 700_000_000;
+
+    // As every resolution path does: settle the TVL fee, then resolve
+    market.accrue_tvl_fee(vault, 30 * DAY).unwrap();
+    market.resolve(0, 30 * DAY).unwrap();
+    let fees = market.accrued_protocol_fees;
+    assert!(fees > 60_000_000);
+
+    // Nothing accrues once resolved, however late a crank runs
+    assert_eq!(market.accrue_tvl_fee(vault, 40 * DAY).unwrap(), 0);
+    assert_eq!(market.accrued_protocol_fees, fees);
+
+    let paid_first = market.redeem(0, first, vault).unwrap();
+    let paid_second = market.redeem(0, second, vault - paid_first).unwrap();
+    assert_eq!(paid_first + paid_second, vault - fees);
+    assert_eq!(
+        vault - paid_first - paid_second,
+        market.accrued_protocol_fees
+    );
+}
+
+#[test]
+fn test_accrue_tvl_fee_never_drains_redemptions() {
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        resolve_at: i64::MAX,
+        fee_config: FeeConfig {
+            tvl_fee: 100_000_000_000_000_000,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let shares = market.buy_shares(0, 2_000_000_000, NOW).unwrap();
+
+    // A vault only slightly above the largest supply: a decade of fees is capped at the surplus
+    let vault = shares + 1_000;
+    let fee = market.accrue_tvl_fee(vault, 10 * 365 * 86_400).unwrap();
+    assert_eq!(fee, 1_000);
+    assert_eq!(market.tvl(vault), shares);
+
+    // Above MAX_TVL_FEE is rejected
+    market.fee_config.tvl_fee = 100_000_000_000_000_001;
+    assert_eq!(
+        market.accrue_tvl_fee(vault, i64::MAX).unwrap_err(),
+        ErrorCode::InvalidTvlFee.into()
+    );
+}
+
+#[test]
+fn test_trading_window() {
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        trading_open_at: 100,
+        resolve_at: 200,
+        mints_initialized: 0b11,
+        ..Default::default()
+    };
+
+    // Before open, during, and from resolve_at on
+    assert_eq!(
+        market.is_tradable(99).unwrap_err(),
+        ErrorCode::MarketPaused.into()
+    );
+    market.is_tradable(100).unwrap();
+    market.is_tradable(199).unwrap();
+    assert_eq!(
+        market.is_tradable(200).unwrap_err(),
+        ErrorCode::MarketExpired.into()
+    );
+
+    // Halftime: close trading until 150
+    market.set_trading_window(150).unwrap();
+    assert_eq!(
+        market.is_tradable(120).unwrap_err(),
+        ErrorCode::MarketPaused.into()
+    );
+    market.is_tradable(150).unwrap();
+
+    assert_eq!(
+        market.set_trading_window(200).unwrap_err(),
+        ErrorCode::InvalidTradingWindow.into()
+    );
+
+    market.resolve(0, 200).unwrap();
+    assert_eq!(
+        market.is_tradable(150).unwrap_err(),
+        ErrorCode::MarketAlreadyResolved.into()
+    );
+    assert_eq!(
+        market.set_trading_window(0).unwrap_err(),
+        ErrorCode::MarketAlreadyResolved.into()
+    );
+}
+
+/// Each condition that blocks trading surfaces its own error through `is_tradable`
+#[test]
+fn test_is_tradable_table() {
+    let open = Market {
+        trading_open_at: 100,
+        resolve_at: 200,
+        trading_halt_before_resolve: 20,
+        ..Market::new_for_test(3, 1_000_000_000, 200).unwrap()
+    };
+    open.is_tradable(150).unwrap();
+
+    let cases: [(&str, Market, i64, ErrorCode); 5] = [
+        (
+            "mint missing",
+            Market {
+                mints_initialized: 0b011,
+                ..open
+            },
+            150,
+            ErrorCode::MintsNotReady,
+        ),
+        (
+            "resolved",
+            Market {
+                resolved: 1,
+                ..open
+            },
+            150,
+            ErrorCode::MarketAlreadyResolved,
+        ),
+        ("not open yet", open, 99, ErrorCode::MarketPaused),
+        ("halted", open, 180, ErrorCode::TradingWindowClosed),
+        ("expired", open, 200, ErrorCode::MarketExpired),
+    ];
+    for (case, market, now, error) in cases {
+        assert_eq!(market.is_tradable(now).unwrap_err(), error.into(), "{case}");
+    }
+}
+
+/// Trades during the fee holiday accrue no fees, trades after it the configured ones
+#[test]
+fn test_fee_holiday() {
+    let mut market = Market::new_for_test(2, 1_000_000_000, 1_000).unwrap();
+    market.fee_config.buy_bps = 100;
+    market.fee_config.sell_bps = 200;
+    market.set_fee_holiday(500).unwrap();
+
+    let shares = market.buy_shares(0, 100_000_000, 499).unwrap();
+    market.sell_shares(0, shares / 2, 499).unwrap();
+    assert_eq!(market.accrued_protocol_fees, 0);
+
+    market.buy_shares(1, 100_000_000, 500).unwrap();
+    assert_eq!(market.accrued_protocol_fees, 1_000_000);
+
+    // Decided by the time passed in, not by when prices were last accumulated, so a quote off a
+    // market no one has traded since the holiday ended charges the fee too
+    assert_eq!(market.price_cumulative_at, 0);
+    let mut charged = market;
+    let quote = market.quote_buy(0, 100_000_000, 600).unwrap();
+    assert_eq!(
+        quote.shares_out,
+        charged.buy_shares(0, 100_000_000, 600).unwrap()
+    );
+    assert_eq!(charged.accrued_protocol_fees, 2_000_000);
+    assert!(quote.shares_out < market.quote_buy(0, 100_000_000, 499).unwrap().shares_out);
+
+    assert_eq!(
+        market.set_fee_holiday(1_001).unwrap_err(),
+        ErrorCode::InvalidFeeHoliday.into()
+    );
+    market.set_fee_holiday(1_000).unwrap();
+    assert_eq!(market.trade_fees(999).buy_bps, 0);
+    assert_eq!(market.trade_fees(1_000), market.fee_config);
+    market.set_fee_holiday(0).unwrap();
+    assert_eq!(market.trade_fees(NOW), market.fee_config);
+}
+
+/// No one trades in `[resolve_at - halt, resolve_at)`, nor after `resolve_at`
+#[test]
+fn test_trading_halt_before_resolve() {
+    let mut market = Market::new_for_test(2, 1_000_000_000, 200).unwrap();
+    market.trading_open_at = 100;
+    market.trading_halt_before_resolve = 30;
+    assert_eq!(market.trading_close_at(), 170);
+
+    market.is_tradable(169).unwrap();
+    for now in [170, 199] {
+        assert_eq!(
+            market.is_tradable(now).unwrap_err(),
+            ErrorCode::TradingWindowClosed.into()
+        );
+    }
+    for now in [200, 10_000] {
+        assert_eq!(
+            market.is_tradable(now).unwrap_err(),
+            ErrorCode::MarketExpired.into()
+        );
+    }
+
+    // Reopening trading can't reach into the halt either
+    assert_eq!(
+        market.set_trading_window(170).unwrap_err(),
+        ErrorCode::InvalidTradingWindow.into()
+    );
+    market.set_trading_window(169).unwrap();
+
+    market.resolve(0, 200).unwrap();
+    assert_eq!(
+        market.is_tradable(169).unwrap_err(),
+        ErrorCode::MarketAlreadyResolved.into()
+    );
+}
+
+#[test]
+fn test_sell_shares_round_trip() {
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+
+    let shares = market.buy_shares(0, 1_000_000_000, NOW).unwrap();
+    let payout = market.sell_shares(0, shares, NOW).unwrap();
+
+    // Selling straight back returns the deposit, never more
+    assert!(payout <= 1_000_000_000);
+    assert!(1_000_000_000 - payout < 1_000);
+    assert_eq!(market.supplies[0], 0);
+    // The market is empty again, so the rounding it kept is no longer on the ledger
+    assert_eq!(market.reserves[0], 0);
+
+    assert_eq!(
+        market.sell_shares(0, 1, NOW).unwrap_err(),
+        ErrorCode::BurnIsMoreThanSupply.into()
+    );
+    assert_eq!(
+        market.sell_shares(0, 0, NOW).unwrap_err(),
+        ErrorCode::BurnIsZero.into()
+    );
+}
+
+#[test]
+fn test_quote_buy() {
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+    market.buy_shares(1, 400_000_000, NOW).unwrap();
+
+    let quote = market.quote_buy(0, 1_000_000_000, NOW).unwrap();
+    assert_eq!(market.supplies[0], 0);
+
+    let shares_out = market.buy_shares(0, 1_000_000_000, NOW).unwrap();
+    assert_eq!(quote.shares_out, shares_out);
+    assert_eq!(quote.new_price, market.price(0).unwrap());
+    assert!(quote.new_price > 500_000_000);
+
+    assert_eq!(
+        market.quote_buy(2, 1_000_000_000, NOW).unwrap_err(),
+        ErrorCode::InvalidOutcomeIndex.into()
+    );
+    assert_eq!(
+        market.quote_buy(0, 0, NOW).unwrap_err(),
+        ErrorCode::DepositIsZero.into()
+    );
+}
+
+#[test]
+fn test_sell_basket() {
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 3,
+        ..Default::default()
+    };
+    let held = [
+        market.buy_shares(0, 1_000_000_000, NOW).unwrap(),
+        market.buy_shares(1, 500_000_000, NOW).unwrap(),
+        market.buy_shares(2, 250_000_000, NOW).unwrap(),
+    ];
+    let legs = |order: [usize; 3]| {
+        order.map(|i| BasketLeg {
+            outcome_index: i as u8,
+            shares: held[i],
+        })
+    };
+
+    // Exiting every position returns the deposits, less rounding, whatever the order
+    let mut forward = market;
+    let total = forward.sell_basket(&legs([0, 1, 2]), NOW).unwrap();
+    assert!(total <= 1_750_000_000);
+    assert!(1_750_000_000 - total < 1_000, "{}", total);
+    assert!(forward.is_empty());
+
+    let mut reverse = market;
+    let reverse_total = reverse.sell_basket(&legs([2, 1, 0]), NOW).unwrap();
+    assert!(reverse_total.abs_diff(total) < 10);
+
+    // The same as selling each leg in turn
+    let mut one_by_one = market;
+    let mut payouts = [0u64; 3];
+    for (i, payout) in payouts.iter_mut().enumerate() {
+        *payout = one_by_one.sell_shares(i as u8, held[i], NOW).unwrap();
+    }
+    assert_eq!(payouts.iter().sum::<u64>(), total);
+
+    // But a leg's payout depends on what was sold before it
+    let mut first = market;
+    let sold_first = first.sell_shares(2, held[2], NOW).unwrap();
+    assert_ne!(sold_first, payouts[2]);
+
+    assert_eq!(
+        market.sell_basket(&[], NOW).unwrap_err(),
+        ErrorCode::InvalidBatchSize.into()
+    );
+    assert_eq!(
+        market
+            .sell_basket(
+                &[legs([0, 1, 2]).as_slice(), &legs([0, 0, 0])[..1]].concat(),
+                NOW
+            )
+            .unwrap_err(),
+        ErrorCode::InvalidBatchSize.into()
+    );
+    // Any failing leg rejects the whole basket
+    let mut too_much = legs([0, 1, 2]);
+    too_much[2].shares += 1;
+    assert_eq!(
+        market.sell_basket(&too_much, NOW).unwrap_err(),
+        ErrorCode::BurnIsMoreThanSupply.into()
+    );
+}
+
+#[test]
+fn test_flip() {
+    for fee_bps in [0u16, 100] {
+        let mut market = Market::new_for_test(3, 1_000_000_000, 0).unwrap();
+        market.fee_config.buy_bps = fee_bps;
+        market.fee_config.sell_bps = fee_bps;
+        let held = market.buy_shares(0, 1_000_000_000, NOW).unwrap();
+        market.buy_shares(1, 300_000_000, NOW).unwrap();
+
+        // The same as selling and buying with the payout in turn
+        let mut in_turn = market;
+        let payout = in_turn.sell_shares(0, held, NOW).unwrap();
+        let expected = in_turn.buy_shares(2, payout, NOW).unwrap();
+
+        let before = market;
+        let target_shares = market.flip(0, 2, held, expected, NOW).unwrap();
+        assert_eq!(target_shares, expected);
+        assert_eq!(market.supplies, in_turn.supplies);
+        assert_eq!(market.reserves, in_turn.reserves);
+        assert_eq!(market.supplies[0], before.supplies[0] - held);
+        assert_eq!(market.supplies[1], before.supplies[1]);
+
+        // Short of the target by one share unit, neither leg happens
+        let mut short = before;
+        assert_eq!(
+            short.flip(0, 2, held, expected + 1, NOW).unwrap_err(),
+            ErrorCode::SlippageExceeded.into()
+        );
+        assert_eq!(short.supplies, before.supplies);
+        assert_eq!(short.reserves, before.reserves);
+        assert_eq!(short.accrued_protocol_fees, before.accrued_protocol_fees);
+    }
+
+    let mut market = Market::new_for_test(2, 1_000_000_000, 0).unwrap();
+    let held = market.buy_shares(0, 1_000_000_000, NOW).unwrap();
+    assert_eq!(
+        market.flip(0, 0, held, 0, NOW).unwrap_err(),
+        ErrorCode::SameOutcome.into()
+    );
+    assert_eq!(
+        market.flip(0, 2, held, 0, NOW).unwrap_err(),
+        ErrorCode::InvalidOutcomeIndex.into()
+    );
+    assert_eq!(
+        market.flip(0, 1, held + 1, 0, NOW).unwrap_err(),
+        ErrorCode::BurnIsMoreThanSupply.into()
+    );
+}
+
+#[test]
+fn test_sell_shares_reserves_never_underflow() {
+    let deposits = |market: &Market| market.reserves.iter().sum::<u64>();
+
+    // Outcome 1 is bought heavily first, so outcome 0 is cheap. Once outcome 1 is sold off,
+    // outcome 0 sells for more than was ever deposited into it.
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+    let shares_1 = market.buy_shares(1, 3_000_000_000, NOW).unwrap();
+    let shares_0 = market.buy_shares(0, 500_000_000, NOW).unwrap();
+    market.sell_shares(1, shares_1, NOW).unwrap();
+
+    let payout = market.sell_shares(0, shares_0, NOW).unwrap();
+    assert!(payout > 500_000_000, "{}", payout);
+    assert_eq!(market.reserves[0], 0);
+
+    // Interleaved trades on both outcomes, selling in pieces
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+    let mut held = [0u64; 2];
+    let mut deposited = 0u64;
+    let mut paid_out = 0u64;
+    for round in 0..20u64 {
+        let outcome = (round % 2) as usize;
+        let amount_in = 100_000_000 + round * 37_000_000;
+        held[outcome] += market.buy_shares(outcome as u8, amount_in, NOW).unwrap();
+        deposited += amount_in;
+
+        let sell = 1 - outcome;
+        if held[sell] > 0 {
+            let shares = held[sell] / 2;
+            paid_out += market.sell_shares(sell as u8, shares, NOW).unwrap();
+            held[sell] -= shares;
+        }
+        assert_eq!(deposits(&market), deposited - paid_out);
+    }
+    for (outcome, shares) in held.iter().enumerate() {
+        paid_out += market.sell_shares(outcome as u8, *shares, NOW).unwrap();
+    }
+
+    // Everything sold: the pool covered every payout, only rounding dust is left in the vault
+    // and the emptied market's ledger is cleared
+    assert_eq!(market.supplies[..2], [0, 0]);
+    assert!(paid_out <= deposited);
+    assert!(deposited - paid_out < 100_000);
+    assert_eq!(deposits(&market), 0);
+}
+
+#[test]
+fn test_dust_is_the_rounding_surplus() {
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 3,
+        ..Default::default()
+    };
+
+    // The vault starts holding exactly cost() at q = 0 (b * ln(3)) from initial liquidity
+    market.initial_liquidity = market.cost().unwrap();
+    let mut vault = market.initial_liquidity;
+    assert_eq!(market.dust(vault).unwrap(), 0);
+
+    // Track what each trade paid against the exact cost delta it caused. A single buy can land a
+    // few lamports either side of its cost delta, but the market keeps the surplus overall.
+    let mut rounding_surplus = 0i64;
+    let mut held = [0u64; 3];
+    for round in 0..12u64 {
+        let outcome = (round % 3) as usize;
+        let cost_before = market.cost().unwrap();
+        let amount_in = 50_000_000 + round * 91_000_000;
+        held[outcome] += market.buy_shares(outcome as u8, amount_in, NOW).unwrap();
+        vault += amount_in;
+        rounding_surplus += amount_in as i64 - (market.cost().unwrap() - cost_before) as i64;
+
+        if round % 4 == 3 {
+            let cost_before = market.cost().unwrap();
+            let shares = held[outcome] / 3;
+            let payout = market.sell_shares(outcome as u8, shares, NOW).unwrap();
+            held[outcome] -= shares;
+            vault -= payout;
+            rounding_surplus += (cost_before - market.cost().unwrap()) as i64 - payout as i64;
+        }
+    }
+
+    let dust = market.dust(vault).unwrap();
+    assert!(dust > 0);
+    assert_eq!(dust as i64, rounding_surplus);
+
+    // After sweeping, the vault is exactly at cost() and still backs the largest outcome
+    vault -= dust;
+    assert_eq!(vault, market.cost().unwrap());
+    assert!(vault >= *market.supplies.iter().max().unwrap());
+    assert_eq!(market.dust(vault).unwrap(), 0);
+
+    // Accrued fees are not dust
+    market.accrued_protocol_fees = 1_000;
+    assert_eq!(market.dust(vault + 1_000).unwrap(), 0);
+}
+
+#[test]
+fn test_dust_on_unseeded_market() {
+    // The default: nothing seeded, the vault only holds what trades paid in
+    let mut market = Market::new_for_test(3, 1_000_000_000, 0).unwrap();
+    let mut vault = 0;
+    for (outcome, amount_in) in [(0, 400_000_000), (0, 250_000_000), (1, 90_000_000)] {
+        market.buy_shares(outcome, amount_in, NOW).unwrap();
+        vault += amount_in;
+    }
+
+    // One-sided, outcome 0's supply is above everything paid in, so its rounding stays put
+    assert!(market.supplies[0] > vault);
+    assert!(market.accrued_rounding_surplus > 0);
+    assert_eq!(market.sweepable_surplus(vault).unwrap(), 0);
+
+    // Bought evenly, the deposits cover every supply and the surplus is swept down to what
+    // trading owes
+    let target = market.supplies[0];
+    for outcome in 1..3u8 {
+        let shares = target - market.supplies[outcome as usize];
+        vault += market
+            .buy_exact_shares(outcome, shares, u64::MAX, NOW)
+            .unwrap();
+    }
+    let surplus = market.sweepable_surplus(vault).unwrap();
+    assert!(surplus > 0);
+    assert_eq!(surplus, market.accrued_rounding_surplus);
+    vault -= surplus;
+    assert!(vault >= market.required_collateral().unwrap());
+    assert!(vault >= target);
+}
+
+#[test]
+fn test_withdrawable_excess() {
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+    market.buy_shares(0, 1_000_000_000, NOW).unwrap();
+
+    // Without initial liquidity the vault only holds the deposit, below cost()
+    let vault = 1_000_000_000;
+    assert!(vault < market.cost().unwrap());
+    assert_eq!(market.withdrawable_excess(vault).unwrap(), 0);
+
+    // A transfer first covers the shortfall, only the rest is excess
+    let cost = market.cost().unwrap();
+    assert_eq!(market.withdrawable_excess(cost + 7_000).unwrap(), 7_000);
+
+    // Fees are not excess
+    market.accrued_protocol_fees = 7_000;
+    assert_eq!(market.withdrawable_excess(cost + 7_000).unwrap(), 0);
+    market.accrued_protocol_fees = 0;
+
+    // A supply is owed in full even where the cost is barely above it
+    let mut skewed = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+    skewed.supplies[0] = 19_900_000_000;
+    let max_supply = skewed.supplies[0];
+    let owed = skewed.cost().unwrap();
+    assert!(owed >= max_supply && owed - max_supply < 10, "{}", owed);
+    assert_eq!(skewed.withdrawable_excess(owed).unwrap(), 0);
+    assert_eq!(skewed.withdrawable_excess(owed + 1).unwrap(), 1);
+
+    // Once resolved to a held outcome, the whole vault is the winners' pool
+    market.resolved = 1;
+    assert_eq!(market.withdrawable_excess(cost + 7_000).unwrap(), 0);
+}
+
+/// The subsidy is never excess or dust while it backs payouts, and the winners redeem it
+#[test]
+fn test_subsidy_pays_out_more_than_cash_in() {
+    let subsidy = 2_000_000_000;
+    let mut market = Market::new_for_test(2, 1_000_000_000, 0).unwrap();
+    market.subsidy = subsidy;
+
+    let winning_shares = market.buy_shares(0, 1_000_000_000, NOW).unwrap();
+    market.buy_shares(1, 400_000_000, NOW).unwrap();
+    let cash_in = 1_400_000_000;
+    let vault = cash_in + subsidy;
+
+    // The same vault without the subsidy lock would hold lamports above cost()
+    let unlocked = Market {
+        subsidy: 0,
+        ..market
+    };
+    assert!(unlocked.dust(vault).unwrap() > 0);
+    assert!(unlocked.withdrawable_excess(vault).unwrap() > 0);
+    assert_eq!(market.dust(vault).unwrap(), 0);
+    assert_eq!(market.withdrawable_excess(vault).unwrap(), 0);
+
+    market.resolve(0, 0).unwrap();
+    assert_eq!(market.winning_supply().unwrap(), winning_shares);
+    let payout = market.redeem(0, winning_shares, vault).unwrap();
+    assert_eq!(payout, vault);
+    assert!(payout > cash_in);
+    assert_eq!(payout - cash_in, subsidy);
+
+    // Refund mode only owes the reserves, so the subsidy is released as excess
+    let mut voided = Market::new_for_test(2, 1_000_000_000, 0).unwrap();
+    voided.subsidy = subsidy;
+    voided.buy_shares(0, 1_000_000_000, NOW).unwrap();
+    voided.resolve(VOID_OUTCOME, 0).unwrap();
+    assert_eq!(voided.locked_subsidy(), 0);
+    let vault = 1_000_000_000 + subsidy;
+    assert_eq!(
+        voided.withdrawable_excess(vault).unwrap(),
+        Market {
+            subsidy: 0,
+            ..voided
+        }
+        .withdrawable_excess(vault)
+        .unwrap()
+    );
+}
+
+#[test]
+fn test_max_loss() {
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+
+    // b * ln(2) = 0.693147180... SOL
+    assert_eq!(market.max_loss().unwrap(), 693_147_180);
+    // Independent of trading, and equal to the cost of a fresh market
+    assert_eq!(market.max_loss().unwrap(), market.cost().unwrap());
+    market.buy_shares(0, 1_000_000_000, NOW).unwrap();
+    assert_eq!(market.max_loss().unwrap(), 693_147_180);
+
+    // b * ln(4) = 1.386294361... SOL, within fp_ln's precision
+    let market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 4,
+        ..Default::default()
+    };
+    let analytic = (1e9 * 4f64.ln()) as u64;
+    assert!(market.max_loss().unwrap().abs_diff(analytic) <= 1);
+
+    // Scales linearly with b
+    let market = Market {
+        scale: 50_000_000_000,
+        num_outcomes: 4,
+        ..Default::default()
+    };
+    let analytic = (50e9 * 4f64.ln()) as u64;
+    assert!(market.max_loss().unwrap().abs_diff(analytic) <= 100);
+}
+
+#[test]
+fn test_health_factor() {
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        resolve_at: 1_000,
+        ..Default::default()
+    };
+
+    // Healthy: the vault holds the b * ln(2) it was seeded with plus every deposit
+    market.initial_liquidity = market.max_loss().unwrap();
+    let mut vault = market.initial_liquidity;
+    market.buy_shares(0, 2_000_000_000, NOW).unwrap();
+    vault += 2_000_000_000;
+
+    let health = market.health_factor(vault, 400).unwrap();
+    assert!(health.collateralization >= 1_000_000_000);
+    assert_eq!(health.seconds_to_resolve, 600);
+    assert_eq!(market.snapshot(vault, 400).unwrap().health, health);
+
+    // Under-collateralized: the vault lost a tenth of its lamports
+    let health = market.health_factor(vault * 9 / 10, 1_500).unwrap();
+    assert!(health.collateralization < 1_000_000_000);
+    assert!(health.collateralization > 890_000_000);
+    assert_eq!(health.seconds_to_resolve, 0);
+
+    // Accrued fees don't count as collateral
+    market.accrued_protocol_fees = vault / 2;
+    assert!(market.health_factor(vault, 0).unwrap().collateralization < 1_000_000_000);
+}
+
+#[test]
+fn test_health_factor_unseeded() {
+    let mut market = Market::new_for_test(2, 1_000_000_000, 1_000).unwrap();
+    assert_eq!(market.required_collateral().unwrap(), 0);
+    assert_eq!(
+        market.health_factor(0, 0).unwrap().collateralization,
+        u64::MAX
+    );
+
+    // Nobody funded b * ln(2), the vault only holds what the trades paid in. Once both outcomes
+    // are bought up to the same supply that covers every share.
+    let mut vault = 1_000_000_000;
+    market.buy_shares(0, vault, NOW).unwrap();
+    let shares = market.supplies[0];
+    vault += market.buy_exact_shares(1, shares, u64::MAX, NOW).unwrap();
+    vault -= market.sell_shares(1, shares / 2, NOW).unwrap();
+    vault += market
+        .buy_exact_shares(1, shares / 2, u64::MAX, NOW)
+        .unwrap();
+    assert!(vault < market.cost().unwrap());
+
+    let required = market.required_collateral().unwrap();
+    assert_eq!(required, shares);
+    let health = market.health_factor(vault, 0).unwrap();
+    assert!(health.collateralization >= 1_000_000_000);
+    assert!(health.collateralization < 1_000_001_000);
+
+    // One-sided, the supply bought is worth more than was paid for it, which the vault only
+    // covers if seeded
+    let mut one_sided = Market::new_for_test(2, 1_000_000_000, 1_000).unwrap();
+    let shares = one_sided.buy_shares(0, 1_000_000_000, NOW).unwrap();
+    assert_eq!(one_sided.required_collateral().unwrap(), shares);
+    let short = one_sided.health_factor(1_000_000_000, 0).unwrap();
+    assert!(short.collateralization < 1_000_000_000);
+    one_sided.initial_liquidity = one_sided.max_loss().unwrap();
+    let seeded = one_sided.health_factor(1_000_000_000 + one_sided.initial_liquidity, 0);
+    assert!(seeded.unwrap().collateralization >= 1_000_000_000);
+}
+
+#[test]
+fn test_price_sum_residual() {
+    let b = 1_000_000_000u64;
+    let skewed_states: [&[u64]; 6] = [
+        &[0, 0],
+        &[5 * b, 0],
+        &[19 * b, 0],
+        &[b / 3, 2 * b, 7 * b],
+        &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+        &[
+            11 * b,
+            b,
+            0,
+            3 * b,
+            17 * b,
+            b / 7,
+            4 * b,
+            9 * b,
+            2,
+            6 * b,
+            b / 2,
+            13 * b,
+            0,
+            8 * b,
+            5 * b,
+            15 * b,
+        ],
+    ];
+
+    for supplies in skewed_states {
+        let n = supplies.len();
+        let mut market = Market {
+            scale: b,
+            num_outcomes: n as u8,
+            ..Default::default()
+        };
+        market.supplies[..n].copy_from_slice(supplies);
+
+        let residual = market.price_sum_residual().unwrap();
+        assert!(
+            residual.unsigned_abs() <= price_sum_tolerance(n as u8) && residual <= 0,
+            "{:?}: {}",
+            supplies,
+            residual
+        );
+    }
+
+    // A corrupted supply far past the fp_exp domain can't be priced, rather than drifting silently
+    let mut market = Market {
+        scale: b,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+    market.supplies[0] = u64::MAX;
+    assert_eq!(
+        market.price_sum_residual().unwrap_err(),
+        ErrorCode::SupplySaturated.into()
+    );
+}
+
+#[test]
+fn test_lazy_outcome_mints() {
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 16,
+        resolve_at: 100,
+        ..Default::default()
+    };
+    assert_eq!(market.all_mints_mask(), u16::MAX);
+
+    for index in 0..15 {
+        market.mark_mint_initialized(index).unwrap();
+        assert!(!market.mints_ready());
+        assert_eq!(
+            market.is_tradable(0).unwrap_err(),
+            ErrorCode::MintsNotReady.into()
+        );
+    }
+    assert_eq!(
+        market.mark_mint_initialized(3).unwrap_err(),
+        ErrorCode::MintAlreadyInitialized.into()
+    );
+    assert_eq!(
+        market.mark_mint_initialized(16).unwrap_err(),
+        ErrorCode::InvalidOutcomeIndex.into()
+    );
+
+    // The last mint opens trading
+    market.mark_mint_initialized(15).unwrap();
+    assert!(market.mints_ready());
+    market.is_tradable(0).unwrap();
+
+    let binary = Market {
+        num_outcomes: 2,
+        ..Default::default()
+    };
+    assert_eq!(binary.all_mints_mask(), 0b11);
+}
+
+#[test]
+fn test_high_precision_rounding_error() {
+    // The same trade sequence on a D9 and a D18 market, measuring how far each buy's price
+    // (amount_in) is from the cost delta it caused, i.e. the lamports lost to rounding
+    let trades: [(usize, u64); 6] = [
+        (0, 300_000_000),
+        (1, 1_700_000_000),
+        (0, 50_000_000),
+        (1, 2_500_000_000),
+        (0, 4_000_000_000),
+        (1, 123_456_789),
+    ];
+    let rounding_error = |high_precision: u8| {
+        let mut market = Market {
+            scale: 1_000_000_000,
+            num_outcomes: 2,
+            high_precision,
+            ..Default::default()
+        };
+        let mut total = 0;
+        for (outcome, amount_in) in trades {
+            let cost_before = market.cost().unwrap();
+            market.buy_shares(outcome as u8, amount_in, NOW).unwrap();
+            let cost_delta = market.cost().unwrap() - cost_before;
+            total += (amount_in as i64 - cost_delta as i64).abs();
+        }
+        (market, total)
+    };
+
+    let (d9, d9_error) = rounding_error(0);
+    let (d18, d18_error) = rounding_error(1);
+    assert_eq!(d9.fp_one(), 1_000_000_000);
+    assert_eq!(d18.fp_one(), 1_000_000_000_000_000_000);
+    assert!(d18_error < d9_error, "d18 {} d9 {}", d18_error, d9_error);
+    // Within a couple of lamports per trade, from flooring shares and cost to integers
+    assert!(d18_error <= 2 * trades.len() as i64, "{}", d18_error);
+
+    // Against the exact LMSR cost, b * ln(Σ exp(q_i / b))
+    let exact_cost = |market: &Market| {
+        let b = market.scale as f64;
+        let sum: f64 = market.supplies[..2]
+            .iter()
+            .map(|&q| (q as f64 / b).exp())
+            .sum();
+        b * sum.ln()
+    };
+    // Both land on the floor of the exact cost here, only the trades above tell them apart
+    let d9_drift = (d9.cost().unwrap() as f64 - exact_cost(&d9)).abs();
+    let d18_drift = (d18.cost().unwrap() as f64 - exact_cost(&d18)).abs();
+    assert!(d9_drift <= 1.0, "{}", d9_drift);
+    assert!(d18_drift <= 1.0, "{}", d18_drift);
+
+    // Prices stay on the 1e9 scale either way
+    let residual = d18.price_sum_residual().unwrap();
+    assert!(residual <= 0 && residual.unsigned_abs() <= price_sum_tolerance(2));
+}
+
+#[test]
+fn test_reentrancy_guard() {
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+
+    market.enter().unwrap();
+    assert_eq!(market.in_progress, 1);
+    // A nested instruction, e.g. from a transfer hook CPI, can't start while the first is running
+    assert_eq!(
+        market.enter().unwrap_err(),
+        ErrorCode::ReentrancyDetected.into()
+    );
+
+    market.exit();
+    assert_eq!(market.in_progress, 0);
+    market.enter().unwrap();
+}
+
+#[test]
+fn test_twap_between() {
+    const OPEN: i64 = 1_750_000_000;
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        price_cumulative_at: OPEN,
+        ..Default::default()
+    };
+
+    // A client captures the start of the window 100s after open, before any trade
+    let start = (
+        market.price_cumulative_now(0, OPEN + 100).unwrap() as u128,
+        OPEN + 100,
+    );
+    assert_eq!(start.0, 100 * 500_000_000);
+
+    // Even odds until a buy 400s after open, then the new price for another 600s
+    market.accumulate_prices(OPEN + 400).unwrap();
+    market.buy_shares(0, 1_000_000_000, NOW).unwrap();
+    let price_after = market.price(0).unwrap() as u128;
+    let end = (
+        market.price_cumulative_now(0, OPEN + 1_000).unwrap() as u128,
+        OPEN + 1_000,
+    );
+
+    let twap = market.twap_between(start, end, 0).unwrap() as u128;
+    assert_eq!(twap, (300 * 500_000_000 + 600 * price_after) / 900);
+
+    // Binary TWAPs still sum to ~1.0
+    let start_1 = (100 * 500_000_000, OPEN + 100);
+    let end_1 = (
+        market.price_cumulative_now(1, OPEN + 1_000).unwrap() as u128,
+        OPEN + 1_000,
+    );
+    let twap_1 = market.twap_between(start_1, end_1, 1).unwrap() as u128;
+    assert!(1_000_000_000 - (twap + twap_1) <= 2);
+
+    // A window across the accumulator wrapping around u64::MAX
+    let wrapped = market
+        .twap_between((u64::MAX as u128 - 99, 0), (900, 10), 0)
+        .unwrap();
+    assert_eq!(wrapped, 100);
+
+    assert_eq!(
+        market.twap_between(end, start, 0).unwrap_err(),
+        ErrorCode::InvalidTwapWindow.into()
+    );
+    // A span too wide for an i64 is rejected rather than overflowing
+    assert_eq!(
+        market.twap_between((0, i64::MIN), (900, 1), 0).unwrap_err(),
+        ErrorCode::InvalidTwapWindow.into()
+    );
+    assert_eq!(
+        market.twap_between(start, end, 2).unwrap_err(),
+        ErrorCode::InvalidOutcomeIndex.into()
+    );
+}
+
+#[test]
+fn test_sell_everything_back_reopens_fresh() {
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 3,
+        ..Default::default()
+    };
+    let initial_cost = market.cost().unwrap();
+    market.initial_liquidity = initial_cost;
+    assert!(market.is_empty());
+
+    let mut held = [0u64; 3];
+    for (outcome, amount_in) in [
+        (0, 700_000_000),
+        (1, 250_000_000),
+        (0, 90_000_000),
+        (2, 1_300_000_000),
+    ] {
+        held[outcome] += market.buy_shares(outcome as u8, amount_in, NOW).unwrap();
+    }
+    assert!(!market.is_empty());
+
+    let mut vault: u64 = 2_340_000_000;
+    for (outcome, shares) in held.into_iter().enumerate() {
+        vault -= market.sell_shares(outcome as u8, shares, NOW).unwrap();
+    }
+
+    // Exactly the initial state: same cost, equal prices, nothing left on the ledger
+    assert!(market.is_empty());
+    assert_eq!(market.cost().unwrap(), initial_cost);
+    assert_eq!(market.price(0).unwrap(), 333_333_334);
+    for i in 1..3 {
+        assert_eq!(market.price(i).unwrap(), 333_333_333);
+    }
+    assert_eq!(market.reserves, [0; 16]);
+
+    // The rounding the market kept is sweepable dust and nothing is owed
+    assert!(vault > 0);
+    assert_eq!(market.dust(initial_cost + vault).unwrap(), vault);
+    assert_eq!(
+        market.withdrawable_excess(initial_cost + vault).unwrap(),
+        vault
+    );
+
+    // And it trades again like a fresh market
+    let mut fresh = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 3,
+        ..Default::default()
+    };
+    assert_eq!(
+        market.buy_shares(1, 500_000_000, NOW).unwrap(),
+        fresh.buy_shares(1, 500_000_000, NOW).unwrap()
+    );
+}
+
+#[test]
+fn test_cost_to_balance() {
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 3,
+        ..Default::default()
+    };
+    assert_eq!(market.cost_to_balance().unwrap(), 0);
+
+    // Equal supplies are uniform too, however large
+    market.supplies[..3].fill(4_000_000_000);
+    assert_eq!(market.cost_to_balance().unwrap(), 0);
+
+    // Heavily skewed toward outcome 0
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 3,
+        ..Default::default()
+    };
+    let shares = market.buy_shares(0, 5_000_000_000, NOW).unwrap();
+    let cost = market.cost_to_balance().unwrap();
+    assert!(cost > 0);
+
+    // Spending it buys the other outcomes back to uniform. The lamport costs are floored
+    // separately, the delta is rounded up once.
+    let cost_before = market.cost().unwrap();
+    market.supplies[1] = shares;
+    market.supplies[2] = shares;
+    assert!(cost.abs_diff(market.cost().unwrap() - cost_before) <= 1);
+    for i in 0..3 {
+        assert_eq!(market.price(i).unwrap(), 333_333_333);
+    }
+}
+
+#[test]
+fn test_marginal_cost_equals_price() {
+    // A few units of 1e9 from the price floor and the two logs of the finite difference
+    const TOLERANCE: i64 = 4;
+    let b = 1_000_000_000u64;
+    let states: [&[u64]; 5] = [
+        &[0, 0],
+        &[b, 0],
+        &[b, 4 * b],
+        &[b / 3, 2 * b, 7 * b / 2],
+        &[0, 1, b / 2, 3 * b, b, 5, 2 * b, b / 9],
+    ];
+
+    for supplies in states {
+        for high_precision in [0, 1] {
+            let n = supplies.len();
+            let mut market = Market {
+                scale: b,
+                num_outcomes: n as u8,
+                high_precision,
+                ..Default::default()
+            };
+            market.supplies[..n].copy_from_slice(supplies);
+
+            for i in 0..n {
+                let price = market.price(i as u8).unwrap() as i64;
+                let marginal = market.marginal_cost(i as u8).unwrap() as i64;
+                assert!(
+                    (price - marginal).abs() <= TOLERANCE,
+                    "{:?} outcome {}: price {} marginal cost {}",
+                    supplies,
+                    i,
+                    price,
+                    marginal
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_price_uniform_fast_path() {
+    for (num_outcomes, remainder) in [(3u8, 1), (7, 6)] {
+        let mut market = Market {
+            scale: 1_000_000_000,
+            num_outcomes,
+            ..Default::default()
+        };
+        let prices: Vec<u64> = (0..num_outcomes)
+            .map(|i| market.price(i).unwrap())
+            .collect();
+
+        let share = 1_000_000_000 / num_outcomes as u64;
+        assert_eq!(prices[0], share + remainder);
+        assert!(prices[1..].iter().all(|&p| p == share));
+        assert_eq!(prices.iter().sum::<u64>(), 1_000_000_000);
+
+        // Supplies past num_outcomes are not part of the market
+        market.supplies[num_outcomes as usize] = 1_000_000_000;
+        assert_eq!(market.price(0).unwrap(), share + remainder);
+
+        // Any active supply goes back through the exp path
+        market.supplies[1] = 1;
+        assert!(market.price(0).unwrap() < share + remainder);
+    }
+}
+
+#[test]
+fn test_buy_and_sell_fees() {
+    let no_fee = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+    let mut market = Market {
+        fee_config: FeeConfig {
+            buy_bps: 50,
+            sell_bps: 100,
+            ..Default::default()
+        },
+        ..no_fee
+    };
+
+    // 0.5% of the buy is set aside, the rest buys what it would without fees
+    let shares = market.buy_shares(0, 1_000_000_000, NOW).unwrap();
+    assert_eq!(market.accrued_protocol_fees, 5_000_000);
+    assert_eq!(market.reserves[0], 995_000_000);
+    let mut expected = no_fee;
+    assert_eq!(shares, expected.buy_shares(0, 995_000_000, NOW).unwrap());
+    assert_eq!(market.supplies, expected.supplies);
+
+    // 1% of the sell payout stays in the vault
+    let gross = expected.sell_shares(0, shares / 2, NOW).unwrap();
+    let payout = market.sell_shares(0, shares / 2, NOW).unwrap();
+    assert_eq!(payout, gross - gross / 100);
+    assert_eq!(market.accrued_protocol_fees, 5_000_000 + gross / 100);
+    assert_eq!(market.reserves, expected.reserves);
+}
+
+/// Trade fees and rounding surplus accrue in their own counters and drain independently.
+#[test]
+fn test_fees_and_rounding_surplus_accrue_separately() {
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 3,
+        fee_config: FeeConfig {
+            buy_bps: 30,
+            sell_bps: 20,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    market.initial_liquidity = market.cost().unwrap();
+    let mut vault = market.initial_liquidity;
+
+    let (mut fees, mut rounding) = (0u64, 0i64);
+    let mut held = [0u64; 3];
+    for round in 0..9u64 {
+        let outcome = (round % 3) as usize;
+        let amount_in = 70_000_000 + round * 113_000_000;
+        let fee = market.fee_config.buy_fee(amount_in);
+        let cost_before = market.cost_precise().unwrap();
+        held[outcome] += market.buy_shares(outcome as u8, amount_in, NOW).unwrap();
+        vault += amount_in;
+        fees += fee;
+        let cost_delta = (market.cost_precise().unwrap() - cost_before).div_ceil(1_000_000_000);
+        rounding += (amount_in - fee) as i64 - cost_delta as i64;
+        assert_eq!(market.accrued_protocol_fees, fees);
+
+        // A sell pays the cost delta rounded down and keeps less than a lamport, which isn't
+        // recorded
+        if round % 3 == 2 {
+            let cost_before = market.cost_precise().unwrap();
+            let shares = held[outcome] / 2;
+            let payout = market.sell_shares(outcome as u8, shares, NOW).unwrap();
+            held[outcome] -= shares;
+            vault -= payout;
+            let gross = ((cost_before - market.cost_precise().unwrap()) / 1_000_000_000) as u64;
+            fees += gross - payout;
+            assert_eq!(market.accrued_protocol_fees, fees);
+        }
+    }
+
+    // The surplus counter only clamps at zero, which this sequence never reaches. The dust also
+    // holds the sub-lamport remainders, at most a lamport per trade.
+    assert!(rounding > 0);
+    assert_eq!(market.accrued_rounding_surplus as i64, rounding);
+    let dust = market.dust(vault).unwrap();
+    assert!(dust >= market.accrued_rounding_surplus);
+    assert!(dust - market.accrued_rounding_surplus <= 12);
+
+    // Withdrawing the fees leaves the surplus, and what can be swept, unchanged
+    let surplus = market.accrued_rounding_surplus;
+    vault -= market.accrued_protocol_fees;
+    market.accrued_protocol_fees = 0;
+    assert_eq!(market.accrued_rounding_surplus, surplus);
+    assert_eq!(market.sweepable_surplus(vault).unwrap(), surplus);
+
+    // Sweeping the surplus leaves the vault at most the remainders above what it must hold, and
+    // SOL sent to the vault isn't surplus
+    vault -= surplus;
+    market.accrued_rounding_surplus = 0;
+    assert!(vault - market.required_collateral().unwrap() <= 12);
+    assert_eq!(market.sweepable_surplus(vault + 5_000).unwrap(), 0);
+}
+
+/// `p (1 - p) / b` peaks at p = 0.5, vanishes at the extremes and matches the price moved by a
+/// small trade
+#[test]
+fn test_price_sensitivity() {
+    let b = 1_000_000_000u64;
+    let mut market = Market::new_for_test(2, b, 0).unwrap();
+
+    // 0.25 * 1e18 / b at even odds
+    let peak = market.price_sensitivity(0).unwrap();
+    assert_eq!(peak, 250_000_000);
+
+    let mut previous = peak;
+    for supply in [1, 2, 5, 10, 19].map(|k| k * b) {
+        market.supplies[0] = supply;
+        let sensitivity = market.price_sensitivity(0).unwrap();
+        assert!(sensitivity < previous);
+        // Both outcomes of a binary market move equally, up to the flooring of their prices
+        assert!(sensitivity.abs_diff(market.price_sensitivity(1).unwrap()) <= 1);
+
+        // Logistic derivative, against a finite difference of 1/1000 of b shares
+        let p = market.price(0).unwrap() as f64 / 1e9;
+        let expected = p * (1.0 - p) * 1e18 / b as f64;
+        assert!((sensitivity as f64 - expected).abs() <= expected * 1e-6 + 2.0);
+        let step = b / 1_000;
+        let mut after = market;
+        after.supplies[0] += step;
+        let moved = (after.price(0).unwrap() - market.price(0).unwrap()) as f64 * 1e9 / step as f64;
+        assert!(
+            (moved - expected).abs() <= expected * 0.01 + 1e6,
+            "{moved} {expected}"
+        );
+        previous = sensitivity;
+    }
+    // At 19b the favorite is priced at about 1 - 6e-9
+    assert!(previous < 10);
+
+    // A deeper market is proportionally more stable
+    let deep = Market::new_for_test(2, 10 * b, 0).unwrap();
+    assert_eq!(deep.price_sensitivity(0).unwrap(), peak / 10);
+}
+
+#[test]
+fn test_liquidity_depth() {
+    let market = |scale| Market {
+        scale,
+        num_outcomes: 3,
+        ..Default::default()
+    };
+
+    // A higher `scale` takes more lamports to move, in proportion
+    let mut shallow = market(1_000_000_000);
+    let mut deep = market(5_000_000_000);
+    let (shallow_depth, deep_depth) = (
+        shallow.liquidity_depth(NOW).unwrap(),
+        deep.liquidity_depth(NOW).unwrap(),
+    );
+    assert!(deep_depth > shallow_depth);
+    assert!(deep_depth.abs_diff(5 * shallow_depth) <= 5);
+
+    // It is measured on the favorite, wherever the flow went
+    shallow.buy_shares(2, 400_000_000, NOW).unwrap();
+    deep.buy_shares(2, 2_000_000_000, NOW).unwrap();
+    assert_eq!(
+        shallow.liquidity_depth(NOW).unwrap(),
+        shallow.depth(2, LIQUIDITY_DEPTH_IMPACT_BPS, NOW).unwrap()
+    );
+    assert!(deep.liquidity_depth(NOW).unwrap() > shallow.liquidity_depth(NOW).unwrap());
+
+    assert_eq!(
+        market(0).liquidity_depth(NOW).unwrap_err(),
+        ErrorCode::LiquidityParameterIsZero.into()
+    );
+}
+
+#[test]
+fn test_depth() {
+    let market = |scale| Market {
+        scale,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+
+    // Buying exactly the depth moves the price by the impact
+    let mut shallow = market(1_000_000_000);
+    let depth = shallow.depth(0, 100, NOW).unwrap();
+    let start = shallow.price(0).unwrap();
+    assert_eq!(start, 500_000_000);
+    let after = shallow
+        .price_after_hypothetical_buy(0, depth, 0, NOW)
+        .unwrap();
+    assert!(
+        after.abs_diff(505_000_000) <= 1,
+        "price after depth {after}"
+    );
+
+    // b ln(0.5 / 0.495)
+    let exact = 1e9 * (0.5f64 / 0.495).ln();
+    assert!((depth as f64 - exact).abs() <= 2.0, "{depth} vs {exact}");
+
+    // Depth is linear in b
+    let deep = market(10_000_000_000).depth(0, 100, NOW).unwrap();
+    assert!(deep.abs_diff(10 * depth) <= 10, "{deep} vs {}", 10 * depth);
+
+    // Each lamport moves the price by (1 - p) / b, so a favored outcome takes more lamports
+    // to move by the same relative impact, until the target price is past 1.0
+    let mut last = depth;
+    for _ in 0..3 {
+        shallow.buy_shares(0, 500_000_000, NOW).unwrap();
+        let favored = shallow.depth(0, 100, NOW).unwrap();
+        assert!(favored > last, "{favored} <= {last}");
+        last = favored;
+    }
+    let mut unreachable = market(1_000_000_000);
+    unreachable.buy_shares(0, 5_000_000_000, NOW).unwrap();
+    assert!(unreachable.price(0).unwrap() > 990_099_010);
+    assert_eq!(
+        unreachable.depth(0, 100, NOW).unwrap(),
+        unreachable.max_buy(0, NOW).unwrap()
+    );
+
+    // The buy fee comes on top, the same net amount still reaches the price
+    let with_fee = Market {
+        fee_config: FeeConfig {
+            buy_bps: 100,
+            ..Default::default()
+        },
+        ..market(1_000_000_000)
+    };
+    let gross = with_fee.depth(0, 100, NOW).unwrap();
+    assert_eq!(gross - with_fee.fee_config.buy_fee(gross), depth);
+}
+
+#[test]
+fn test_buy_shares_within_impact() {
+    let fresh = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+
+    // 0.5 SOL takes outcome 0 from 0.50 to about 0.70, a 39% impact, and is rejected whole
+    let mut market = fresh;
+    assert_eq!(
+        market
+            .buy_shares_within_impact(0, 500_000_000, 2_000, NOW)
+            .unwrap_err(),
+        ErrorCode::PriceImpactTooHigh.into()
+    );
+    assert_eq!(market.supplies, fresh.supplies);
+    assert_eq!(market.reserves, fresh.reserves);
+
+    // A smaller buy under the cap mints exactly what an uncapped buy would
+    let mut uncapped = fresh;
+    let shares = market
+        .buy_shares_within_impact(0, 100_000_000, 2_000, NOW)
+        .unwrap();
+    assert_eq!(shares, uncapped.buy_shares(0, 100_000_000, NOW).unwrap());
+    assert!(market.price(0).unwrap() <= 600_000_000);
+
+    // 0 disables the cap
+    let mut market = fresh;
+    market
+        .buy_shares_within_impact(0, 5_000_000_000, 0, NOW)
+        .unwrap();
+}
+
+/// `cost_of_buy` of the shares a buy minted lands closer to the lamports paid than the difference
+/// of the floored `cost()`s
+#[test]
+fn test_cost_of_buy_uses_precise_delta() {
+    for high_precision in [0u8, 1] {
+        let (mut precise_error, mut truncated_error) = (0u64, 0u64);
+        let mut market = Market::new_for_test(3, 1_000_000_000, 0).unwrap();
+        market.high_precision = high_precision;
+
+        for i in 0..60u64 {
+            let outcome = (i % 3) as u8;
+            let amount_in = 10_000_000 + i * 7_654_321;
+            let before = market;
+            let shares = market.buy_shares(outcome, amount_in, NOW).unwrap();
+
+            let precise = before.cost_of_buy(outcome, shares).unwrap();
+            let truncated = market.cost().unwrap() - before.cost().unwrap();
+            assert!(precise.abs_diff(amount_in) <= truncated.abs_diff(amount_in) + 1);
+            precise_error += precise.abs_diff(amount_in);
+            truncated_error += truncated.abs_diff(amount_in);
+        }
+        // At 1e9 with b = 1 SOL the ln carries no digits below a lamport, so the two agree
+        if high_precision == 1 {
+            assert!(precise_error < truncated_error);
+        } else {
+            assert!(precise_error <= truncated_error);
+        }
+    }
+}
+
+/// The binary fast path prices and costs within 1 unit of the general N-outcome path
+#[test]
+fn test_binary_fast_path_matches_general() {
+    for high_precision in [0u8, 1] {
+        let mut market = Market::new_for_test(2, 1_000_000_000, 0).unwrap();
+        market.high_precision = high_precision;
+        assert!(market.is_binary());
+
+        for (q0, q1) in [
+            (1, 0),
+            (500_000_000, 0),
+            (0, 3_000_000_000),
+            (7_654_321_000, 7_654_321_000),
+            (12_000_000_000, 2_500_000_000),
+        ] {
+            market.supplies[0] = q0;
+            market.supplies[1] = q1;
+            for outcome in 0..2 {
+                let fast = market.price(outcome).unwrap();
+                let general = market.price_general(outcome).unwrap();
+                assert!(
+                    fast.abs_diff(general) <= 1,
+                    "{q0} {q1}: {fast} vs {general}"
+                );
+            }
+            let fast = market.cost_precise().unwrap();
+            let general = market.cost_precise_general().unwrap();
+            assert!(
+                fast.abs_diff(general) <= 1,
+                "{q0} {q1}: {fast} vs {general}"
+            );
+        }
+    }
+
+    assert!(!Market::new_for_test(3, 1_000_000_000, 0)
+        .unwrap()
+        .is_binary());
+}
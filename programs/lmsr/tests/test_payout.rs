@@ -0,0 +1,49 @@
+use common::errors::ErrorCode;
+use common::utils::{break_even_probability, PayoutMode};
+
+#[test]
+fn test_break_even_probability_unit_payout() {
+    // Bought at an average of 0.62 SOL per share, each winning share pays 1 SOL:
+    // EV = p * 1.00 - 0.62, so the position breaks even at p = 62%
+    assert_eq!(
+        break_even_probability(620_000_000, PayoutMode::Unit).unwrap(),
+        620_000_000
+    );
+    assert_eq!(break_even_probability(0, PayoutMode::Unit).unwrap(), 0);
+}
+
+#[test]
+fn test_break_even_probability_proportional_payout() {
+    // 10 SOL pot split between 8 winning shares pays 1.25 SOL per share.
+    // Bought at 0.50 SOL per share: EV = p * 1.25 - 0.50, breaking even at p = 40%
+    let payout_mode = PayoutMode::Proportional {
+        pot: 10_000_000_000,
+        winning_supply: 8_000_000_000,
+    };
+    assert_eq!(payout_mode.payout_per_share().unwrap(), 1_250_000_000);
+    assert_eq!(
+        break_even_probability(500_000_000, payout_mode).unwrap(),
+        400_000_000
+    );
+
+    // 3 SOL pot split between 4 winning shares pays 0.75 SOL per share.
+    // Bought at 0.90 SOL per share the position loses even if it wins: p = 120%
+    let payout_mode = PayoutMode::Proportional {
+        pot: 3_000_000_000,
+        winning_supply: 4_000_000_000,
+    };
+    assert_eq!(
+        break_even_probability(900_000_000, payout_mode).unwrap(),
+        1_200_000_000
+    );
+
+    // Nobody holds the winning outcome: no payout rate to compare against
+    let payout_mode = PayoutMode::Proportional {
+        pot: 3_000_000_000,
+        winning_supply: 0,
+    };
+    assert_eq!(
+        break_even_probability(900_000_000, payout_mode).unwrap_err(),
+        ErrorCode::SupplyIsZero.into()
+    );
+}
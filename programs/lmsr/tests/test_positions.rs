@@ -0,0 +1,437 @@
+mod utils;
+
+use common::errors::ErrorCode;
+use litesvm::types::TransactionResult;
+use lmsr::events::PositionRedeemed;
+use lmsr::state::{BettorPosition, Market};
+use lmsr::types::{BasketLeg, InitMarketParams};
+use solana_sdk::signer::Signer;
+use utils::*;
+
+fn returned_u64(result: TransactionResult) -> u64 {
+    u64::from_le_bytes(result.unwrap().return_data.data.try_into().unwrap())
+}
+
+#[test]
+fn test_bettor_position_credit_and_debit() {
+    let mut position = BettorPosition::default();
+    position.credit(3, 700, 350).unwrap();
+    position.credit(3, 300, 250).unwrap();
+    assert_eq!(position.shares[3], 1_000);
+    assert_eq!(position.cost_basis[3], 600);
+
+    assert_eq!(
+        position.debit(3, 1_001).unwrap_err(),
+        ErrorCode::InsufficientPositionShares.into()
+    );
+    // The basis comes off pro rata, and all of it with the last share
+    assert_eq!(position.debit(3, 400).unwrap(), 240);
+    assert_eq!(position.shares[3], 600);
+    assert_eq!(position.cost_basis[3], 360);
+    assert_eq!(position.debit(3, 600).unwrap(), 360);
+    assert_eq!(position.cost_basis[3], 0);
+
+    assert_eq!(
+        position.credit(16, 1, 1).unwrap_err(),
+        ErrorCode::InvalidOutcomeIndex.into()
+    );
+    position.shares[0] = u64::MAX;
+    assert_eq!(
+        position.credit(0, 1, 1).unwrap_err(),
+        ErrorCode::MathOverflow.into()
+    );
+}
+
+#[test]
+fn test_unrealized_pnl_follows_price() {
+    let mut market = Market {
+        scale: 1_000_000_000,
+        num_outcomes: 2,
+        ..Default::default()
+    };
+    let mut position = BettorPosition::default();
+
+    let amount_in = 1_000_000_000;
+    let shares = market.buy_shares(0, amount_in, START_TIMESTAMP).unwrap();
+    position.credit(0, shares, amount_in).unwrap();
+
+    // Marked at the price right after the buy, the position is about even: the buy paid the
+    // average price on its way up, which is below the marginal price it ends at
+    let pnl_after_buy = position
+        .unrealized_pnl(0, market.price(0).unwrap())
+        .unwrap();
+    assert!(pnl_after_buy >= 0);
+    assert!(pnl_after_buy < amount_in as i64 / 2);
+
+    // Others buying the same outcome push the price up, and the position with it
+    market
+        .buy_shares(0, 2_000_000_000, START_TIMESTAMP)
+        .unwrap();
+    let pnl = position
+        .unrealized_pnl(0, market.price(0).unwrap())
+        .unwrap();
+    assert!(pnl > pnl_after_buy);
+    assert!(pnl > 0);
+
+    // Buying the other outcome pushes it down, to a loss
+    market
+        .buy_shares(1, 10_000_000_000, START_TIMESTAMP)
+        .unwrap();
+    assert!(
+        position
+            .unrealized_pnl(0, market.price(0).unwrap())
+            .unwrap()
+            < 0
+    );
+
+    assert_eq!(
+        position.unrealized_pnl(16, 0).unwrap_err(),
+        ErrorCode::InvalidOutcomeIndex.into()
+    );
+}
+
+/// Winners redeem at the same rate per share, so the one who bought cheaper realizes more
+#[test]
+fn test_realized_pnl_on_redemption() {
+    let mut market = Market::new_for_test(2, 1_000_000_000, 0).unwrap();
+    let (mut early, mut late) = (BettorPosition::default(), BettorPosition::default());
+
+    let amount_in = 1_000_000_000;
+    let early_shares = market.buy_shares(0, amount_in, START_TIMESTAMP).unwrap();
+    early.credit(0, early_shares, amount_in).unwrap();
+    let late_shares = market.buy_shares(0, amount_in, START_TIMESTAMP).unwrap();
+    late.credit(0, late_shares, amount_in).unwrap();
+    market.buy_shares(1, amount_in, START_TIMESTAMP).unwrap();
+    assert!(late_shares < early_shares);
+
+    market.resolve(0, 0).unwrap();
+    let mut vault = 3 * amount_in;
+    let mut realize = |position: &mut BettorPosition, shares: u64| {
+        let payout = market.redeem(0, shares, vault).unwrap();
+        vault -= payout;
+        let cost_basis = position.debit(0, shares).unwrap();
+        assert_eq!(cost_basis, amount_in);
+        BettorPosition::realized_pnl(payout, cost_basis).unwrap()
+    };
+    let early_pnl = realize(&mut early, early_shares);
+    let late_pnl = realize(&mut late, late_shares);
+
+    assert!(late_pnl > 0);
+    assert!(early_pnl > late_pnl);
+    // The losing side's lamports are split between them
+    assert!((early_pnl + late_pnl).abs_diff(amount_in as i64) < 10);
+
+    assert_eq!(BettorPosition::realized_pnl(100, 250).unwrap(), -150);
+    assert_eq!(
+        BettorPosition::realized_pnl(u64::MAX, 0).unwrap_err(),
+        ErrorCode::MathOverflow.into()
+    );
+}
+
+#[test]
+fn test_redeem_position_emits_realized_pnl() {
+    let (mut svm, admin) = setup();
+    let params = InitMarketParams {
+        tokenized: false,
+        ..market_params("pnl")
+    };
+    let market = market_accounts(&params.label, params.num_outcomes).market;
+    let resolve_at = params.resolve_at;
+    send_ix(
+        &mut svm,
+        init_market_without_mints_ix(&admin.pubkey(), params),
+        &admin,
+    )
+    .unwrap();
+
+    let (early, late) = (funded_keypair(&mut svm), funded_keypair(&mut svm));
+    for (buyer, outcome) in [(&early, 0), (&late, 0), (&admin, 1)] {
+        send_ix(
+            &mut svm,
+            buy_position_ix(&buyer.pubkey(), &market, outcome, 1_000_000_000, 0),
+            buyer,
+        )
+        .unwrap();
+    }
+    warp_to(&mut svm, resolve_at);
+    send_ix(
+        &mut svm,
+        resolve_market_ix(&admin.pubkey(), &market, 0),
+        &admin,
+    )
+    .unwrap();
+
+    let redeemed: Vec<PositionRedeemed> = [&early, &late]
+        .iter()
+        .map(|holder| {
+            let shares = load_position(&svm, &market, &holder.pubkey()).shares[0];
+            let result = send_ix(
+                &mut svm,
+                redeem_position_ix(&holder.pubkey(), &market, 0, shares),
+                holder,
+            );
+            let mut emitted = events::<PositionRedeemed>(&result);
+            assert_eq!(emitted.len(), 1);
+            emitted.remove(0)
+        })
+        .collect();
+
+    for (event, holder) in redeemed.iter().zip([&early, &late]) {
+        assert_eq!(event.market, market);
+        assert_eq!(event.holder, holder.pubkey());
+        assert_eq!(event.cost_basis, 1_000_000_000);
+        assert_eq!(event.pnl, event.amount_out as i64 - 1_000_000_000);
+    }
+    assert!(redeemed[1].pnl > 0);
+    assert!(redeemed[0].pnl > redeemed[1].pnl);
+}
+
+/// The same trades in a tokenized and an untokenized market pay and cost exactly the same.
+#[test]
+fn test_tokenized_and_position_markets_trade_alike() {
+    let (mut svm, admin) = setup();
+    let tokens = market_params("tokenized");
+    let positions = InitMarketParams {
+        tokenized: false,
+        ..market_params("untokenized")
+    };
+    let tokens_market = market_accounts(&tokens.label, tokens.num_outcomes).market;
+    let positions_market = market_accounts(&positions.label, positions.num_outcomes).market;
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), tokens), &admin).unwrap();
+    send_ix(
+        &mut svm,
+        init_market_without_mints_ix(&admin.pubkey(), positions),
+        &admin,
+    )
+    .unwrap();
+
+    // Untokenized markets trade right away, without any mint
+    let market = load_market(&svm, &positions_market);
+    assert_eq!(market.tokenized, 0);
+    assert!(market.mints_ready());
+    assert!(svm
+        .get_account(&outcome_mint(&positions_market, 0))
+        .is_none());
+
+    let buyer = funded_keypair(&mut svm);
+    let mut held = [0u64; 2];
+    for (outcome, amount_in) in [(0u8, 1_000_000_000), (1, 400_000_000), (0, 250_000_000)] {
+        let minted = returned_u64(send_ix(
+            &mut svm,
+            buy_shares_ix(&buyer.pubkey(), &tokens_market, outcome, amount_in, 0),
+            &buyer,
+        ));
+        let recorded = returned_u64(send_ix(
+            &mut svm,
+            buy_position_ix(&buyer.pubkey(), &positions_market, outcome, amount_in, 0),
+            &buyer,
+        ));
+        assert_eq!(minted, recorded);
+        held[outcome as usize] += minted;
+    }
+
+    let position = load_position(&svm, &positions_market, &buyer.pubkey());
+    assert_eq!(position.market, positions_market);
+    assert_eq!(position.owner, buyer.pubkey());
+    assert_eq!(position.cost_basis[..2], [1_250_000_000, 400_000_000]);
+    assert_eq!(position.last_trade_at, START_TIMESTAMP);
+    for outcome in 0..2u8 {
+        assert_eq!(position.shares[outcome as usize], held[outcome as usize]);
+        assert_eq!(
+            outcome_balance(&svm, &buyer.pubkey(), &tokens_market, outcome),
+            held[outcome as usize]
+        );
+    }
+
+    // Sell half of outcome 0 from each
+    let burned = returned_u64(send_ix(
+        &mut svm,
+        sell_basket_ix(
+            &buyer.pubkey(),
+            &tokens_market,
+            vec![BasketLeg {
+                outcome_index: 0,
+                shares: held[0] / 2,
+            }],
+            0,
+        ),
+        &buyer,
+    ));
+    let debited = returned_u64(send_ix(
+        &mut svm,
+        sell_position_ix(&buyer.pubkey(), &positions_market, 0, held[0] / 2, 0),
+        &buyer,
+    ));
+    assert_eq!(burned, debited);
+    held[0] -= held[0] / 2;
+
+    let (a, b) = (
+        load_market(&svm, &tokens_market),
+        load_market(&svm, &positions_market),
+    );
+    assert_eq!(a.supplies, b.supplies);
+    assert_eq!(a.reserves, b.reserves);
+    assert_eq!(
+        svm.get_balance(&vault(&tokens_market)).unwrap(),
+        svm.get_balance(&vault(&positions_market)).unwrap()
+    );
+
+    // Resolve both to outcome 0 and redeem everything that is left
+    warp_to(&mut svm, market_params("").resolve_at);
+    for market in [&tokens_market, &positions_market] {
+        send_ix(
+            &mut svm,
+            resolve_market_ix(&admin.pubkey(), market, 0),
+            &admin,
+        )
+        .unwrap();
+    }
+    let redeemed = returned_u64(send_ix(
+        &mut svm,
+        redeem_ix(&buyer.pubkey(), &tokens_market, 0, held[0]),
+        &buyer,
+    ));
+    let redeemed_position = returned_u64(send_ix(
+        &mut svm,
+        redeem_position_ix(&buyer.pubkey(), &positions_market, 0, held[0]),
+        &buyer,
+    ));
+    assert_eq!(redeemed, redeemed_position);
+    assert_eq!(
+        load_position(&svm, &positions_market, &buyer.pubkey()).shares[0],
+        0
+    );
+}
+
+#[test]
+fn test_position_and_token_instructions_reject_other_mode() {
+    let (mut svm, admin) = setup();
+    let tokens = market_params("tokens_only");
+    let positions = InitMarketParams {
+        tokenized: false,
+        ..market_params("positions_only")
+    };
+    let tokens_market = market_accounts(&tokens.label, tokens.num_outcomes).market;
+    let positions_market = market_accounts(&positions.label, positions.num_outcomes).market;
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), tokens), &admin).unwrap();
+
+    // An untokenized market can't be created with mints, or get them later
+    assert_error(
+        send_ix(&mut svm, init_market_ix(&admin.pubkey(), positions), &admin),
+        ErrorCode::InvalidMintCount,
+    );
+    send_ix(
+        &mut svm,
+        init_market_without_mints_ix(&admin.pubkey(), positions),
+        &admin,
+    )
+    .unwrap();
+    assert_error(
+        send_ix(
+            &mut svm,
+            init_outcome_mint_ix(&admin.pubkey(), &positions_market, 0),
+            &admin,
+        ),
+        ErrorCode::MarketNotTokenized,
+    );
+
+    let buyer = funded_keypair(&mut svm);
+    assert_error(
+        send_ix(
+            &mut svm,
+            buy_position_ix(&buyer.pubkey(), &tokens_market, 0, 1_000_000_000, 0),
+            &buyer,
+        ),
+        ErrorCode::MarketIsTokenized,
+    );
+    assert_error(
+        send_ix(
+            &mut svm,
+            buy_shares_ix(&buyer.pubkey(), &positions_market, 0, 1_000_000_000, 0),
+            &buyer,
+        ),
+        ErrorCode::OutcomeMintNotInitialized,
+    );
+
+    // A position can't sell more than it holds
+    let shares = returned_u64(send_ix(
+        &mut svm,
+        buy_position_ix(&buyer.pubkey(), &positions_market, 1, 1_000_000_000, 0),
+        &buyer,
+    ));
+    assert_error(
+        send_ix(
+            &mut svm,
+            sell_position_ix(&buyer.pubkey(), &positions_market, 1, shares + 1, 0),
+            &buyer,
+        ),
+        ErrorCode::InsufficientPositionShares,
+    );
+}
+
+#[test]
+fn test_tokenized_trades_track_the_signers_position() {
+    let (mut svm, admin) = setup();
+    let params = market_params("tracked");
+    let market = market_accounts(&params.label, params.num_outcomes).market;
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+
+    // Buys that pass the position record the shares and what they cost
+    let buyer = funded_keypair(&mut svm);
+    let mut tracked = 0;
+    for amount_in in [1_000_000_000, 500_000_000] {
+        tracked += returned_u64(send_ix(
+            &mut svm,
+            buy_shares_tracked_ix(&buyer.pubkey(), &market, 0, amount_in, 0),
+            &buyer,
+        ));
+    }
+    let position = load_position(&svm, &market, &buyer.pubkey());
+    assert_eq!((position.market, position.owner), (market, buyer.pubkey()));
+    assert_eq!(position.shares[0], tracked);
+    assert_eq!(position.cost_basis[0], 1_500_000_000);
+    assert_eq!(outcome_balance(&svm, &buyer.pubkey(), &market, 0), tracked);
+
+    // One that leaves it out doesn't
+    let untracked = returned_u64(send_ix(
+        &mut svm,
+        buy_shares_ix(&buyer.pubkey(), &market, 0, 250_000_000, 0),
+        &buyer,
+    ));
+    assert_eq!(
+        load_position(&svm, &market, &buyer.pubkey()).shares[0],
+        tracked
+    );
+
+    // Selling a third takes a third of the basis off
+    send_ix(
+        &mut svm,
+        sell_shares_ix(&buyer.pubkey(), &market, 0, tracked / 3, 0, true),
+        &buyer,
+    )
+    .unwrap();
+    let position = load_position(&svm, &market, &buyer.pubkey());
+    assert_eq!(position.shares[0], tracked - tracked / 3);
+    assert_eq!(
+        position.cost_basis[0],
+        1_500_000_000 - (1_500_000_000 * (tracked / 3) as u128 / tracked as u128) as u64
+    );
+
+    // Selling more than was tracked empties the position rather than failing
+    send_ix(
+        &mut svm,
+        sell_shares_ix(
+            &buyer.pubkey(),
+            &market,
+            0,
+            tracked - tracked / 3 + untracked,
+            0,
+            true,
+        ),
+        &buyer,
+    )
+    .unwrap();
+    let position = load_position(&svm, &market, &buyer.pubkey());
+    assert_eq!((position.shares[0], position.cost_basis[0]), (0, 0));
+    assert_eq!(outcome_balance(&svm, &buyer.pubkey(), &market, 0), 0);
+}
@@ -0,0 +1,313 @@
+mod utils;
+
+use anchor_lang::AnchorSerialize;
+use anchor_spl::token::spl_token;
+use common::constants::{CONSENSUS_DWELL_SECONDS, VOID_OUTCOME};
+use common::errors::ErrorCode;
+use lmsr::types::{BasketLeg, InitMarketParams, MarketResolution, OracleReport};
+use solana_program::program_pack::Pack;
+use solana_sdk::{account::Account, pubkey::Pubkey, signer::Signer};
+use spl_token::solana_program;
+use utils::*;
+
+#[test]
+fn test_resolve_to_zero_supply_outcome_refunds_buyers() {
+    let (mut svm, admin) = setup();
+    let params = market_params("refund_mode");
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+
+    // Both buyers back outcome 0, outcome 1 is never bought
+    let buyer_a = funded_keypair(&mut svm);
+    let buyer_b = funded_keypair(&mut svm);
+    for (buyer, amount_in) in [(&buyer_a, 1_000_000_000), (&buyer_b, 500_000_000)] {
+        send_ix(
+            &mut svm,
+            buy_shares_ix(&buyer.pubkey(), &accounts.market, 0, amount_in, 0),
+            buyer,
+        )
+        .unwrap();
+    }
+
+    // Not resolvable before resolve_at
+    assert_error(
+        send_ix(
+            &mut svm,
+            resolve_market_ix(&admin.pubkey(), &accounts.market, 1),
+            &admin,
+        ),
+        ErrorCode::MarketNotReadyToResolve,
+    );
+
+    warp_to(&mut svm, params.resolve_at);
+    send_ix(
+        &mut svm,
+        resolve_market_ix(&admin.pubkey(), &accounts.market, 1),
+        &admin,
+    )
+    .unwrap();
+
+    let market = load_market(&svm, &accounts.market);
+    assert_eq!(market.resolved, 1);
+    assert_eq!(market.refund_mode, 1);
+
+    // Outcome 0's deposits are returned pro rata to its shares, minus rounding
+    let mut refunded = 0;
+    for buyer in [&buyer_a, &buyer_b] {
+        let shares = outcome_balance(&svm, &buyer.pubkey(), &accounts.market, 0);
+        let before = svm.get_balance(&buyer.pubkey()).unwrap();
+        send_ix(
+            &mut svm,
+            redeem_ix(&buyer.pubkey(), &accounts.market, 0, shares),
+            buyer,
+        )
+        .unwrap();
+        // Each redeem pays a 5000 lamport signature fee
+        refunded += svm.get_balance(&buyer.pubkey()).unwrap() + 5_000 - before;
+        assert_eq!(
+            outcome_balance(&svm, &buyer.pubkey(), &accounts.market, 0),
+            0
+        );
+    }
+    assert!(1_500_000_000 - refunded <= 1, "refunded {}", refunded);
+
+    let market = load_market(&svm, &accounts.market);
+    assert_eq!(market.supplies[0], 0);
+}
+
+/// Voiding refunds every outcome, each holder getting back what they paid into theirs
+#[test]
+fn test_void_refunds_every_holder() {
+    let (mut svm, admin) = setup();
+    let params = market_params("voided");
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+
+    let buyers = [
+        (funded_keypair(&mut svm), 0u8, 1_000_000_000u64),
+        (funded_keypair(&mut svm), 1, 700_000_000),
+    ];
+    for (buyer, outcome, amount_in) in &buyers {
+        send_ix(
+            &mut svm,
+            buy_shares_ix(&buyer.pubkey(), &accounts.market, *outcome, *amount_in, 0),
+            buyer,
+        )
+        .unwrap();
+    }
+
+    warp_to(&mut svm, params.resolve_at);
+    send_ix(
+        &mut svm,
+        resolve_market_ix(&admin.pubkey(), &accounts.market, VOID_OUTCOME),
+        &admin,
+    )
+    .unwrap();
+    let market = load_market(&svm, &accounts.market);
+    assert_eq!(market.resolution(), Some(MarketResolution::Void));
+    assert_eq!(market.refund_mode, 1);
+
+    for (buyer, outcome, amount_in) in &buyers {
+        let shares = outcome_balance(&svm, &buyer.pubkey(), &accounts.market, *outcome);
+        let before = svm.get_balance(&buyer.pubkey()).unwrap();
+        send_ix(
+            &mut svm,
+            redeem_ix(&buyer.pubkey(), &accounts.market, *outcome, shares),
+            buyer,
+        )
+        .unwrap();
+        // Each redeem pays a 5000 lamport signature fee
+        let refunded = svm.get_balance(&buyer.pubkey()).unwrap() + 5_000 - before;
+        assert_eq!(refunded, *amount_in);
+    }
+}
+
+/// Each winning redemption is paid against `supplies[w]`, which the mint supply matches unit for unit
+#[test]
+fn test_redeem_keeps_mint_supply_equal_to_market_supply() {
+    let (mut svm, admin) = setup();
+    let params = market_params("mint_supply");
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+
+    let buyers = [funded_keypair(&mut svm), funded_keypair(&mut svm)];
+    for (outcome, buyer) in buyers.iter().enumerate() {
+        send_ix(
+            &mut svm,
+            buy_shares_ix(
+                &buyer.pubkey(),
+                &accounts.market,
+                outcome as u8,
+                1_000_000_000,
+                0,
+            ),
+            buyer,
+        )
+        .unwrap();
+    }
+
+    warp_to(&mut svm, params.resolve_at);
+    send_ix(
+        &mut svm,
+        resolve_market_ix(&admin.pubkey(), &accounts.market, 0),
+        &admin,
+    )
+    .unwrap();
+
+    let mint_supply = |svm: &litesvm::LiteSVM| {
+        let mint = svm.get_account(&accounts.outcome_mints[0]).unwrap();
+        spl_token::state::Mint::unpack(&mint.data).unwrap().supply
+    };
+    let market = load_market(&svm, &accounts.market);
+    assert_eq!(market.refund_mode, 0);
+    assert_eq!(mint_supply(&svm), market.supplies[0]);
+
+    // Redeem half, then the rest; the burn and the market's supply move together
+    let shares = outcome_balance(&svm, &buyers[0].pubkey(), &accounts.market, 0);
+    for amount in [shares / 2, shares - shares / 2] {
+        send_ix(
+            &mut svm,
+            redeem_ix(&buyers[0].pubkey(), &accounts.market, 0, amount),
+            &buyers[0],
+        )
+        .unwrap();
+        let market = load_market(&svm, &accounts.market);
+        assert_eq!(mint_supply(&svm), market.supplies[0]);
+    }
+    assert_eq!(load_market(&svm, &accounts.market).supplies[0], 0);
+}
+
+#[test]
+fn test_resolve_from_mock_oracle() {
+    let (mut svm, admin) = setup();
+    let oracle = Pubkey::new_unique();
+    let params = InitMarketParams {
+        oracle: Some(oracle),
+        ..market_params("oracle_market")
+    };
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+
+    let buyer = funded_keypair(&mut svm);
+    send_ix(
+        &mut svm,
+        buy_shares_ix(&buyer.pubkey(), &accounts.market, 1, 1_000_000_000, 0),
+        &buyer,
+    )
+    .unwrap();
+
+    // Mock oracle account reporting outcome 1, owned by some other program
+    let data = OracleReport { winning_outcome: 1 }.try_to_vec().unwrap();
+    svm.set_account(
+        oracle,
+        Account {
+            lamports: svm.minimum_balance_for_rent_exemption(data.len()),
+            data: data.clone(),
+            owner: Pubkey::new_unique(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    )
+    .unwrap();
+
+    // An impostor account with the same data is rejected
+    let impostor = Pubkey::new_unique();
+    svm.set_account(
+        impostor,
+        Account {
+            lamports: svm.minimum_balance_for_rent_exemption(data.len()),
+            data,
+            owner: Pubkey::new_unique(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    )
+    .unwrap();
+
+    warp_to(&mut svm, params.resolve_at);
+    assert_error(
+        send_ix(
+            &mut svm,
+            resolve_from_oracle_ix(&accounts.market, &impostor),
+            &buyer,
+        ),
+        ErrorCode::InvalidOracle,
+    );
+
+    // Anyone can crank the resolution from the committed oracle
+    send_ix(
+        &mut svm,
+        resolve_from_oracle_ix(&accounts.market, &oracle),
+        &buyer,
+    )
+    .unwrap();
+
+    let market = load_market(&svm, &accounts.market);
+    assert_eq!(market.resolved, 1);
+    assert_eq!(market.winning_outcome, 1);
+    assert_eq!(market.refund_mode, 0);
+}
+
+#[test]
+fn test_resolve_by_consensus_ignores_momentary_spike() {
+    let (mut svm, admin) = setup();
+    let params = InitMarketParams {
+        resolve_at: START_TIMESTAMP + 10 * CONSENSUS_DWELL_SECONDS,
+        ..market_params("consensus")
+    };
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+    let cranker = funded_keypair(&mut svm);
+    let crank = resolve_by_consensus_ix(&accounts.market);
+
+    // Flash buy over 95%, crank, and sell back
+    let whale = funded_keypair(&mut svm);
+    send_ix(
+        &mut svm,
+        buy_shares_ix(&whale.pubkey(), &accounts.market, 0, 2_500_000_000, 0),
+        &whale,
+    )
+    .unwrap();
+    assert_error(
+        send_ix(&mut svm, crank.clone(), &cranker),
+        ErrorCode::MarketNotReadyToResolve,
+    );
+    let spike = outcome_balance(&svm, &whale.pubkey(), &accounts.market, 0);
+    send_ix(
+        &mut svm,
+        sell_basket_ix(
+            &whale.pubkey(),
+            &accounts.market,
+            vec![BasketLeg {
+                outcome_index: 0,
+                shares: spike,
+            }],
+            0,
+        ),
+        &whale,
+    )
+    .unwrap();
+
+    // A consensus formed half a dwell before resolve_at; the earlier spike doesn't count toward it
+    let decided_at = params.resolve_at - CONSENSUS_DWELL_SECONDS / 2;
+    warp_to(&mut svm, decided_at);
+    let buyer = funded_keypair(&mut svm);
+    send_ix(
+        &mut svm,
+        buy_shares_ix(&buyer.pubkey(), &accounts.market, 1, 2_500_000_000, 0),
+        &buyer,
+    )
+    .unwrap();
+    warp_to(&mut svm, params.resolve_at);
+    assert_error(
+        send_ix(&mut svm, crank.clone(), &cranker),
+        ErrorCode::ConsensusDwellNotElapsed,
+    );
+
+    // Held for the whole dwell time it resolves
+    warp_to(&mut svm, decided_at + CONSENSUS_DWELL_SECONDS);
+    send_ix(&mut svm, crank, &cranker).unwrap();
+
+    let market = load_market(&svm, &accounts.market);
+    assert_eq!((market.resolved, market.winning_outcome), (1, 1));
+}
@@ -0,0 +1,74 @@
+mod utils;
+
+use common::errors::ErrorCode;
+use lmsr::types::{BasketLeg, InitMarketParams};
+use solana_sdk::signer::Signer;
+use utils::*;
+
+#[test]
+fn test_sell_basket_exits_three_outcomes_at_once() {
+    let (mut svm, admin) = setup();
+    let params = InitMarketParams {
+        num_outcomes: 3,
+        ..market_params("sell_basket")
+    };
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+
+    let trader = funded_keypair(&mut svm);
+    let deposits = [1_000_000_000, 500_000_000, 250_000_000];
+    for (i, amount_in) in deposits.iter().enumerate() {
+        send_ix(
+            &mut svm,
+            buy_shares_ix(&trader.pubkey(), &accounts.market, i as u8, *amount_in, 0),
+            &trader,
+        )
+        .unwrap();
+    }
+
+    let legs: Vec<BasketLeg> = (0..3)
+        .map(|i| BasketLeg {
+            outcome_index: i,
+            shares: outcome_balance(&svm, &trader.pubkey(), &accounts.market, i),
+        })
+        .collect();
+    let expected = load_market(&svm, &accounts.market)
+        .sell_basket(&legs, START_TIMESTAMP)
+        .unwrap();
+    let deposited: u64 = deposits.iter().sum();
+    assert!(deposited - expected < 1_000, "{}", expected);
+
+    // The guard applies to the total, not to any one leg
+    assert_error(
+        send_ix(
+            &mut svm,
+            sell_basket_ix(
+                &trader.pubkey(),
+                &accounts.market,
+                legs.clone(),
+                expected + 1,
+            ),
+            &trader,
+        ),
+        ErrorCode::SlippageExceeded,
+    );
+
+    let balance_before = svm.get_balance(&trader.pubkey()).unwrap();
+    send_ix(
+        &mut svm,
+        sell_basket_ix(&trader.pubkey(), &accounts.market, legs, expected),
+        &trader,
+    )
+    .unwrap();
+
+    // The payer also covers the fee
+    let received = svm.get_balance(&trader.pubkey()).unwrap() + 5_000 - balance_before;
+    assert_eq!(received, expected);
+    for i in 0..3 {
+        assert_eq!(
+            outcome_balance(&svm, &trader.pubkey(), &accounts.market, i),
+            0
+        );
+    }
+    assert!(load_market(&svm, &accounts.market).is_empty());
+}
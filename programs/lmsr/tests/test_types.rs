@@ -0,0 +1,277 @@
+use common::constants::{
+    MARKET_SEED, MAX_CREATION_FEE, MAX_RESOLVE_HORIZON, MAX_TRADE_FEE_BPS, MAX_TVL_FEE,
+    REFERENCE_TRADE_LAMPORTS,
+};
+use common::errors::ErrorCode;
+use lmsr::state::MAX_EXP_ARG;
+use lmsr::types::{FeeConfig, FixedSizeString, InitMarketParams};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+
+#[test]
+fn test_fixed_size_string_ignores_residual_padding() {
+    let label = FixedSizeString::new("election_2028");
+
+    // Same logical label, but with garbage left behind after the terminating zero byte
+    let mut dirty = FixedSizeString::new("election_2028");
+    dirty.value[64] = 0xff;
+    dirty.value[127] = 0x01;
+    assert_ne!(label.value, dirty.value);
+
+    assert_eq!(label, dirty);
+    assert_eq!(label.trimmed(), b"election_2028");
+    assert_eq!(dirty.trimmed(), b"election_2028");
+    assert_eq!(dirty.normalized().value, label.value);
+
+    let set: HashSet<FixedSizeString> = [label, dirty].into_iter().collect();
+    assert_eq!(set.len(), 1);
+
+    // Both must derive the same market PDA
+    let program_id = lmsr::id();
+    assert_eq!(label.as_bytes(), dirty.as_bytes());
+    assert_eq!(
+        Pubkey::find_program_address(&[MARKET_SEED, &label.as_bytes()], &program_id),
+        Pubkey::find_program_address(&[MARKET_SEED, &dirty.as_bytes()], &program_id),
+    );
+
+    assert_ne!(label, FixedSizeString::new("election_2032"));
+}
+
+#[test]
+fn test_fixed_size_string_full_length() {
+    let input = "x".repeat(200);
+    let label = FixedSizeString::new(&input);
+
+    assert_eq!(label.trimmed().len(), 128);
+    assert_eq!(label, FixedSizeString::new(&input[..128]));
+}
+
+#[test]
+fn test_init_market_params_rejects_fewer_than_two_outcomes() {
+    let params = |num_outcomes| InitMarketParams {
+        num_outcomes,
+        scale: 1_000_000_000,
+        resolve_at: 100,
+        label: FixedSizeString::new("outcome_count"),
+        ..Default::default()
+    };
+
+    for num_outcomes in [0, 1] {
+        assert_eq!(
+            params(num_outcomes).validate(0).unwrap_err(),
+            ErrorCode::NotEnoughOutcomes.into()
+        );
+    }
+    params(2).validate(0).unwrap();
+    params(16).validate(0).unwrap();
+    assert_eq!(
+        params(17).validate(0).unwrap_err(),
+        ErrorCode::TooManyOutcomes.into()
+    );
+}
+
+#[test]
+fn test_init_market_params_rejects_past_resolve_at() {
+    let now = 1_750_000_000;
+    let params = |resolve_at| InitMarketParams {
+        num_outcomes: 2,
+        scale: 1_000_000_000,
+        resolve_at,
+        label: FixedSizeString::new("resolve_time"),
+        ..Default::default()
+    };
+
+    // A duration mistaken for a timestamp is decades in the past
+    for resolve_at in [10, now - 86_400, now] {
+        assert_eq!(
+            params(resolve_at).validate(now).unwrap_err(),
+            ErrorCode::ResolveTimeInPast.into()
+        );
+    }
+    // In the future but shorter than the minimum duration
+    assert_eq!(
+        params(now + 1).validate(now).unwrap_err(),
+        ErrorCode::MarketTooQuick.into()
+    );
+    params(now + 86_400).validate(now).unwrap();
+}
+
+#[test]
+fn test_init_market_params_rejects_implausible_resolve_at() {
+    let now = 1_750_000_000;
+    let params = |resolve_at| InitMarketParams {
+        num_outcomes: 2,
+        scale: 1_000_000_000,
+        resolve_at,
+        label: FixedSizeString::new("resolve_bounds"),
+        ..Default::default()
+    };
+
+    // Zero, negative, and far enough out that `resolve_at - now` math would overflow
+    for resolve_at in [0, -1, i64::MIN, i64::MAX, now + MAX_RESOLVE_HORIZON + 1] {
+        assert_eq!(
+            params(resolve_at).validate(now).unwrap_err(),
+            ErrorCode::InvalidResolveTime.into()
+        );
+    }
+    params(now + MAX_RESOLVE_HORIZON).validate(now).unwrap();
+
+    // At the end of i64 the minimum duration check can't overflow either
+    assert_eq!(
+        params(i64::MAX).validate(i64::MAX - 1).unwrap_err(),
+        ErrorCode::MarketTooQuick.into()
+    );
+}
+
+#[test]
+fn test_init_market_params_bounds_fee_holiday() {
+    let now = 1_750_000_000;
+    let params = |fee_free_until| InitMarketParams {
+        num_outcomes: 2,
+        scale: 1_000_000_000,
+        resolve_at: now + 86_400,
+        label: FixedSizeString::new("fee_holiday"),
+        fee_free_until,
+        ..Default::default()
+    };
+
+    params(now + 86_400).validate(now).unwrap();
+    assert_eq!(
+        params(now + 86_401).validate(now).unwrap_err(),
+        ErrorCode::InvalidFeeHoliday.into()
+    );
+}
+
+#[test]
+fn test_init_market_params_bounds_trading_halt() {
+    let now = 1_750_000_000;
+    let params = |trading_halt_before_resolve| InitMarketParams {
+        num_outcomes: 2,
+        scale: 1_000_000_000,
+        resolve_at: now + 86_400,
+        label: FixedSizeString::new("trading_halt"),
+        trading_halt_before_resolve,
+        ..Default::default()
+    };
+
+    params(0).validate(now).unwrap();
+    params(86_399).validate(now).unwrap();
+    // Negative, no trading time left, and far enough to overflow `resolve_at - halt`
+    for halt in [-1, 86_400, i64::MIN, i64::MAX] {
+        assert_eq!(
+            params(halt).validate(now).unwrap_err(),
+            ErrorCode::InvalidTradingWindow.into()
+        );
+    }
+}
+
+#[test]
+fn test_init_market_params_bounds_outcome_decimals() {
+    let now = 1_750_000_000;
+    let params = |outcome_decimals| InitMarketParams {
+        num_outcomes: 2,
+        scale: 1_000_000_000,
+        resolve_at: now + 86_400,
+        label: FixedSizeString::new("outcome_decimals"),
+        outcome_decimals,
+        ..Default::default()
+    };
+
+    for decimals in [0, 3, 6, 9] {
+        params(decimals).validate(now).unwrap();
+    }
+    // The reference trade buys about 0.02 shares, less than one token at 1 decimal
+    assert_eq!(
+        params(1).validate(now).unwrap_err(),
+        ErrorCode::ScaleTooSmall.into()
+    );
+    for decimals in [10, u8::MAX] {
+        assert_eq!(
+            params(decimals).validate(now).unwrap_err(),
+            ErrorCode::InvalidOutcomeDecimals.into()
+        );
+    }
+}
+
+#[test]
+fn test_init_market_params_rejects_unusable_scale() {
+    let params = |scale, high_precision| InitMarketParams {
+        num_outcomes: 2,
+        scale,
+        resolve_at: 100,
+        label: FixedSizeString::new("scale"),
+        high_precision,
+        ..Default::default()
+    };
+
+    for high_precision in [false, true] {
+        assert_eq!(
+            params(0, high_precision).validate(0).unwrap_err(),
+            ErrorCode::LiquidityParameterIsZero.into()
+        );
+        // The reference trade alone would take the supply past MAX_EXP_ARG * b
+        for scale in [1, 100_000, REFERENCE_TRADE_LAMPORTS / MAX_EXP_ARG] {
+            assert_eq!(
+                params(scale, high_precision).validate(0).unwrap_err(),
+                ErrorCode::ScaleTooSmall.into()
+            );
+        }
+        params(REFERENCE_TRADE_LAMPORTS / (MAX_EXP_ARG - 1), high_precision)
+            .validate(0)
+            .unwrap();
+        params(1_000_000_000, high_precision).validate(0).unwrap();
+    }
+}
+
+#[test]
+fn test_init_market_params_rejects_fees_above_caps() {
+    let params = |fee_config| InitMarketParams {
+        num_outcomes: 2,
+        scale: 1_000_000_000,
+        resolve_at: 100,
+        label: FixedSizeString::new("fees"),
+        fee_config,
+        ..Default::default()
+    };
+    let at_caps = FeeConfig {
+        buy_bps: MAX_TRADE_FEE_BPS,
+        sell_bps: MAX_TRADE_FEE_BPS,
+        creation_fee: MAX_CREATION_FEE,
+        tvl_fee: MAX_TVL_FEE as u64,
+        ..Default::default()
+    };
+    params(at_caps).validate(0).unwrap();
+
+    for (fee_config, error) in [
+        (
+            FeeConfig {
+                buy_bps: MAX_TRADE_FEE_BPS + 1,
+                ..at_caps
+            },
+            ErrorCode::TradeFeeTooHigh,
+        ),
+        (
+            FeeConfig {
+                sell_bps: MAX_TRADE_FEE_BPS + 1,
+                ..at_caps
+            },
+            ErrorCode::TradeFeeTooHigh,
+        ),
+        (
+            FeeConfig {
+                creation_fee: MAX_CREATION_FEE + 1,
+                ..at_caps
+            },
+            ErrorCode::CreationFeeTooHigh,
+        ),
+        (
+            FeeConfig {
+                tvl_fee: MAX_TVL_FEE as u64 + 1,
+                ..at_caps
+            },
+            ErrorCode::InvalidTvlFee,
+        ),
+    ] {
+        assert_eq!(params(fee_config).validate(0).unwrap_err(), error.into());
+    }
+}
@@ -0,0 +1,71 @@
+mod utils;
+
+use common::errors::ErrorCode;
+use lmsr::types::{FeeConfig, InitMarketParams};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signer::Signer;
+use utils::*;
+
+#[test]
+fn test_withdraw_fees_leaves_rounding_surplus() {
+    let (mut svm, admin) = setup();
+    let params = InitMarketParams {
+        fee_config: FeeConfig {
+            buy_bps: 50,
+            creation_fee: 10_000_000,
+            ..Default::default()
+        },
+        ..market_params("withdraw_fees")
+    };
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+    send_ix(&mut svm, init_market_ix(&admin.pubkey(), params), &admin).unwrap();
+
+    let buyer = funded_keypair(&mut svm);
+    send_ix(
+        &mut svm,
+        buy_shares_ix(&buyer.pubkey(), &accounts.market, 0, 1_000_000_000, 0),
+        &buyer,
+    )
+    .unwrap();
+    let before = load_market(&svm, &accounts.market);
+    assert_eq!(before.accrued_protocol_fees, 10_000_000 + 5_000_000);
+
+    // Only the admin can withdraw, to any recipient
+    let recipient = Pubkey::new_unique();
+    assert!(send_ix(
+        &mut svm,
+        withdraw_fees_ix(&buyer.pubkey(), &accounts.market, &recipient),
+        &buyer,
+    )
+    .is_err());
+
+    let vault_before = svm.get_balance(&accounts.market_vault).unwrap();
+    send_ix(
+        &mut svm,
+        withdraw_fees_ix(&admin.pubkey(), &accounts.market, &recipient),
+        &admin,
+    )
+    .unwrap();
+    assert_eq!(svm.get_balance(&recipient).unwrap(), 15_000_000);
+    assert_eq!(
+        vault_before - svm.get_balance(&accounts.market_vault).unwrap(),
+        15_000_000
+    );
+
+    // The rounding surplus, and every trader's claim, are untouched
+    let after = load_market(&svm, &accounts.market);
+    assert_eq!(after.accrued_protocol_fees, 0);
+    assert_eq!(
+        after.accrued_rounding_surplus,
+        before.accrued_rounding_surplus
+    );
+    assert_eq!(after.reserves, before.reserves);
+    assert_error(
+        send_ix(
+            &mut svm,
+            withdraw_fees_ix(&admin.pubkey(), &accounts.market, &recipient),
+            &admin,
+        ),
+        ErrorCode::NothingToWithdraw,
+    );
+}
@@ -0,0 +1,818 @@
+//! Shared litesvm helpers for the integration tests.
+#![allow(dead_code, clippy::result_large_err)]
+
+use anchor_lang::AccountDeserialize;
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use common::errors::ErrorCode;
+use common::utils::outcome_mints;
+use litesvm::{types::TransactionResult, LiteSVM};
+use lmsr::state::{BettorPosition, Market, Registry, RegistryPage};
+use lmsr::types::{BasketLeg, FixedSizeString, InitMarketParams};
+use solana_program::program_pack::Pack;
+use spl_token::solana_program;
+use {
+    anchor_lang::{
+        prelude::AccountMeta, solana_program::instruction::Instruction, system_program,
+        InstructionData, ToAccountMetas,
+    },
+    common::constants::{MARKET_SEED, OUTCOME_MINT_SEED, POSITION_SEED, REGISTRY_SEED, VAULT_SEED},
+    solana_sdk::{
+        clock::Clock,
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        signer::keypair::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    },
+};
+
+pub const AIRDROP_LAMPORTS: u64 = 100_000_000_000;
+
+/// Unix timestamp the [`setup`] clock starts at. `resolve_at` is compared against the real
+/// clock, so tests must use timestamps, not durations.
+pub const START_TIMESTAMP: i64 = 1_750_000_000;
+
+/// Create a [`LiteSVM`] with the lmsr program loaded and a funded admin.
+pub fn setup() -> (LiteSVM, Keypair) {
+    let mut svm = LiteSVM::new();
+    let bytes = include_bytes!("../../../../target/deploy/lmsr.so");
+    svm.add_program(lmsr::id(), bytes);
+
+    warp_to(&mut svm, START_TIMESTAMP);
+
+    let admin = Keypair::new();
+    svm.airdrop(&admin.pubkey(), AIRDROP_LAMPORTS).unwrap();
+
+    (svm, admin)
+}
+
+/// PDAs for a [`Market`] derived from its label.
+pub struct MarketAccounts {
+    pub market: Pubkey,
+    pub market_vault: Pubkey,
+    pub outcome_mints: Vec<Pubkey>,
+}
+
+pub fn market_accounts(label: &FixedSizeString, num_outcomes: u8) -> MarketAccounts {
+    let program_id = lmsr::id();
+    let market = Pubkey::find_program_address(&[MARKET_SEED, &label.as_bytes()], &program_id).0;
+    let market_vault = Pubkey::find_program_address(&[VAULT_SEED, market.as_ref()], &program_id).0;
+    let outcome_mints = outcome_mints(&program_id, &market, num_outcomes);
+
+    MarketAccounts {
+        market,
+        market_vault,
+        outcome_mints,
+    }
+}
+
+/// Default parameters for a tokenized binary market with b = 1 SOL.
+pub fn market_params(label: &str) -> InitMarketParams {
+    InitMarketParams {
+        num_outcomes: 2,
+        scale: 1_000_000_000,
+        resolve_at: START_TIMESTAMP + 10,
+        label: FixedSizeString::new(label),
+        tokenized: true,
+        ..Default::default()
+    }
+}
+
+pub fn init_market_ix(admin: &Pubkey, params: InitMarketParams) -> Instruction {
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+
+    let mut accounts_ctx = lmsr::accounts::InitMarket {
+        system_program: system_program::ID,
+        rent: anchor_lang::solana_program::sysvar::rent::ID,
+        token_program: anchor_spl::token::ID,
+        admin: *admin,
+        market: accounts.market,
+        market_vault: accounts.market_vault,
+        registry: None,
+        registry_page: None,
+    }
+    .to_account_metas(None);
+    for mint in accounts.outcome_mints {
+        accounts_ctx.push(AccountMeta::new(mint, false));
+    }
+
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::InitMarket { params }.data(),
+        accounts_ctx,
+    )
+}
+
+/// `init_market` without any outcome mints, which are then created by [`init_outcome_mint_ix`].
+pub fn init_market_without_mints_ix(admin: &Pubkey, params: InitMarketParams) -> Instruction {
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::InitMarket { params }.data(),
+        lmsr::accounts::InitMarket {
+            system_program: system_program::ID,
+            rent: anchor_lang::solana_program::sysvar::rent::ID,
+            token_program: anchor_spl::token::ID,
+            admin: *admin,
+            market: accounts.market,
+            market_vault: accounts.market_vault,
+            registry: None,
+            registry_page: None,
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// `init_market` without mints that also lists the market on registry page `page`.
+pub fn init_registered_market_ix(
+    admin: &Pubkey,
+    params: InitMarketParams,
+    page: u32,
+) -> Instruction {
+    let accounts = market_accounts(&params.label, params.num_outcomes);
+
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::InitMarket { params }.data(),
+        lmsr::accounts::InitMarket {
+            system_program: system_program::ID,
+            rent: anchor_lang::solana_program::sysvar::rent::ID,
+            token_program: anchor_spl::token::ID,
+            admin: *admin,
+            market: accounts.market,
+            market_vault: accounts.market_vault,
+            registry: Some(registry()),
+            registry_page: Some(registry_page(page)),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn init_registry_ix(payer: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::InitRegistry {}.data(),
+        lmsr::accounts::InitRegistry {
+            system_program: system_program::ID,
+            payer: *payer,
+            registry: registry(),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn init_outcome_mint_ix(admin: &Pubkey, market: &Pubkey, outcome_index: u8) -> Instruction {
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::InitOutcomeMint { outcome_index }.data(),
+        lmsr::accounts::InitOutcomeMint {
+            system_program: system_program::ID,
+            rent: anchor_lang::solana_program::sysvar::rent::ID,
+            token_program: anchor_spl::token::ID,
+            admin: *admin,
+            market: *market,
+            outcome_mint: outcome_mint(market, outcome_index),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn batch_init_markets_ix(admin: &Pubkey, params: Vec<InitMarketParams>) -> Instruction {
+    let mut accounts_ctx = lmsr::accounts::BatchInitMarkets {
+        system_program: system_program::ID,
+        rent: anchor_lang::solana_program::sysvar::rent::ID,
+        token_program: anchor_spl::token::ID,
+        admin: *admin,
+    }
+    .to_account_metas(None);
+    for market_params in params.iter() {
+        let accounts = market_accounts(&market_params.label, market_params.num_outcomes);
+        accounts_ctx.push(AccountMeta::new(accounts.market, false));
+        accounts_ctx.push(AccountMeta::new(accounts.market_vault, false));
+        for mint in accounts.outcome_mints {
+            accounts_ctx.push(AccountMeta::new(mint, false));
+        }
+    }
+
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::BatchInitMarkets { params }.data(),
+        accounts_ctx,
+    )
+}
+
+pub fn get_price_ix(market: &Pubkey, outcome_index: u8) -> Instruction {
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::GetPrice { outcome_index }.data(),
+        lmsr::accounts::GetPrice { market: *market }.to_account_metas(None),
+    )
+}
+
+pub fn get_cost_ix(market: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::GetCost {}.data(),
+        lmsr::accounts::GetCost { market: *market }.to_account_metas(None),
+    )
+}
+
+pub fn quote_cpi_ix(market: &Pubkey, outcome_index: u8, amount_in: u64) -> Instruction {
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::QuoteCpi {
+            outcome_index,
+            amount_in,
+        }
+        .data(),
+        lmsr::accounts::QuoteCpi { market: *market }.to_account_metas(None),
+    )
+}
+
+/// `buy_shares` without a price impact cap.
+pub fn buy_shares_ix(
+    buyer: &Pubkey,
+    market: &Pubkey,
+    outcome_index: u8,
+    amount_in: u64,
+    min_shares_out: u64,
+) -> Instruction {
+    buy_shares_capped_ix(buyer, market, outcome_index, amount_in, min_shares_out, 0)
+}
+
+pub fn buy_shares_capped_ix(
+    buyer: &Pubkey,
+    market: &Pubkey,
+    outcome_index: u8,
+    amount_in: u64,
+    min_shares_out: u64,
+    max_price_impact_bps: u16,
+) -> Instruction {
+    buy_shares_order_ix(
+        buyer,
+        market,
+        outcome_index,
+        amount_in,
+        min_shares_out,
+        max_price_impact_bps,
+        None,
+    )
+}
+
+pub fn buy_shares_order_ix(
+    buyer: &Pubkey,
+    market: &Pubkey,
+    outcome_index: u8,
+    amount_in: u64,
+    min_shares_out: u64,
+    max_price_impact_bps: u16,
+    client_order_id: Option<u64>,
+) -> Instruction {
+    let outcome_mint = outcome_mint(market, outcome_index);
+
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::BuyShares {
+            outcome_index,
+            amount_in,
+            min_shares_out,
+            max_price_impact_bps,
+            client_order_id,
+        }
+        .data(),
+        lmsr::accounts::BuyShares {
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            buyer: *buyer,
+            market: *market,
+            market_vault: vault(market),
+            outcome_mint,
+            buyer_token_account: get_associated_token_address(buyer, &outcome_mint),
+            position: None,
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// `buy_shares` that also records the buy in the buyer's position.
+pub fn buy_shares_tracked_ix(
+    buyer: &Pubkey,
+    market: &Pubkey,
+    outcome_index: u8,
+    amount_in: u64,
+    min_shares_out: u64,
+) -> Instruction {
+    let outcome_mint = outcome_mint(market, outcome_index);
+
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::BuyShares {
+            outcome_index,
+            amount_in,
+            min_shares_out,
+            max_price_impact_bps: 0,
+            client_order_id: None,
+        }
+        .data(),
+        lmsr::accounts::BuyShares {
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            buyer: *buyer,
+            market: *market,
+            market_vault: vault(market),
+            outcome_mint,
+            buyer_token_account: get_associated_token_address(buyer, &outcome_mint),
+            position: Some(position(market, buyer)),
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// `sell_shares`, also taking the shares off the seller's position if `tracked`.
+pub fn sell_shares_ix(
+    seller: &Pubkey,
+    market: &Pubkey,
+    outcome_index: u8,
+    shares: u64,
+    min_lamports_out: u64,
+    tracked: bool,
+) -> Instruction {
+    let outcome_mint = outcome_mint(market, outcome_index);
+
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::SellShares {
+            outcome_index,
+            shares,
+            min_lamports_out,
+        }
+        .data(),
+        lmsr::accounts::SellShares {
+            token_program: anchor_spl::token::ID,
+            seller: *seller,
+            market: *market,
+            market_vault: vault(market),
+            outcome_mint,
+            seller_token_account: get_associated_token_address(seller, &outcome_mint),
+            position: tracked.then(|| position(market, seller)),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn buy_capped_ix(
+    buyer: &Pubkey,
+    market: &Pubkey,
+    outcome_index: u8,
+    amount_in: u64,
+    min_shares_out: u64,
+) -> Instruction {
+    let outcome_mint = outcome_mint(market, outcome_index);
+
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::BuyCapped {
+            outcome_index,
+            amount_in,
+            min_shares_out,
+        }
+        .data(),
+        lmsr::accounts::BuyShares {
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            buyer: *buyer,
+            market: *market,
+            market_vault: vault(market),
+            outcome_mint,
+            buyer_token_account: get_associated_token_address(buyer, &outcome_mint),
+            position: None,
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn buy_exact_shares_ix(
+    buyer: &Pubkey,
+    market: &Pubkey,
+    outcome_index: u8,
+    shares_out: u64,
+    max_amount_in: u64,
+) -> Instruction {
+    let outcome_mint = outcome_mint(market, outcome_index);
+
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::BuyExactShares {
+            outcome_index,
+            shares_out,
+            max_amount_in,
+        }
+        .data(),
+        lmsr::accounts::BuyShares {
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            buyer: *buyer,
+            market: *market,
+            market_vault: vault(market),
+            outcome_mint,
+            buyer_token_account: get_associated_token_address(buyer, &outcome_mint),
+            position: None,
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// `buy_position` without a price impact cap.
+pub fn buy_position_ix(
+    buyer: &Pubkey,
+    market: &Pubkey,
+    outcome_index: u8,
+    amount_in: u64,
+    min_shares_out: u64,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::BuyPosition {
+            outcome_index,
+            amount_in,
+            min_shares_out,
+            max_price_impact_bps: 0,
+        }
+        .data(),
+        lmsr::accounts::BuyPosition {
+            system_program: system_program::ID,
+            buyer: *buyer,
+            market: *market,
+            market_vault: vault(market),
+            position: position(market, buyer),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn sell_position_ix(
+    seller: &Pubkey,
+    market: &Pubkey,
+    outcome_index: u8,
+    shares: u64,
+    min_lamports_out: u64,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::SellPosition {
+            outcome_index,
+            shares,
+            min_lamports_out,
+        }
+        .data(),
+        lmsr::accounts::SellPosition {
+            seller: *seller,
+            market: *market,
+            market_vault: vault(market),
+            position: position(market, seller),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn redeem_position_ix(
+    holder: &Pubkey,
+    market: &Pubkey,
+    outcome_index: u8,
+    shares: u64,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::RedeemPosition {
+            outcome_index,
+            shares,
+        }
+        .data(),
+        lmsr::accounts::RedeemPosition {
+            holder: *holder,
+            market: *market,
+            market_vault: vault(market),
+            position: position(market, holder),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn resolve_market_ix(admin: &Pubkey, market: &Pubkey, winning_outcome: u8) -> Instruction {
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::ResolveMarket { winning_outcome }.data(),
+        lmsr::accounts::ResolveMarket {
+            admin: *admin,
+            market: *market,
+            market_vault: vault(market),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn admin_withdraw_excess_ix(admin: &Pubkey, market: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::AdminWithdrawExcess {}.data(),
+        lmsr::accounts::AdminWithdrawExcess {
+            admin: *admin,
+            market: *market,
+            market_vault: vault(market),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn withdraw_fees_ix(admin: &Pubkey, market: &Pubkey, fee_recipient: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::WithdrawFees {}.data(),
+        lmsr::accounts::WithdrawFees {
+            admin: *admin,
+            market: *market,
+            market_vault: vault(market),
+            fee_recipient: *fee_recipient,
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn resolve_by_consensus_ix(market: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::ResolveByConsensus {}.data(),
+        lmsr::accounts::ResolveByConsensus {
+            market: *market,
+            market_vault: vault(market),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn resolve_from_oracle_ix(market: &Pubkey, oracle: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::ResolveFromOracle {}.data(),
+        lmsr::accounts::ResolveFromOracle {
+            market: *market,
+            market_vault: vault(market),
+            oracle: *oracle,
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn set_trading_window_ix(admin: &Pubkey, market: &Pubkey, trading_open_at: i64) -> Instruction {
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::SetTradingWindow { trading_open_at }.data(),
+        lmsr::accounts::SetTradingWindow {
+            admin: *admin,
+            market: *market,
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn set_fee_holiday_ix(admin: &Pubkey, market: &Pubkey, fee_free_until: i64) -> Instruction {
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::SetFeeHoliday { fee_free_until }.data(),
+        lmsr::accounts::SetFeeHoliday {
+            admin: *admin,
+            market: *market,
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn redeem_ix(holder: &Pubkey, market: &Pubkey, outcome_index: u8, shares: u64) -> Instruction {
+    let outcome_mint = outcome_mint(market, outcome_index);
+
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::Redeem {
+            outcome_index,
+            shares,
+        }
+        .data(),
+        lmsr::accounts::Redeem {
+            token_program: anchor_spl::token::ID,
+            holder: *holder,
+            market: *market,
+            market_vault: vault(market),
+            outcome_mint,
+            holder_token_account: get_associated_token_address(holder, &outcome_mint),
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn flip_ix(
+    trader: &Pubkey,
+    market: &Pubkey,
+    from_outcome: u8,
+    to_outcome: u8,
+    shares: u64,
+    min_target_shares: u64,
+) -> Instruction {
+    let from_mint = outcome_mint(market, from_outcome);
+    let to_mint = outcome_mint(market, to_outcome);
+
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::Flip {
+            from_outcome,
+            to_outcome,
+            shares,
+            min_target_shares,
+        }
+        .data(),
+        lmsr::accounts::Flip {
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            trader: *trader,
+            market: *market,
+            from_mint,
+            from_token_account: get_associated_token_address(trader, &from_mint),
+            to_mint,
+            to_token_account: get_associated_token_address(trader, &to_mint),
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// `sell_basket` with each leg's outcome mint and `seller`'s token account as remaining accounts.
+pub fn sell_basket_ix(
+    seller: &Pubkey,
+    market: &Pubkey,
+    legs: Vec<BasketLeg>,
+    min_total_out: u64,
+) -> Instruction {
+    let mut accounts = lmsr::accounts::SellBasket {
+        token_program: anchor_spl::token::ID,
+        seller: *seller,
+        market: *market,
+        market_vault: vault(market),
+    }
+    .to_account_metas(None);
+    for leg in &legs {
+        let outcome_mint = outcome_mint(market, leg.outcome_index);
+        accounts.push(AccountMeta::new(outcome_mint, false));
+        accounts.push(AccountMeta::new(
+            get_associated_token_address(seller, &outcome_mint),
+            false,
+        ));
+    }
+
+    Instruction::new_with_bytes(
+        lmsr::id(),
+        &lmsr::instruction::SellBasket {
+            legs,
+            min_total_out,
+        }
+        .data(),
+        accounts,
+    )
+}
+
+pub fn vault(market: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[VAULT_SEED, market.as_ref()], &lmsr::id()).0
+}
+
+pub fn outcome_mint(market: &Pubkey, outcome_index: u8) -> Pubkey {
+    Pubkey::find_program_address(
+        &[OUTCOME_MINT_SEED, market.as_ref(), &[outcome_index]],
+        &lmsr::id(),
+    )
+    .0
+}
+
+pub fn position(market: &Pubkey, owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[POSITION_SEED, market.as_ref(), owner.as_ref()],
+        &lmsr::id(),
+    )
+    .0
+}
+
+pub fn registry() -> Pubkey {
+    Pubkey::find_program_address(&[REGISTRY_SEED], &lmsr::id()).0
+}
+
+pub fn registry_page(page: u32) -> Pubkey {
+    Pubkey::find_program_address(&[REGISTRY_SEED, &page.to_le_bytes()], &lmsr::id()).0
+}
+
+/// Token balance of `owner`'s associated token account for an outcome, 0 if it doesn't exist.
+pub fn outcome_balance(svm: &LiteSVM, owner: &Pubkey, market: &Pubkey, outcome_index: u8) -> u64 {
+    let ata = get_associated_token_address(owner, &outcome_mint(market, outcome_index));
+    svm.get_account(&ata)
+        .map(|account| {
+            spl_token::state::Account::unpack(&account.data)
+                .unwrap()
+                .amount
+        })
+        .unwrap_or(0)
+}
+
+/// Create a new funded keypair.
+pub fn funded_keypair(svm: &mut LiteSVM) -> Keypair {
+    let keypair = Keypair::new();
+    svm.airdrop(&keypair.pubkey(), AIRDROP_LAMPORTS).unwrap();
+    keypair
+}
+
+/// Set the clock's unix timestamp.
+pub fn warp_to(svm: &mut LiteSVM, unix_timestamp: i64) {
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp = unix_timestamp;
+    svm.set_sysvar::<Clock>(&clock);
+}
+
+/// Send a single instruction in its own transaction.
+/// The blockhash is expired first so identical instructions can be sent repeatedly.
+pub fn send_ix(svm: &mut LiteSVM, ix: Instruction, payer: &Keypair) -> TransactionResult {
+    svm.expire_blockhash();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer.pubkey()),
+        &[payer],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(tx)
+}
+
+pub fn load_market(svm: &LiteSVM, market: &Pubkey) -> Market {
+    let market_account = svm.get_account(market).unwrap();
+    Market::try_deserialize(&mut market_account.data.as_ref()).unwrap()
+}
+
+pub fn load_position(svm: &LiteSVM, market: &Pubkey, owner: &Pubkey) -> BettorPosition {
+    let position_account = svm.get_account(&position(market, owner)).unwrap();
+    BettorPosition::try_deserialize(&mut position_account.data.as_ref()).unwrap()
+}
+
+pub fn load_registry(svm: &LiteSVM) -> Registry {
+    let registry_account = svm.get_account(&registry()).unwrap();
+    Registry::try_deserialize(&mut registry_account.data.as_ref()).unwrap()
+}
+
+pub fn load_registry_page(svm: &LiteSVM, page: u32) -> RegistryPage {
+    let page_account = svm.get_account(&registry_page(page)).unwrap();
+    RegistryPage::try_deserialize(&mut page_account.data.as_ref()).unwrap()
+}
+
+/// Overwrite the on-chain [`Market`] account data, e.g. to put it in a state that is hard to trade into.
+pub fn store_market(svm: &mut LiteSVM, key: &Pubkey, market: &Market) {
+    let mut account = svm.get_account(key).unwrap();
+    account.data[8..].copy_from_slice(bytemuck::bytes_of(market));
+    svm.set_account(*key, account).unwrap();
+}
+
+/// Every event of type `T` a transaction emitted, decoded from its `Program data:` log lines.
+pub fn events<T: anchor_lang::Event + anchor_lang::AnchorDeserialize>(
+    result: &TransactionResult,
+) -> Vec<T> {
+    use base64::Engine;
+
+    let logs = match result {
+        Ok(meta) => &meta.logs,
+        Err(failed) => &failed.meta.logs,
+    };
+    logs.iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .filter_map(|data| base64::engine::general_purpose::STANDARD.decode(data).ok())
+        .filter(|data| data.starts_with(T::DISCRIMINATOR))
+        .map(|data| T::try_from_slice(&data[T::DISCRIMINATOR.len()..]).unwrap())
+        .collect()
+}
+
+/// Assert the first instruction of a transaction failed with the given program error.
+pub fn assert_error(result: TransactionResult, error: ErrorCode) {
+    let failed = result.expect_err("transaction should have failed");
+    assert_eq!(
+        failed.err,
+        TransactionError::InstructionError(0, InstructionError::Custom(u32::from(error))),
+        "logs: {:#?}",
+        failed.meta.logs
+    );
+}
+
+/// [`assert_error`] for errors raised by Anchor's account constraints
+pub fn assert_anchor_error(result: TransactionResult, error: anchor_lang::error::ErrorCode) {
+    let failed = result.expect_err("transaction should have failed");
+    assert_eq!(
+        failed.err,
+        TransactionError::InstructionError(0, InstructionError::Custom(u32::from(error))),
+        "logs: {:#?}",
+        failed.meta.logs
+    );
+}